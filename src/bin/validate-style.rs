@@ -0,0 +1,39 @@
+// Usage: validate-style <style.json>...
+//
+// Prints one line per problem as `<file>:<json pointer>: <message>` and exits
+// with a non-zero code if any style has problems, so it can gate CI.
+use mapbox_gl_native_rs::mapbox::style_spec::validator;
+
+use std::env;
+use std::fs;
+use std::process;
+
+fn main() {
+    let paths: Vec<String> = env::args().skip(1).collect();
+    if paths.is_empty() {
+        eprintln!("usage: validate-style <style.json>...");
+        process::exit(2);
+    }
+
+    let mut problem_count = 0;
+    for path in paths.iter() {
+        let diagnostics = match fs::read_to_string(path) {
+            Ok(json) => validator::validate_style_json(&json),
+            Err(err) => {
+                println!("{}: {}", path, err);
+                problem_count += 1;
+                continue;
+            }
+        };
+
+        for diagnostic in diagnostics.iter() {
+            println!("{}:{}", path, diagnostic);
+        }
+        problem_count += diagnostics.len();
+    }
+
+    if problem_count > 0 {
+        eprintln!("{} problem(s) found", problem_count);
+        process::exit(1);
+    }
+}
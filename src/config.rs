@@ -1,3 +1,5 @@
+use crate::mapbox::geo::LatLng;
+
 // GL Configs
 pub const GL_VIEWPORT_WIDTH: u32 = 900;
 pub const GL_VIEWPORT_HEIGHT: u32 = 900;
@@ -7,4 +9,4 @@ pub const STYLESHEET_BASE: &str = "REMOVED";
 pub const UBER_AUTH_HEADER: &str = "REMOVED";
 pub const UBER_AUTH_TOKEN: &str = "REMOVED";
 
-pub const MAP_CENTER_LATLONG: (f32, f32) = (37.75, -122.39); // San Francisco
+pub const MAP_CENTER_LATLONG: LatLng = LatLng::new(37.75, -122.39); // San Francisco
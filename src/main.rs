@@ -6,28 +6,30 @@ extern crate gfx_text;
 extern crate gfx_window_glutin;
 extern crate glutin;
 
-mod config;
-mod mapbox;
 mod painter;
 
+use mapbox::input_handler::InputHandler;
+use mapbox_gl_native_rs::{config, mapbox};
 use painter::painter::Painter;
+use painter::window_input;
 use std::cell::RefCell;
 use std::sync::Arc;
 use std::time::{Duration, SystemTime};
 
 fn main() {
     let map_arc = Arc::new(RefCell::new(mapbox::map::Map::new()));
-    map_arc
-        .borrow_mut()
-        .set_center(config::MAP_CENTER_LATLONG.0, config::MAP_CENTER_LATLONG.1);
+    map_arc.borrow_mut().set_center(config::MAP_CENTER_LATLONG);
 
     let mut events_loop = glutin::EventsLoop::new();
     let mut painter = Painter::new(map_arc.clone(), &events_loop);
+    let mut input_handler = InputHandler::new();
 
     // Issue map load after painter is ready. Otherwise it is possible that
     // vector tiles are loaded and parsed before painter is ready and then
     // nothing will show up on map.
-    map_arc.borrow_mut().load_map(config::STYLESHEET_BASE);
+    map_arc
+        .borrow_mut()
+        .load_map(config::STYLESHEET_BASE.to_string());
 
     // Main Event Loop
     let mut ts = SystemTime::now();
@@ -48,7 +50,11 @@ fn main() {
                             },
                         ..
                     } => painter.toggle_show_grid(),
-                    _ => {}
+                    _ => {
+                        if let Some(event) = window_input::to_input_event(&event) {
+                            input_handler.handle_event(&mut map_arc.borrow_mut(), &event);
+                        }
+                    }
                 }
             }
         });
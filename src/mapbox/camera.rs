@@ -0,0 +1,618 @@
+// Ref: https://github.com/mapbox/mapbox-gl-js/blob/main/src/ui/camera.js
+//
+// Animated camera moves. A transition only knows where it started, where it
+// goes and how; Map advances it every frame with the time of its clock.
+use super::geo::LatLng;
+use super::transform::Transform;
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const DEFAULT_EASE_DURATION: Duration = Duration::from_millis(500);
+// Zoom-out curvature and speed of fly_to, as mapbox-gl picks them
+const DEFAULT_FLY_CURVE: f64 = 1.42;
+const DEFAULT_FLY_SPEED: f64 = 1.2;
+
+// Fields left at None keep their current value
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CameraOptions {
+    pub center: Option<LatLng>,
+    pub zoom: Option<f64>,
+    pub bearing: Option<f64>,
+    pub pitch: Option<f64>,
+}
+
+#[derive(Default)]
+pub struct AnimationOptions {
+    // Defaults to 500ms for ease_to. fly_to derives it from speed.
+    pub duration: Option<Duration>,
+    pub easing: Option<UnitBezier>,
+    // fly_to only: how far it zooms out on the way, and screenfuls per second
+    pub curve: Option<f64>,
+    pub speed: Option<f64>,
+    // Called once the camera has arrived
+    pub on_complete: Option<Box<dyn FnOnce()>>,
+    // Called when another camera change interrupts the transition
+    pub on_cancel: Option<Box<dyn FnOnce()>>,
+}
+
+// Frame clock transitions are timed with. Inject a ManualClock to step
+// animations deterministically.
+pub trait Clock {
+    fn now(&self) -> Duration;
+}
+
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> SystemClock {
+        SystemClock {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> SystemClock {
+        SystemClock::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+// Only moves when told to. Clones share their time.
+#[derive(Debug, Clone, Default)]
+pub struct ManualClock {
+    now: Arc<Mutex<Duration>>,
+}
+
+impl ManualClock {
+    pub fn new() -> ManualClock {
+        ManualClock::default()
+    }
+
+    pub fn advance(&self, delta: Duration) {
+        *self.now.lock().unwrap() += delta;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        *self.now.lock().unwrap()
+    }
+}
+
+// Ref: https://github.com/mapbox/unitbezier
+//
+// CSS style cubic-bezier easing from (0, 0) to (1, 1)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UnitBezier {
+    cx: f64,
+    bx: f64,
+    ax: f64,
+    cy: f64,
+    by: f64,
+    ay: f64,
+}
+
+impl UnitBezier {
+    pub fn new(p1x: f64, p1y: f64, p2x: f64, p2y: f64) -> UnitBezier {
+        let cx = 3.0 * p1x;
+        let bx = 3.0 * (p2x - p1x) - cx;
+        let cy = 3.0 * p1y;
+        let by = 3.0 * (p2y - p1y) - cy;
+        UnitBezier {
+            cx,
+            bx,
+            ax: 1.0 - cx - bx,
+            cy,
+            by,
+            ay: 1.0 - cy - by,
+        }
+    }
+
+    // "ease"
+    pub fn ease() -> UnitBezier {
+        UnitBezier::new(0.25, 0.1, 0.25, 1.0)
+    }
+
+    pub fn linear() -> UnitBezier {
+        UnitBezier::new(0.0, 0.0, 1.0, 1.0)
+    }
+
+    fn sample_x(&self, t: f64) -> f64 {
+        ((self.ax * t + self.bx) * t + self.cx) * t
+    }
+
+    fn sample_y(&self, t: f64) -> f64 {
+        ((self.ay * t + self.by) * t + self.cy) * t
+    }
+
+    fn sample_derivative_x(&self, t: f64) -> f64 {
+        (3.0 * self.ax * t + 2.0 * self.bx) * t + self.cx
+    }
+
+    // Curve parameter for `x`, Newton's method first, bisection if that
+    // doesn't converge
+    fn solve_x(&self, x: f64) -> f64 {
+        const EPSILON: f64 = 1e-6;
+
+        let mut t = x;
+        for _ in 0..8 {
+            let error = self.sample_x(t) - x;
+            if error.abs() < EPSILON {
+                return t;
+            }
+            let derivative = self.sample_derivative_x(t);
+            if derivative.abs() < EPSILON {
+                break;
+            }
+            t -= error / derivative;
+        }
+
+        let (mut low, mut high) = (0.0, 1.0);
+        t = x;
+        while low < high {
+            let sample = self.sample_x(t);
+            if (sample - x).abs() < EPSILON {
+                return t;
+            }
+            if x > sample {
+                low = t;
+            } else {
+                high = t;
+            }
+            t = (high - low) / 2.0 + low;
+            if high - low < EPSILON {
+                break;
+            }
+        }
+        t
+    }
+
+    // Eased progress for a linear progress `x` in [0, 1]
+    pub fn solve(&self, x: f64) -> f64 {
+        self.sample_y(self.solve_x(x.clamp(0.0, 1.0)))
+    }
+}
+
+enum Path {
+    Ease,
+    // Ref: van Wijk, Nuij - Smooth and efficient zooming and panning (2003)
+    Fly {
+        rho: f64,
+        r0: f64,
+        w0: f64,
+        u1: f64,
+        length: f64, // S, the length of the path
+        // Pure zoom without panning: w(s) = exp(zoom_direction * rho * s)
+        zoom_direction: Option<f64>,
+    },
+}
+
+pub struct CameraTransition {
+    start_time: Duration,
+    duration: Duration,
+    easing: UnitBezier,
+    from: Transform,
+    to: CameraOptions, // Every field set
+    path: Path,
+    on_complete: Option<Box<dyn FnOnce()>>,
+    on_cancel: Option<Box<dyn FnOnce()>>,
+}
+
+impl CameraTransition {
+    // Interpolates every camera property from `from` to `to` at once
+    pub fn ease(
+        from: &Transform,
+        to: &CameraOptions,
+        options: AnimationOptions,
+        now: Duration,
+    ) -> CameraTransition {
+        let duration = options.duration.unwrap_or(DEFAULT_EASE_DURATION);
+        CameraTransition::new(from, to, options, now, duration, Path::Ease)
+    }
+
+    // Zooms out, pans and zooms back in, along the path which feels shortest
+    pub fn fly(
+        from: &Transform,
+        to: &CameraOptions,
+        options: AnimationOptions,
+        now: Duration,
+    ) -> CameraTransition {
+        let target = complete(from, to);
+        let rho = options.curve.unwrap_or(DEFAULT_FLY_CURVE);
+        let speed = options.speed.unwrap_or(DEFAULT_FLY_SPEED);
+        let rho2 = rho * rho;
+
+        let zoom_scale = 2f64.powf(target.zoom.unwrap() - from.get_zoom());
+        let (width, height) = from.get_size();
+        let w0 = width.max(height) as f64;
+        let w1 = w0 / zoom_scale;
+
+        // Distance to travel, in pixels at the starting zoom
+        let p0 = from.project(&from.get_center());
+        let p1 = from.project(&target.center.unwrap());
+        let u1 = ((p1.0 - p0.0).powi(2) + (p1.1 - p0.1).powi(2)).sqrt();
+
+        let r = |is_end: bool| {
+            let (w, sign) = if is_end { (w1, -1.0) } else { (w0, 1.0) };
+            let b = (w1 * w1 - w0 * w0 + sign * rho2 * rho2 * u1 * u1) / (2.0 * w * rho2 * u1);
+            ((b * b + 1.0).sqrt() - b).ln()
+        };
+        let r0 = r(false);
+        let mut length = (r(true) - r0) / rho;
+        let mut zoom_direction = None;
+
+        if u1.abs() < 1e-6 || !length.is_finite() {
+            // Nothing to pan, only zoom
+            if (w0 - w1).abs() < 1e-6 {
+                return CameraTransition::ease(from, &target, options, now);
+            }
+            zoom_direction = Some(if w1 < w0 { -1.0 } else { 1.0 });
+            length = (w1 / w0).ln().abs() / rho;
+        }
+
+        let duration = options
+            .duration
+            .unwrap_or_else(|| Duration::from_secs_f64(length / speed));
+        let path = Path::Fly {
+            rho,
+            r0,
+            w0,
+            u1,
+            length,
+            zoom_direction,
+        };
+        CameraTransition::new(from, &target, options, now, duration, path)
+    }
+
+    fn new(
+        from: &Transform,
+        to: &CameraOptions,
+        options: AnimationOptions,
+        now: Duration,
+        duration: Duration,
+        path: Path,
+    ) -> CameraTransition {
+        CameraTransition {
+            start_time: now,
+            duration,
+            easing: options.easing.unwrap_or_else(UnitBezier::ease),
+            from: *from,
+            to: complete(from, to),
+            path,
+            on_complete: options.on_complete,
+            on_cancel: options.on_cancel,
+        }
+    }
+
+    pub fn is_done(&self, now: Duration) -> bool {
+        now >= self.start_time + self.duration
+    }
+
+    // Camera at `now`, the target itself once the time is up
+    pub fn frame(&self, now: Duration) -> CameraOptions {
+        if self.is_done(now) {
+            return self.to;
+        }
+        let elapsed = now.saturating_sub(self.start_time).as_secs_f64();
+        let k = self.easing.solve(elapsed / self.duration.as_secs_f64());
+
+        let (center0, center1) = (self.from.get_center(), self.to.center.unwrap());
+        let (zoom0, zoom1) = (self.from.get_zoom(), self.to.zoom.unwrap());
+
+        // Share of the way the center has come, and the zoom there
+        let (progress, zoom) = match self.path {
+            Path::Ease => (k, interpolate(zoom0, zoom1, k)),
+            Path::Fly {
+                rho,
+                r0,
+                w0,
+                u1,
+                length,
+                zoom_direction,
+            } => {
+                let s = k * length;
+                let (w, u) = match zoom_direction {
+                    Some(direction) => ((direction * rho * s).exp(), 0.0),
+                    None => {
+                        let w = r0.cosh() / (r0 + rho * s).cosh();
+                        let u = w0
+                            * ((r0.cosh() * (r0 + rho * s).tanh() - r0.sinh()) / (rho * rho))
+                            / u1;
+                        (w, u)
+                    }
+                };
+                (u, zoom0 + (1.0 / w).log2())
+            }
+        };
+
+        CameraOptions {
+            center: Some(LatLng::new(
+                interpolate(center0.lat, center1.lat, progress),
+                interpolate(center0.long, center1.long, progress),
+            )),
+            zoom: Some(zoom),
+            bearing: Some(interpolate(
+                self.from.get_bearing(),
+                self.to.bearing.unwrap(),
+                k,
+            )),
+            pitch: Some(interpolate(
+                self.from.get_pitch(),
+                self.to.pitch.unwrap(),
+                k,
+            )),
+        }
+    }
+
+    pub fn finish(mut self) {
+        if let Some(on_complete) = self.on_complete.take() {
+            on_complete();
+        }
+    }
+
+    pub fn cancel(mut self) {
+        if let Some(on_cancel) = self.on_cancel.take() {
+            on_cancel();
+        }
+    }
+}
+
+// Fills the unset fields of `to` from the current camera. Longitude and
+// bearing are moved by whole turns so that the camera takes the short way.
+fn complete(from: &Transform, to: &CameraOptions) -> CameraOptions {
+    let center0 = from.get_center();
+    let center = to.center.unwrap_or(center0);
+    let long = center0.long + shortest_turn(center.long - center0.long);
+    let bearing = to.bearing.unwrap_or_else(|| from.get_bearing());
+    CameraOptions {
+        center: Some(LatLng::new(center.lat, long)),
+        zoom: Some(to.zoom.unwrap_or_else(|| from.get_zoom())),
+        bearing: Some(from.get_bearing() + shortest_turn(bearing - from.get_bearing())),
+        pitch: Some(to.pitch.unwrap_or_else(|| from.get_pitch())),
+    }
+}
+
+// `degrees` wrapped into [-180, 180)
+fn shortest_turn(degrees: f64) -> f64 {
+    (degrees + 180.0).rem_euclid(360.0) - 180.0
+}
+
+fn interpolate(from: f64, to: f64, t: f64) -> f64 {
+    from + (to - from) * t
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::map::Map;
+    use super::*;
+
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    const EPSILON: f64 = 1e-6;
+
+    fn map_with_clock() -> (Map, ManualClock) {
+        let clock = ManualClock::new();
+        let mut map = Map::new();
+        map.set_clock(Box::new(clock.clone()));
+        map.jump_to(CameraOptions {
+            center: Some(LatLng::new(0.0, 0.0)),
+            zoom: Some(4.0),
+            bearing: Some(0.0),
+            pitch: Some(0.0),
+        });
+        (map, clock)
+    }
+
+    // Options counting how often the callbacks fire
+    fn counted(duration_ms: u64) -> (AnimationOptions, Rc<Cell<u32>>, Rc<Cell<u32>>) {
+        let completed = Rc::new(Cell::new(0));
+        let cancelled = Rc::new(Cell::new(0));
+        let (on_complete, on_cancel) = (completed.clone(), cancelled.clone());
+        let options = AnimationOptions {
+            duration: Some(Duration::from_millis(duration_ms)),
+            easing: Some(UnitBezier::linear()),
+            on_complete: Some(Box::new(move || on_complete.set(on_complete.get() + 1))),
+            on_cancel: Some(Box::new(move || on_cancel.set(on_cancel.get() + 1))),
+            ..Default::default()
+        };
+        (options, completed, cancelled)
+    }
+
+    fn assert_camera(map: &Map, lat: f64, long: f64, zoom: f64, bearing: f64) {
+        let center = map.get_center();
+        assert!((center.lat - lat).abs() < EPSILON, "lat {}", center.lat);
+        assert!((center.long - long).abs() < EPSILON, "long {}", center.long);
+        assert!(
+            (map.get_zoom() - zoom).abs() < EPSILON,
+            "zoom {}",
+            map.get_zoom()
+        );
+        assert!(
+            (map.get_bearing() - bearing).abs() < EPSILON,
+            "bearing {}",
+            map.get_bearing()
+        );
+    }
+
+    #[test]
+    fn linear_bezier_is_identity() {
+        let linear = UnitBezier::linear();
+        for x in [0.0, 0.25, 0.5, 0.75, 1.0].iter() {
+            assert!((linear.solve(*x) - x).abs() < EPSILON);
+        }
+        let ease = UnitBezier::ease();
+        assert!(ease.solve(0.0).abs() < EPSILON);
+        assert!((ease.solve(1.0) - 1.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn ease_to_steps_with_clock() {
+        let (mut map, clock) = map_with_clock();
+        let (options, completed, cancelled) = counted(1000);
+        let target = CameraOptions {
+            center: Some(LatLng::new(10.0, 20.0)),
+            zoom: Some(6.0),
+            bearing: Some(90.0),
+            pitch: None,
+        };
+        map.ease_to(target, options);
+        assert!(map.is_in_transition());
+        assert_camera(&map, 0.0, 0.0, 4.0, 0.0);
+
+        clock.advance(Duration::from_millis(500));
+        assert!(map.update_transitions());
+        assert_camera(&map, 5.0, 10.0, 5.0, 45.0);
+        assert_eq!(completed.get(), 0);
+
+        clock.advance(Duration::from_millis(500));
+        assert!(!map.update_transitions());
+        assert_camera(&map, 10.0, 20.0, 6.0, 90.0);
+        assert!(!map.is_in_transition());
+
+        clock.advance(Duration::from_millis(500));
+        assert!(!map.update_transitions());
+        assert_eq!(completed.get(), 1);
+        assert_eq!(cancelled.get(), 0);
+    }
+
+    #[test]
+    fn ease_to_takes_shortest_turn() {
+        let (mut map, clock) = map_with_clock();
+        map.jump_to(CameraOptions {
+            center: Some(LatLng::new(0.0, 170.0)),
+            bearing: Some(170.0),
+            ..Default::default()
+        });
+        let (options, _, _) = counted(1000);
+        map.ease_to(
+            CameraOptions {
+                center: Some(LatLng::new(0.0, -170.0)),
+                bearing: Some(-170.0),
+                ..Default::default()
+            },
+            options,
+        );
+        clock.advance(Duration::from_millis(500));
+        map.update_transitions();
+        // Across the antimeridian and through south, not back around
+        assert!((map.get_center().long.abs() - 180.0).abs() < EPSILON);
+        assert!((map.get_bearing().abs() - 180.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn fly_to_zooms_out_on_the_way() {
+        let (mut map, clock) = map_with_clock();
+        let (options, completed, cancelled) = counted(2000);
+        map.fly_to(
+            CameraOptions {
+                center: Some(LatLng::new(0.0, 40.0)),
+                ..Default::default()
+            },
+            options,
+        );
+        assert_camera(&map, 0.0, 0.0, 4.0, 0.0);
+
+        clock.advance(Duration::from_millis(1000));
+        assert!(map.update_transitions());
+        // Same zoom at both ends, so the path is symmetric around halfway
+        assert!((map.get_center().long - 20.0).abs() < EPSILON);
+        assert!(map.get_zoom() < 4.0 - 0.5);
+
+        clock.advance(Duration::from_millis(1000));
+        assert!(!map.update_transitions());
+        assert_camera(&map, 0.0, 40.0, 4.0, 0.0);
+        assert_eq!(completed.get(), 1);
+        assert_eq!(cancelled.get(), 0);
+    }
+
+    #[test]
+    fn fly_to_without_panning_only_zooms() {
+        let (mut map, clock) = map_with_clock();
+        let (options, completed, _) = counted(1000);
+        map.fly_to(
+            CameraOptions {
+                zoom: Some(8.0),
+                ..Default::default()
+            },
+            options,
+        );
+        clock.advance(Duration::from_millis(500));
+        map.update_transitions();
+        assert_camera(&map, 0.0, 0.0, 6.0, 0.0);
+
+        clock.advance(Duration::from_millis(500));
+        map.update_transitions();
+        assert_camera(&map, 0.0, 0.0, 8.0, 0.0);
+        assert_eq!(completed.get(), 1);
+    }
+
+    #[test]
+    fn second_transition_cancels_first() {
+        let (mut map, clock) = map_with_clock();
+        let (first, first_completed, first_cancelled) = counted(1000);
+        let (second, second_completed, second_cancelled) = counted(1000);
+        map.ease_to(
+            CameraOptions {
+                zoom: Some(6.0),
+                ..Default::default()
+            },
+            first,
+        );
+        clock.advance(Duration::from_millis(500));
+        map.update_transitions();
+
+        // Starts from wherever the first one had got to
+        map.fly_to(
+            CameraOptions {
+                zoom: Some(3.0),
+                ..Default::default()
+            },
+            second,
+        );
+        assert_eq!(first_cancelled.get(), 1);
+        assert_camera(&map, 0.0, 0.0, 5.0, 0.0);
+
+        clock.advance(Duration::from_millis(1000));
+        assert!(!map.update_transitions());
+        assert_camera(&map, 0.0, 0.0, 3.0, 0.0);
+        assert_eq!(first_completed.get(), 0);
+        assert_eq!(first_cancelled.get(), 1);
+        assert_eq!(second_completed.get(), 1);
+        assert_eq!(second_cancelled.get(), 0);
+    }
+
+    #[test]
+    fn user_gesture_cancels_transition() {
+        let (mut map, clock) = map_with_clock();
+        let (options, completed, cancelled) = counted(1000);
+        map.ease_to(
+            CameraOptions {
+                center: Some(LatLng::new(10.0, 10.0)),
+                ..Default::default()
+            },
+            options,
+        );
+        clock.advance(Duration::from_millis(500));
+        map.update_transitions();
+
+        map.pan(1.0, 1.0);
+        assert!(!map.is_in_transition());
+        assert_camera(&map, 6.0, 6.0, 4.0, 0.0);
+
+        clock.advance(Duration::from_millis(1000));
+        assert!(!map.update_transitions());
+        assert_camera(&map, 6.0, 6.0, 4.0, 0.0);
+        assert_eq!(completed.get(), 0);
+        assert_eq!(cancelled.get(), 1);
+    }
+}
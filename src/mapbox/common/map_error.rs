@@ -4,9 +4,11 @@ use std::fmt::{Display, Formatter, Result};
 #[derive(Debug)]
 pub enum MapErrorTag {
     //Unknown,
-    //Style,
+    Style,
     //Manifest,
     Network,
+    File,
+    Camera,
     //DiskCache,
 }
 
@@ -5,3 +5,10 @@
 // Mapbox related consts
 pub const TILE_SIZE: u64 = 512;
 pub const MAP_DEFAULT_ZOOM_LEVEL: f32 = 11.0;
+
+// Default limits of the tile cache, see Map::set_tile_cache_limits
+pub const TILE_CACHE_MAX_TILES: usize = 256;
+pub const TILE_CACHE_MAX_BYTES: usize = 256 * 1024 * 1024;
+
+// Device pixels per CSS pixel. Sprites and glyphs are picked for this density.
+pub const PIXEL_RATIO: f32 = 1.0;
@@ -0,0 +1,183 @@
+// Ref: https://github.com/mapbox/mapbox-gl-native/blob/master/include/mbgl/util/geo.hpp
+//
+// Coordinates, one type per space so that a lat/long can't be passed where
+// a screen pixel is expected. All of them are f64, f32 latitudes are meters
+// off at street level.
+use super::utils::projection::{Projection, WebMercator};
+use super::vector_tile_id::VectorTileID;
+
+use std::f64::consts::PI;
+
+// WGS84 equatorial radius, what EPSG:3857 meters are measured with
+pub const EARTH_RADIUS: f64 = 6_378_137.0;
+
+// A place on the globe, in degrees
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LatLng {
+    pub lat: f64,
+    pub long: f64,
+}
+
+impl LatLng {
+    pub const fn new(lat: f64, long: f64) -> LatLng {
+        LatLng { lat, long }
+    }
+
+    // The same place with the longitude in [-180, 180)
+    pub fn wrapped(&self) -> LatLng {
+        LatLng::new(self.lat, (self.long + 180.0).rem_euclid(360.0) - 180.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LatLngBounds {
+    pub south_west: LatLng,
+    pub north_east: LatLng,
+}
+
+impl LatLngBounds {
+    pub fn new(south_west: LatLng, north_east: LatLng) -> LatLngBounds {
+        LatLngBounds {
+            south_west,
+            north_east,
+        }
+    }
+
+    pub fn south(&self) -> f64 {
+        self.south_west.lat
+    }
+
+    pub fn west(&self) -> f64 {
+        self.south_west.long
+    }
+
+    pub fn north(&self) -> f64 {
+        self.north_east.lat
+    }
+
+    pub fn east(&self) -> f64 {
+        self.north_east.long
+    }
+
+    pub fn north_west(&self) -> LatLng {
+        LatLng::new(self.north(), self.west())
+    }
+
+    pub fn south_east(&self) -> LatLng {
+        LatLng::new(self.south(), self.east())
+    }
+
+    pub fn center(&self) -> LatLng {
+        LatLng::new(
+            (self.south() + self.north()) / 2.0,
+            (self.west() + self.east()) / 2.0,
+        )
+    }
+
+    // South is below north and west left of east
+    pub fn is_valid(&self) -> bool {
+        self.south() < self.north() && self.west() < self.east()
+    }
+
+    pub fn contains(&self, latlng: &LatLng) -> bool {
+        (self.south()..=self.north()).contains(&latlng.lat)
+            && (self.west()..=self.east()).contains(&latlng.long)
+    }
+}
+
+// EPSG:3857 meters from where the equator meets the prime meridian
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProjectedMeters {
+    pub northing: f64,
+    pub easting: f64,
+}
+
+impl ProjectedMeters {
+    pub fn new(northing: f64, easting: f64) -> ProjectedMeters {
+        ProjectedMeters { northing, easting }
+    }
+
+    pub fn from_latlng(latlng: &LatLng) -> ProjectedMeters {
+        let (x, y) = WebMercator.project(latlng);
+        let circumference = 2.0 * PI * EARTH_RADIUS;
+        ProjectedMeters::new((0.5 - y) * circumference, (x - 0.5) * circumference)
+    }
+
+    pub fn to_latlng(&self) -> LatLng {
+        let circumference = 2.0 * PI * EARTH_RADIUS;
+        WebMercator.unproject((
+            self.easting / circumference + 0.5,
+            0.5 - self.northing / circumference,
+        ))
+    }
+}
+
+// Position in units of tiles at zoom `z`, i.e. tile (x, y, z) spans from
+// (x, y) to (x + 1, y + 1)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TileCoordinate {
+    pub x: f64,
+    pub y: f64,
+    pub z: u32,
+}
+
+impl TileCoordinate {
+    pub fn new(x: f64, y: f64, z: u32) -> TileCoordinate {
+        TileCoordinate { x, y, z }
+    }
+
+    pub fn from_latlng(projection: &dyn Projection, latlng: &LatLng, z: u32) -> TileCoordinate {
+        let (x, y) = projection.project(latlng);
+        let tiles = 2f64.powi(z as i32);
+        TileCoordinate::new(x * tiles, y * tiles, z)
+    }
+
+    pub fn to_latlng(&self, projection: &dyn Projection) -> LatLng {
+        let tiles = 2f64.powi(self.z as i32);
+        projection.unproject((self.x / tiles, self.y / tiles))
+    }
+
+    // The same position in units of tiles at zoom `z`
+    pub fn zoom_to(&self, z: u32) -> TileCoordinate {
+        let scale = 2f64.powi(z as i32 - self.z as i32);
+        TileCoordinate::new(self.x * scale, self.y * scale, z)
+    }
+
+    // Tile the coordinate falls into, if it is inside the world
+    pub fn tile_id(&self, projection: &dyn Projection) -> Option<VectorTileID> {
+        let tiles = 2f64.powi(self.z as i32);
+        let columns = tiles * projection.world_width() as f64;
+        if !(0.0..columns).contains(&self.x) || !(0.0..tiles).contains(&self.y) {
+            return None;
+        }
+        Some(VectorTileID::new(
+            self.x.floor() as u32,
+            self.y.floor() as u32,
+            self.z,
+        ))
+    }
+}
+
+// Top left corner of the tile
+impl From<VectorTileID> for TileCoordinate {
+    fn from(tile_id: VectorTileID) -> TileCoordinate {
+        TileCoordinate::new(tile_id.x as f64, tile_id.y as f64, tile_id.z)
+    }
+}
+
+// Pixels from the top left corner of the viewport
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScreenCoordinate {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl ScreenCoordinate {
+    pub fn new(x: f64, y: f64) -> ScreenCoordinate {
+        ScreenCoordinate { x, y }
+    }
+
+    pub fn distance(&self, other: &ScreenCoordinate) -> f64 {
+        (self.x - other.x).hypot(self.y - other.y)
+    }
+}
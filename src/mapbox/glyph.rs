@@ -0,0 +1,253 @@
+// Ref: https://docs.mapbox.com/mapbox-gl-js/style-spec/glyphs/
+//
+// Glyph ranges are requested lazily, only for the codepoints labels actually
+// use. Every font of a fontstack is requested on its own and the first font
+// which has a glyph wins, so fallback fonts (e.g. for CJK) also work with
+// servers that can't combine fontstacks.
+use super::common::map_error::MapError;
+use super::common::task_responder::TaskResponder;
+use super::common::types::{Threadable, ThreadableNew};
+use super::glyph_atlas::{GlyphAtlas, GlyphPosition};
+use super::glyph_model::{self, Glyph, GLYPH_RANGE_COUNT, GLYPH_RANGE_SIZE};
+use super::io::resource::Resource;
+
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RangeState {
+    Requested,
+    Loaded,
+    Failed,
+}
+
+struct GlyphsImpl {
+    template_url: String,
+    // font -> range start -> state
+    ranges: HashMap<String, HashMap<u32, RangeState>>,
+    // font -> codepoint -> glyph
+    glyphs: HashMap<String, HashMap<u32, Glyph>>,
+    glyph_atlas: Threadable<GlyphAtlas>,
+}
+
+impl GlyphsImpl {
+    fn new() -> GlyphsImpl {
+        GlyphsImpl {
+            template_url: String::new(),
+            ranges: HashMap::new(),
+            glyphs: HashMap::new(),
+            glyph_atlas: ThreadableNew(GlyphAtlas::new()),
+        }
+    }
+
+    fn range_state(&self, font: &str, range: u32) -> Option<RangeState> {
+        self.ranges.get(font)?.get(&range).copied()
+    }
+
+    fn set_range_state(&mut self, font: &str, range: u32, state: RangeState) {
+        self.ranges
+            .entry(font.to_owned())
+            .or_default()
+            .insert(range, state);
+    }
+
+    fn on_glyph_range(&mut self, template_url: &str, font: &str, range: u32, data: Vec<u8>) {
+        if template_url != self.template_url {
+            // Response of a glyph url which has been replaced in the meantime
+            return;
+        }
+
+        match glyph_model::parse_glyphs(data) {
+            Ok(glyphs) => {
+                let font_glyphs = self.glyphs.entry(font.to_owned()).or_default();
+                for glyph in glyphs {
+                    font_glyphs.insert(glyph.id, glyph);
+                }
+                self.set_range_state(font, range, RangeState::Loaded);
+            }
+            Err(map_error) => {
+                println!("Error: Glyphs Parse Failed {}", map_error);
+                self.set_range_state(font, range, RangeState::Failed);
+            }
+        }
+    }
+
+    fn on_glyph_range_failed(&mut self, template_url: &str, font: &str, range: u32) {
+        if template_url == self.template_url {
+            self.set_range_state(font, range, RangeState::Failed);
+        }
+    }
+}
+
+// Request of one range of one font
+struct GlyphRequest {
+    template_url: String,
+    font: String,
+    range: u32,
+    glyphs_impl: Threadable<GlyphsImpl>,
+}
+
+impl TaskResponder for GlyphRequest {
+    fn on_task_success(&mut self, url: String, data: Option<Vec<u8>>) {
+        let mut glyphs_impl = self.glyphs_impl.lock().unwrap();
+        match data {
+            Some(bytes) => {
+                glyphs_impl.on_glyph_range(&self.template_url, &self.font, self.range, bytes)
+            }
+            None => {
+                println!("Error: empty glyphs loaded from {}", url);
+                glyphs_impl.on_glyph_range_failed(&self.template_url, &self.font, self.range);
+            }
+        }
+    }
+
+    fn on_task_failure(&self, map_error: MapError) {
+        println!(
+            "Error: Glyphs {} {} Load Failed {}",
+            self.font,
+            glyph_model::glyph_range_name(self.range),
+            map_error
+        );
+        self.glyphs_impl.lock().unwrap().on_glyph_range_failed(
+            &self.template_url,
+            &self.font,
+            self.range,
+        );
+    }
+}
+
+//
+
+pub struct Glyphs {
+    glyphs_impl: Threadable<GlyphsImpl>,
+    resource: Resource,
+}
+
+impl Glyphs {
+    pub fn new() -> Glyphs {
+        Glyphs {
+            glyphs_impl: ThreadableNew(GlyphsImpl::new()),
+            resource: Resource::new(2),
+        }
+    }
+
+    // `template_url` is the style's "glyphs" value. Everything loaded from the
+    // previous url is dropped.
+    pub fn set_url(&self, template_url: &str) {
+        let mut glyphs_impl = self.glyphs_impl.lock().unwrap();
+        if glyphs_impl.template_url == template_url {
+            return;
+        }
+        glyphs_impl.template_url = template_url.to_owned();
+        glyphs_impl.ranges.clear();
+        glyphs_impl.glyphs.clear();
+        glyphs_impl.glyph_atlas.lock().unwrap().clear();
+    }
+
+    // Requests the ranges `text` needs which haven't been requested yet
+    pub fn load_glyphs(&self, fontstack: &[String], text: &str) {
+        let mut glyphs_impl = self.glyphs_impl.lock().unwrap();
+        if glyphs_impl.template_url.is_empty() {
+            return;
+        }
+
+        let mut ranges: Vec<u32> = text
+            .chars()
+            .map(|c| glyph_model::glyph_range_start(c as u32))
+            .filter(|range| *range < GLYPH_RANGE_COUNT * GLYPH_RANGE_SIZE)
+            .collect();
+        ranges.sort();
+        ranges.dedup();
+
+        for font in fontstack.iter() {
+            for range in ranges.iter() {
+                if glyphs_impl.range_state(font, *range).is_some() {
+                    continue;
+                }
+                glyphs_impl.set_range_state(font, *range, RangeState::Requested);
+
+                let url = Glyphs::get_request_url(&glyphs_impl.template_url, font, *range);
+                let responder = ThreadableNew(GlyphRequest {
+                    template_url: glyphs_impl.template_url.clone(),
+                    font: font.clone(),
+                    range: *range,
+                    glyphs_impl: self.glyphs_impl.clone(),
+                });
+                self.resource.get(&url, responder);
+            }
+        }
+    }
+
+    // Whether every range `text` needs has either loaded or failed
+    pub fn is_ready(&self, fontstack: &[String], text: &str) -> bool {
+        let glyphs_impl = self.glyphs_impl.lock().unwrap();
+        if glyphs_impl.template_url.is_empty() {
+            // Nothing will ever load, labels go without text
+            return true;
+        }
+        text.chars().all(|c| {
+            let range = glyph_model::glyph_range_start(c as u32);
+            range >= GLYPH_RANGE_COUNT * GLYPH_RANGE_SIZE
+                || fontstack.iter().all(|font| {
+                    matches!(
+                        glyphs_impl.range_state(font, range),
+                        Some(RangeState::Loaded) | Some(RangeState::Failed)
+                    )
+                })
+        })
+    }
+
+    // Position of the glyph in the atlas, packing it on first use. None if no
+    // font of the stack has it, or if the font which would win isn't loaded yet.
+    pub fn get_glyph(&self, fontstack: &[String], codepoint: u32) -> Option<GlyphPosition> {
+        let glyphs_impl = self.glyphs_impl.lock().unwrap();
+        let range = glyph_model::glyph_range_start(codepoint);
+
+        for font in fontstack.iter() {
+            if let Some(glyph) = glyphs_impl
+                .glyphs
+                .get(font)
+                .and_then(|glyphs| glyphs.get(&codepoint))
+            {
+                return glyphs_impl.glyph_atlas.lock().unwrap().add(font, glyph);
+            }
+            if glyphs_impl.range_state(font, range) == Some(RangeState::Requested) {
+                // Falling back now could pick a different font than later on
+                return None;
+            }
+        }
+        None
+    }
+
+    pub fn get_glyph_atlas(&self) -> Threadable<GlyphAtlas> {
+        self.glyphs_impl.lock().unwrap().glyph_atlas.clone()
+    }
+
+    fn get_request_url(template_url: &str, font: &str, range: u32) -> String {
+        template_url
+            .replace("{fontstack}", &encode_uri_component(font))
+            .replace("{range}", &glyph_model::glyph_range_name(range))
+    }
+}
+
+// Font names have spaces, and fontstacks commas
+fn encode_uri_component(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z'
+            | b'a'..=b'z'
+            | b'0'..=b'9'
+            | b'-'
+            | b'_'
+            | b'.'
+            | b'!'
+            | b'~'
+            | b'*'
+            | b'\''
+            | b'('
+            | b')' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
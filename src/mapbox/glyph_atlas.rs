@@ -0,0 +1,158 @@
+// All glyphs used for labels share one single channel (alpha) texture. Glyph
+// bitmaps are packed on shelves: rows as high as their tallest glyph which
+// are filled from left to right. The atlas grows downwards when it runs out of
+// shelves, so positions handed out earlier stay valid.
+use super::glyph_model::{Glyph, GlyphMetrics};
+
+use std::collections::HashMap;
+
+const ATLAS_WIDTH: u32 = 512;
+const ATLAS_INITIAL_HEIGHT: u32 = 128;
+const ATLAS_MAX_HEIGHT: u32 = 4096;
+// Empty space between glyphs so that linear sampling doesn't bleed
+const ATLAS_PADDING: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GlyphPosition {
+    // Rectangle of the bitmap (border included) in the atlas
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub metrics: GlyphMetrics,
+}
+
+struct Shelf {
+    y: u32,
+    height: u32,
+    free_x: u32,
+}
+
+pub struct GlyphAtlas {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>, // Alpha, row by row
+    // Bumped whenever pixels change so that the texture can be re-uploaded
+    pub version: u64,
+    shelves: Vec<Shelf>,
+    // Keyed by (font name, codepoint)
+    positions: HashMap<(String, u32), GlyphPosition>,
+}
+
+impl GlyphAtlas {
+    pub fn new() -> GlyphAtlas {
+        GlyphAtlas {
+            width: ATLAS_WIDTH,
+            height: ATLAS_INITIAL_HEIGHT,
+            pixels: vec![0; (ATLAS_WIDTH * ATLAS_INITIAL_HEIGHT) as usize],
+            version: 0,
+            shelves: vec![],
+            positions: HashMap::new(),
+        }
+    }
+
+    pub fn get(&self, font: &str, codepoint: u32) -> Option<GlyphPosition> {
+        self.positions.get(&(font.to_owned(), codepoint)).copied()
+    }
+
+    // Copies the glyph bitmap into the atlas unless it's already there.
+    // Returns None when the atlas is full.
+    pub fn add(&mut self, font: &str, glyph: &Glyph) -> Option<GlyphPosition> {
+        if let Some(position) = self.get(font, glyph.id) {
+            return Some(position);
+        }
+
+        let (width, height) = if glyph.has_bitmap() {
+            glyph.bitmap_size()
+        } else {
+            (0, 0)
+        };
+        let (x, y) = if width == 0 {
+            // Nothing to draw, only the metrics matter
+            (0, 0)
+        } else {
+            self.allocate(width + ATLAS_PADDING, height + ATLAS_PADDING)?
+        };
+
+        for row in 0..height {
+            let src = (row * width) as usize;
+            let dst = ((y + row) * self.width + x) as usize;
+            self.pixels[dst..dst + width as usize]
+                .copy_from_slice(&glyph.bitmap[src..src + width as usize]);
+        }
+        if width > 0 {
+            self.version += 1;
+        }
+
+        let position = GlyphPosition {
+            x,
+            y,
+            width,
+            height,
+            metrics: glyph.metrics,
+        };
+        self.positions.insert((font.to_owned(), glyph.id), position);
+        Some(position)
+    }
+
+    // Drops every glyph, e.g. when the style's glyphs url changes. Shared
+    // handles stay valid, the new version has them re-upload the texture.
+    pub fn clear(&mut self) {
+        self.height = ATLAS_INITIAL_HEIGHT;
+        self.pixels = vec![0; (ATLAS_WIDTH * ATLAS_INITIAL_HEIGHT) as usize];
+        self.shelves.clear();
+        self.positions.clear();
+        self.version += 1;
+    }
+
+    fn allocate(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        if width > self.width {
+            return None;
+        }
+
+        // Best fit: the lowest shelf with enough room which wastes the least height
+        let atlas_width = self.width;
+        let best = self
+            .shelves
+            .iter_mut()
+            .filter(|shelf| shelf.height >= height && atlas_width - shelf.free_x >= width)
+            .min_by_key(|shelf| shelf.height - height);
+        if let Some(shelf) = best {
+            let x = shelf.free_x;
+            shelf.free_x += width;
+            return Some((x, shelf.y));
+        }
+
+        let y = self
+            .shelves
+            .last()
+            .map(|shelf| shelf.y + shelf.height)
+            .unwrap_or(0);
+        while y + height > self.height {
+            if self.height * 2 > ATLAS_MAX_HEIGHT {
+                println!("Warning: glyph atlas is full");
+                return None;
+            }
+            self.grow();
+        }
+        self.shelves.push(Shelf {
+            y,
+            height,
+            free_x: width,
+        });
+        Some((0, y))
+    }
+
+    // Rows are appended, so existing glyphs keep their positions
+    fn grow(&mut self) {
+        self.height *= 2;
+        self.pixels.resize((self.width * self.height) as usize, 0);
+        self.version += 1;
+    }
+}
+
+impl Default for GlyphAtlas {
+    fn default() -> GlyphAtlas {
+        GlyphAtlas::new()
+    }
+}
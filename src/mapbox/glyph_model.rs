@@ -0,0 +1,122 @@
+// Ref: https://github.com/mapbox/node-fontnik/blob/master/proto/glyphs.proto
+//
+// Glyphs are served as protobufs holding 256 consecutive codepoints of one
+// fontstack. Every glyph is a signed distance field bitmap (one byte per
+// pixel) with a GLYPH_BORDER pixels wide border on each side, so the bitmap
+// is (width + 2 * GLYPH_BORDER) x (height + 2 * GLYPH_BORDER).
+use super::common::map_error::{MapError, MapErrorTag};
+use super::utils::pbf::Pbf;
+
+pub const GLYPH_BORDER: u32 = 3;
+pub const GLYPH_RANGE_SIZE: u32 = 256;
+// Codepoints above the Basic Multilingual Plane are not served
+pub const GLYPH_RANGE_COUNT: u32 = 256;
+
+// Metrics are in pixels of a 24px font, which is what the SDFs are cut for
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct GlyphMetrics {
+    pub width: u32,
+    pub height: u32,
+    pub left: i32,
+    pub top: i32,
+    pub advance: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct Glyph {
+    pub id: u32,
+    pub metrics: GlyphMetrics,
+    pub bitmap: Vec<u8>, // Empty for glyphs without ink, e.g. spaces
+}
+
+impl Glyph {
+    fn parse(data: &mut Pbf) -> Option<Glyph> {
+        let mut id = None;
+        let mut metrics = GlyphMetrics::default();
+        let mut bitmap = vec![];
+
+        while data.next() {
+            match data.tag {
+                1 => id = Some(data.varint32()),
+                2 => bitmap = data.bytes(),
+                3 => metrics.width = data.varint32(),
+                4 => metrics.height = data.varint32(),
+                5 => metrics.left = data.svarint32(),
+                6 => metrics.top = data.svarint32(),
+                7 => metrics.advance = data.varint32(),
+                _ => data.skip(),
+            }
+        }
+
+        let glyph = Glyph {
+            id: id?,
+            metrics,
+            bitmap,
+        };
+        if glyph.has_bitmap() || glyph.bitmap.is_empty() {
+            Some(glyph)
+        } else {
+            None
+        }
+    }
+
+    // Bitmap dimensions including the border
+    pub fn bitmap_size(&self) -> (u32, u32) {
+        if self.metrics.width == 0 || self.metrics.height == 0 {
+            return (0, 0);
+        }
+        (
+            self.metrics.width + 2 * GLYPH_BORDER,
+            self.metrics.height + 2 * GLYPH_BORDER,
+        )
+    }
+
+    pub fn has_bitmap(&self) -> bool {
+        let (width, height) = self.bitmap_size();
+        width > 0 && self.bitmap.len() == (width * height) as usize
+    }
+}
+
+// message glyphs { repeated fontstack stacks = 1; }
+// message fontstack { name = 1; range = 2; repeated glyph glyphs = 3; }
+pub fn parse_glyphs(data: Vec<u8>) -> Result<Vec<Glyph>, MapError> {
+    let mut data = Pbf::new(data);
+    let mut glyphs = vec![];
+
+    while data.next() {
+        if data.tag != 1 {
+            data.skip();
+            continue;
+        }
+
+        let mut stack = data.message();
+        while stack.next() {
+            if stack.tag != 3 {
+                stack.skip();
+                continue;
+            }
+            let mut msg = stack.message();
+            match Glyph::parse(&mut msg) {
+                Some(glyph) => glyphs.push(glyph),
+                None => {
+                    return Err(MapError::new(
+                        MapErrorTag::Style,
+                        "glyph with a malformed bitmap".to_owned(),
+                    ))
+                }
+            }
+        }
+    }
+
+    Ok(glyphs)
+}
+
+// First codepoint of the range a codepoint belongs to
+pub fn glyph_range_start(codepoint: u32) -> u32 {
+    codepoint / GLYPH_RANGE_SIZE * GLYPH_RANGE_SIZE
+}
+
+// "0-255", "256-511", ...
+pub fn glyph_range_name(range_start: u32) -> String {
+    format!("{}-{}", range_start, range_start + GLYPH_RANGE_SIZE - 1)
+}
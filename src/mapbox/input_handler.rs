@@ -0,0 +1,785 @@
+// Ref: https://github.com/mapbox/mapbox-gl-js/tree/main/src/ui/handler
+//
+// Turns input events into camera moves: drag to pan (with inertia), wheel to
+// zoom around the cursor, double-click to zoom in (out with shift), shift-drag
+// to zoom into a box, right-drag to rotate and pitch, arrows and +/- keys.
+// On touch screens one finger pans and two pinch to zoom. The windowing code
+// translates its own events into InputEvent.
+use super::camera::{AnimationOptions, CameraOptions, Clock, SystemClock, UnitBezier};
+use super::geo::ScreenCoordinate;
+use super::map::Map;
+use super::transform::Transform;
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(300);
+// Pixels the cursor may move between the clicks of a double-click
+const CLICK_TOLERANCE: f64 = 3.0;
+
+// Zoom levels per wheel notch, and per pixel of touchpad scroll
+const WHEEL_ZOOM_PER_LINE: f64 = 0.5;
+const WHEEL_ZOOM_PER_PIXEL: f64 = 1.0 / 200.0;
+
+// Degrees per pixel of right-drag
+const BEARING_PER_PIXEL: f64 = 0.8;
+const PITCH_PER_PIXEL: f64 = -0.5;
+
+const KEY_PAN_STEP: f64 = 100.0;
+const KEY_BEARING_STEP: f64 = 15.0;
+const KEY_PITCH_STEP: f64 = 10.0;
+const KEY_DURATION: Duration = Duration::from_millis(300);
+
+// Inertia after a pan, as mapbox-gl tunes it. Only the moves of the last
+// INERTIA_WINDOW count towards the release velocity.
+const INERTIA_WINDOW: Duration = Duration::from_millis(160);
+const INERTIA_LINEARITY: f64 = 0.3;
+const INERTIA_DECELERATION: f64 = 2500.0; // px/s^2
+const INERTIA_MAX_SPEED: f64 = 1400.0; // px/s
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Left,
+    Right,
+    Up,
+    Down,
+    Plus,
+    Minus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScrollDelta {
+    // Notches of a mouse wheel, positive away from the user
+    Lines(f64),
+    // Pixels of touchpad scroll
+    Pixels(f64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TouchPhase {
+    Started,
+    Moved,
+    Ended,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputEvent {
+    CursorMoved(ScreenCoordinate),
+    CursorLeft,
+    MousePressed {
+        button: MouseButton,
+        shift: bool,
+    },
+    MouseReleased(MouseButton),
+    Scroll(ScrollDelta),
+    KeyPressed {
+        key: Key,
+        shift: bool,
+    },
+    // `id` tells the fingers on the screen apart
+    Touch {
+        id: u64,
+        phase: TouchPhase,
+        position: ScreenCoordinate,
+    },
+}
+
+enum Gesture {
+    Idle,
+    Pan,
+    Rotate,
+    // Screen pixel the box was started at
+    BoxZoom(ScreenCoordinate),
+    Pinch,
+}
+
+pub struct InputHandler {
+    clock: Box<dyn Clock>,
+    cursor: ScreenCoordinate,
+    gesture: Gesture,
+    // Fingers on the screen in the order they came down, the first two pinch
+    touches: Vec<(u64, ScreenCoordinate)>,
+    // (time, cursor) of the latest pan moves, for inertia
+    pan_history: VecDeque<(Duration, ScreenCoordinate)>,
+    // (time, cursor) of the last left click, for double-clicks
+    last_click: Option<(Duration, ScreenCoordinate)>,
+}
+
+impl InputHandler {
+    // Double-clicks and inertia are timed with the system clock
+    pub fn new() -> InputHandler {
+        InputHandler::with_clock(Box::new(SystemClock::new()))
+    }
+
+    pub fn with_clock(clock: Box<dyn Clock>) -> InputHandler {
+        InputHandler {
+            clock,
+            cursor: ScreenCoordinate::new(0.0, 0.0),
+            gesture: Gesture::Idle,
+            touches: vec![],
+            pan_history: VecDeque::new(),
+            last_click: None,
+        }
+    }
+
+    // Returns whether the event moved or may move the camera
+    pub fn handle_event(&mut self, map: &mut Map, event: &InputEvent) -> bool {
+        match *event {
+            InputEvent::CursorMoved(cursor) => self.on_cursor_moved(map, cursor),
+            InputEvent::CursorLeft => {
+                self.gesture = Gesture::Idle;
+                false
+            }
+            InputEvent::MousePressed { button, shift } => self.on_mouse_pressed(map, button, shift),
+            InputEvent::MouseReleased(button) => self.on_mouse_released(map, button),
+            InputEvent::Scroll(delta) => {
+                let zoom_delta = match delta {
+                    ScrollDelta::Lines(lines) => lines * WHEEL_ZOOM_PER_LINE,
+                    ScrollDelta::Pixels(pixels) => pixels * WHEEL_ZOOM_PER_PIXEL,
+                };
+                let transform = map.get_transform();
+                let zoom = transform.get_zoom() + zoom_delta;
+                if let Some(camera) = zoom_around(&transform, zoom, self.cursor, self.cursor) {
+                    map.jump_to(camera);
+                }
+                true
+            }
+            InputEvent::KeyPressed { key, shift } => self.on_key_pressed(map, key, shift),
+            InputEvent::Touch {
+                id,
+                phase,
+                position,
+            } => self.on_touch(map, id, phase, position),
+        }
+    }
+
+    fn on_cursor_moved(&mut self, map: &mut Map, cursor: ScreenCoordinate) -> bool {
+        let previous = self.cursor;
+        self.cursor = cursor;
+
+        match self.gesture {
+            Gesture::Pan if self.touches.is_empty() => {
+                self.pan(map, previous, cursor);
+                true
+            }
+            Gesture::Rotate => {
+                let transform = map.get_transform();
+                map.jump_to(CameraOptions {
+                    bearing: Some(
+                        transform.get_bearing() + (cursor.x - previous.x) * BEARING_PER_PIXEL,
+                    ),
+                    pitch: Some(transform.get_pitch() + (cursor.y - previous.y) * PITCH_PER_PIXEL),
+                    ..Default::default()
+                });
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn on_mouse_pressed(&mut self, map: &mut Map, button: MouseButton, shift: bool) -> bool {
+        match button {
+            MouseButton::Left => {
+                map.cancel_transitions();
+                let now = self.clock.now();
+                let is_double_click = self.last_click.is_some_and(|(time, position)| {
+                    now.saturating_sub(time) <= DOUBLE_CLICK_INTERVAL
+                        && position.distance(&self.cursor) <= CLICK_TOLERANCE
+                });
+
+                if is_double_click {
+                    self.last_click = None;
+                    self.gesture = Gesture::Idle;
+                    let transform = map.get_transform();
+                    let step = if shift { -1.0 } else { 1.0 };
+                    let zoom = transform.get_zoom() + step;
+                    if let Some(camera) = zoom_around(&transform, zoom, self.cursor, self.cursor) {
+                        map.ease_to(camera, AnimationOptions::default());
+                    }
+                    return true;
+                }
+
+                self.last_click = Some((now, self.cursor));
+                if shift {
+                    self.gesture = Gesture::BoxZoom(self.cursor);
+                } else {
+                    self.start_pan(self.cursor);
+                }
+                true
+            }
+            MouseButton::Right => {
+                map.cancel_transitions();
+                self.gesture = Gesture::Rotate;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn on_mouse_released(&mut self, map: &mut Map, button: MouseButton) -> bool {
+        let gesture = std::mem::replace(&mut self.gesture, Gesture::Idle);
+        match (button, gesture) {
+            (MouseButton::Left, Gesture::Pan) => self.start_inertia(map),
+            (MouseButton::Left, Gesture::BoxZoom(start)) => {
+                let transform = map.get_transform();
+                if let Some(camera) = zoom_to_box(&transform, start, self.cursor) {
+                    map.ease_to(camera, AnimationOptions::default());
+                }
+                true
+            }
+            (MouseButton::Right, Gesture::Rotate) => true,
+            (_, gesture) => {
+                // Releasing another button doesn't end the gesture
+                self.gesture = gesture;
+                false
+            }
+        }
+    }
+
+    fn on_touch(
+        &mut self,
+        map: &mut Map,
+        id: u64,
+        phase: TouchPhase,
+        position: ScreenCoordinate,
+    ) -> bool {
+        match phase {
+            TouchPhase::Started => {
+                map.cancel_transitions();
+                self.touches.retain(|(touch, _)| *touch != id);
+                self.touches.push((id, position));
+                match self.touches.len() {
+                    1 => self.start_pan(position),
+                    2 => self.gesture = Gesture::Pinch,
+                    _ => {}
+                }
+                true
+            }
+            TouchPhase::Moved => {
+                let before: Vec<ScreenCoordinate> =
+                    self.touches.iter().map(|(_, position)| *position).collect();
+                match self.touches.iter_mut().find(|(touch, _)| *touch == id) {
+                    Some(touch) => touch.1 = position,
+                    None => return false,
+                }
+                let after: Vec<ScreenCoordinate> =
+                    self.touches.iter().map(|(_, position)| *position).collect();
+
+                match self.gesture {
+                    Gesture::Pan => self.pan(map, before[0], after[0]),
+                    Gesture::Pinch => {
+                        let transform = map.get_transform();
+                        let (from, to) =
+                            (midpoint(before[0], before[1]), midpoint(after[0], after[1]));
+                        let scale = after[0].distance(&after[1]) / before[0].distance(&before[1]);
+                        if !scale.is_finite() || scale <= 0.0 {
+                            return false;
+                        }
+                        let zoom = transform.get_zoom() + scale.log2();
+                        if let Some(camera) = zoom_around(&transform, zoom, from, to) {
+                            map.jump_to(camera);
+                        }
+                    }
+                    _ => return false,
+                }
+                true
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                let count = self.touches.len();
+                self.touches.retain(|(touch, _)| *touch != id);
+                if self.touches.len() == count {
+                    return false;
+                }
+                match (&self.gesture, self.touches.first()) {
+                    // The finger left over carries on panning
+                    (Gesture::Pinch, Some(&(_, remaining))) if self.touches.len() == 1 => {
+                        self.start_pan(remaining);
+                        true
+                    }
+                    (Gesture::Pan, None) => {
+                        self.gesture = Gesture::Idle;
+                        phase == TouchPhase::Ended && self.start_inertia(map)
+                    }
+                    _ => true,
+                }
+            }
+        }
+    }
+
+    fn start_pan(&mut self, position: ScreenCoordinate) {
+        self.gesture = Gesture::Pan;
+        self.pan_history.clear();
+        self.pan_history.push_back((self.clock.now(), position));
+    }
+
+    // Keeps the ground under the pointer as it moves from `previous`
+    fn pan(&mut self, map: &mut Map, previous: ScreenCoordinate, position: ScreenCoordinate) {
+        let transform = map.get_transform();
+        let center = transform
+            .screen_to_latlng(previous)
+            .and_then(|latlng| transform.center_for_anchor(&latlng, position));
+        if let Some(center) = center {
+            map.jump_to(CameraOptions {
+                center: Some(center),
+                ..Default::default()
+            });
+        }
+
+        let now = self.clock.now();
+        self.pan_history.push_back((now, position));
+        while let Some((time, _)) = self.pan_history.front() {
+            if now.saturating_sub(*time) <= INERTIA_WINDOW {
+                break;
+            }
+            self.pan_history.pop_front();
+        }
+    }
+
+    // Keeps the map moving for a moment after a quick pan
+    fn start_inertia(&mut self, map: &mut Map) -> bool {
+        let now = self.clock.now();
+        let first = self
+            .pan_history
+            .iter()
+            .find(|(time, _)| now.saturating_sub(*time) <= INERTIA_WINDOW);
+        let (first, last) = match (first, self.pan_history.back()) {
+            (Some(first), Some(last)) if last.0 > first.0 => (*first, *last),
+            _ => return false,
+        };
+        self.pan_history.clear();
+
+        let elapsed = (last.0 - first.0).as_secs_f64();
+        // Pixels per second
+        let velocity = (
+            (last.1.x - first.1.x) / elapsed,
+            (last.1.y - first.1.y) / elapsed,
+        );
+        let magnitude = velocity.0.hypot(velocity.1);
+        let speed = (magnitude * INERTIA_LINEARITY).min(INERTIA_MAX_SPEED);
+        let duration = speed / (INERTIA_DECELERATION * INERTIA_LINEARITY);
+        if magnitude == 0.0 || duration < 0.01 {
+            return false;
+        }
+
+        // The map follows the cursor, so the view moves the other way
+        let travel = speed * duration / 2.0;
+        let offset = (
+            -velocity.0 / magnitude * travel,
+            -velocity.1 / magnitude * travel,
+        );
+        if let Some(camera) = pan_by(&map.get_transform(), offset) {
+            map.ease_to(
+                camera,
+                AnimationOptions {
+                    duration: Some(Duration::from_secs_f64(duration)),
+                    easing: Some(UnitBezier::new(0.0, 0.0, INERTIA_LINEARITY, 1.0)),
+                    ..Default::default()
+                },
+            );
+        }
+        true
+    }
+
+    fn on_key_pressed(&mut self, map: &mut Map, key: Key, shift: bool) -> bool {
+        let transform = map.get_transform();
+        let camera = match (key, shift) {
+            (Key::Left, true) => Some(CameraOptions {
+                bearing: Some(transform.get_bearing() - KEY_BEARING_STEP),
+                ..Default::default()
+            }),
+            (Key::Right, true) => Some(CameraOptions {
+                bearing: Some(transform.get_bearing() + KEY_BEARING_STEP),
+                ..Default::default()
+            }),
+            (Key::Up, true) => Some(CameraOptions {
+                pitch: Some(transform.get_pitch() + KEY_PITCH_STEP),
+                ..Default::default()
+            }),
+            (Key::Down, true) => Some(CameraOptions {
+                pitch: Some(transform.get_pitch() - KEY_PITCH_STEP),
+                ..Default::default()
+            }),
+            (Key::Left, false) => pan_by(&transform, (-KEY_PAN_STEP, 0.0)),
+            (Key::Right, false) => pan_by(&transform, (KEY_PAN_STEP, 0.0)),
+            (Key::Up, false) => pan_by(&transform, (0.0, -KEY_PAN_STEP)),
+            (Key::Down, false) => pan_by(&transform, (0.0, KEY_PAN_STEP)),
+            (Key::Plus, _) => Some(CameraOptions {
+                zoom: Some(transform.get_zoom().round() + 1.0),
+                ..Default::default()
+            }),
+            (Key::Minus, _) => Some(CameraOptions {
+                zoom: Some(transform.get_zoom().round() - 1.0),
+                ..Default::default()
+            }),
+        };
+
+        if let Some(camera) = camera {
+            map.ease_to(
+                camera,
+                AnimationOptions {
+                    duration: Some(KEY_DURATION),
+                    ..Default::default()
+                },
+            );
+        }
+        true
+    }
+}
+
+impl Default for InputHandler {
+    fn default() -> InputHandler {
+        InputHandler::new()
+    }
+}
+
+fn screen_center(transform: &Transform) -> ScreenCoordinate {
+    let (width, height) = transform.get_size();
+    ScreenCoordinate::new(width as f64 / 2.0, height as f64 / 2.0)
+}
+
+fn midpoint(a: ScreenCoordinate, b: ScreenCoordinate) -> ScreenCoordinate {
+    ScreenCoordinate::new((a.x + b.x) / 2.0, (a.y + b.y) / 2.0)
+}
+
+// Camera at `zoom` which moves the ground under `from` to `to`, keeping it
+// in place when they are the same pixel
+fn zoom_around(
+    transform: &Transform,
+    zoom: f64,
+    from: ScreenCoordinate,
+    to: ScreenCoordinate,
+) -> Option<CameraOptions> {
+    let latlng = transform.screen_to_latlng(from)?;
+    let mut zoomed = *transform;
+    zoomed.set_zoom(zoom);
+    Some(CameraOptions {
+        center: Some(zoomed.center_for_anchor(&latlng, to)?),
+        zoom: Some(zoomed.get_zoom()),
+        ..Default::default()
+    })
+}
+
+// Camera which has the view moved by `offset` screen pixels
+fn pan_by(transform: &Transform, offset: (f64, f64)) -> Option<CameraOptions> {
+    let center = screen_center(transform);
+    let point = ScreenCoordinate::new(center.x + offset.0, center.y + offset.1);
+    Some(CameraOptions {
+        center: Some(transform.screen_to_latlng(point)?),
+        ..Default::default()
+    })
+}
+
+// Camera which fills the view with the box between two screen pixels. None
+// for boxes too small to mean it.
+fn zoom_to_box(
+    transform: &Transform,
+    start: ScreenCoordinate,
+    end: ScreenCoordinate,
+) -> Option<CameraOptions> {
+    let (box_width, box_height) = ((end.x - start.x).abs(), (end.y - start.y).abs());
+    if box_width < CLICK_TOLERANCE || box_height < CLICK_TOLERANCE {
+        return None;
+    }
+    let (width, height) = transform.get_size();
+    let scale = (width as f64 / box_width).min(height as f64 / box_height);
+    let middle = ScreenCoordinate::new((start.x + end.x) / 2.0, (start.y + end.y) / 2.0);
+    Some(CameraOptions {
+        center: Some(transform.screen_to_latlng(middle)?),
+        zoom: Some(transform.get_zoom() + scale.log2()),
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::camera::ManualClock;
+    use super::super::geo::LatLng;
+    use super::*;
+
+    const EPSILON: f64 = 1e-6;
+    // Time between two synthetic moves, about a frame
+    const FRAME: Duration = Duration::from_millis(16);
+
+    fn setup() -> (Map, InputHandler, ManualClock) {
+        let clock = ManualClock::new();
+        let mut map = Map::new();
+        map.set_clock(Box::new(clock.clone()));
+        map.set_size((800, 600));
+        map.jump_to(CameraOptions {
+            center: Some(LatLng::new(0.0, 0.0)),
+            zoom: Some(4.0),
+            bearing: Some(0.0),
+            pitch: Some(0.0),
+        });
+        let handler = InputHandler::with_clock(Box::new(clock.clone()));
+        (map, handler, clock)
+    }
+
+    fn point(x: f64, y: f64) -> ScreenCoordinate {
+        ScreenCoordinate::new(x, y)
+    }
+
+    fn touch(id: u64, phase: TouchPhase, x: f64, y: f64) -> InputEvent {
+        InputEvent::Touch {
+            id,
+            phase,
+            position: point(x, y),
+        }
+    }
+
+    fn left_pressed() -> InputEvent {
+        InputEvent::MousePressed {
+            button: MouseButton::Left,
+            shift: false,
+        }
+    }
+
+    fn assert_latlng(actual: LatLng, expected: LatLng) {
+        assert!(
+            (actual.lat - expected.lat).abs() < EPSILON
+                && (actual.long - expected.long).abs() < EPSILON,
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    // Ground under `point` on the map as it is now
+    fn ground(map: &Map, point: ScreenCoordinate) -> LatLng {
+        map.get_transform().screen_to_latlng(point).unwrap()
+    }
+
+    // Drags from `from` by `step` pixels each frame, `frames` times
+    fn drag(
+        map: &mut Map,
+        handler: &mut InputHandler,
+        clock: &ManualClock,
+        from: ScreenCoordinate,
+        step: (f64, f64),
+        frames: u32,
+    ) -> ScreenCoordinate {
+        handler.handle_event(map, &InputEvent::CursorMoved(from));
+        handler.handle_event(map, &left_pressed());
+        let mut cursor = from;
+        for _ in 0..frames {
+            clock.advance(FRAME);
+            cursor = point(cursor.x + step.0, cursor.y + step.1);
+            handler.handle_event(map, &InputEvent::CursorMoved(cursor));
+        }
+        cursor
+    }
+
+    #[test]
+    fn drag_keeps_ground_under_cursor() {
+        let (mut map, mut handler, clock) = setup();
+        let start = point(400.0, 300.0);
+        let grabbed = ground(&map, start);
+
+        let end = drag(&mut map, &mut handler, &clock, start, (-10.0, 5.0), 10);
+        assert_latlng(ground(&map, end), grabbed);
+        assert!(map.get_center().long > 0.0);
+        assert!(map.get_center().lat > 0.0);
+
+        // Held still before letting go, so nothing carries on
+        clock.advance(Duration::from_millis(500));
+        handler.handle_event(&mut map, &InputEvent::MouseReleased(MouseButton::Left));
+        assert!(!map.is_in_transition());
+        assert_latlng(ground(&map, end), grabbed);
+    }
+
+    #[test]
+    fn quick_drag_carries_on_with_inertia() {
+        let (mut map, mut handler, clock) = setup();
+        let end = drag(
+            &mut map,
+            &mut handler,
+            &clock,
+            point(400.0, 300.0),
+            (-20.0, 0.0),
+            5,
+        );
+        let released_long = map.get_center().long;
+        handler.handle_event(&mut map, &InputEvent::MouseReleased(MouseButton::Left));
+        assert!(map.is_in_transition());
+
+        // 1250px/s, eased out over half a second
+        clock.advance(Duration::from_millis(250));
+        assert!(map.update_transitions());
+        let halfway_long = map.get_center().long;
+        assert!(halfway_long > released_long);
+
+        clock.advance(Duration::from_millis(250));
+        assert!(!map.update_transitions());
+        assert!(map.get_center().long > halfway_long);
+        assert!(map.get_center().lat.abs() < EPSILON);
+        // The ground kept moving the way the cursor went
+        assert!(ground(&map, end).long > 0.0);
+    }
+
+    #[test]
+    fn press_stops_inertia() {
+        let (mut map, mut handler, clock) = setup();
+        drag(
+            &mut map,
+            &mut handler,
+            &clock,
+            point(400.0, 300.0),
+            (-20.0, 0.0),
+            5,
+        );
+        handler.handle_event(&mut map, &InputEvent::MouseReleased(MouseButton::Left));
+        clock.advance(Duration::from_millis(100));
+        map.update_transitions();
+        let center = map.get_center();
+
+        handler.handle_event(&mut map, &left_pressed());
+        assert!(!map.is_in_transition());
+        clock.advance(Duration::from_millis(500));
+        map.update_transitions();
+        assert_latlng(map.get_center(), center);
+    }
+
+    #[test]
+    fn scroll_zooms_around_cursor() {
+        let (mut map, mut handler, _) = setup();
+        let cursor = point(600.0, 150.0);
+        handler.handle_event(&mut map, &InputEvent::CursorMoved(cursor));
+        let under_cursor = ground(&map, cursor);
+
+        handler.handle_event(&mut map, &InputEvent::Scroll(ScrollDelta::Lines(2.0)));
+        assert!((map.get_zoom() - 5.0).abs() < EPSILON);
+        assert_latlng(ground(&map, cursor), under_cursor);
+
+        handler.handle_event(&mut map, &InputEvent::Scroll(ScrollDelta::Pixels(-400.0)));
+        assert!((map.get_zoom() - 3.0).abs() < EPSILON);
+        assert_latlng(ground(&map, cursor), under_cursor);
+    }
+
+    #[test]
+    fn pinch_zooms_and_pans_with_fingers() {
+        let (mut map, mut handler, _) = setup();
+        let middle = point(400.0, 300.0);
+        let under_middle = ground(&map, middle);
+
+        handler.handle_event(&mut map, &touch(1, TouchPhase::Started, 350.0, 300.0));
+        handler.handle_event(&mut map, &touch(2, TouchPhase::Started, 450.0, 300.0));
+        // Fingers twice as far apart
+        handler.handle_event(&mut map, &touch(1, TouchPhase::Moved, 300.0, 300.0));
+        handler.handle_event(&mut map, &touch(2, TouchPhase::Moved, 500.0, 300.0));
+        assert!((map.get_zoom() - 5.0).abs() < EPSILON);
+        assert_latlng(ground(&map, middle), under_middle);
+
+        // Both move down together, the ground follows
+        handler.handle_event(&mut map, &touch(1, TouchPhase::Moved, 300.0, 350.0));
+        handler.handle_event(&mut map, &touch(2, TouchPhase::Moved, 500.0, 350.0));
+        assert!((map.get_zoom() - 5.0).abs() < EPSILON);
+        assert_latlng(ground(&map, point(400.0, 350.0)), under_middle);
+    }
+
+    #[test]
+    fn finger_left_after_pinch_pans() {
+        let (mut map, mut handler, clock) = setup();
+        handler.handle_event(&mut map, &touch(1, TouchPhase::Started, 300.0, 300.0));
+        handler.handle_event(&mut map, &touch(2, TouchPhase::Started, 500.0, 300.0));
+        handler.handle_event(&mut map, &touch(1, TouchPhase::Ended, 300.0, 300.0));
+
+        let grabbed = ground(&map, point(500.0, 300.0));
+        clock.advance(FRAME);
+        handler.handle_event(&mut map, &touch(2, TouchPhase::Moved, 450.0, 250.0));
+        assert!((map.get_zoom() - 4.0).abs() < EPSILON);
+        assert_latlng(ground(&map, point(450.0, 250.0)), grabbed);
+
+        // Lifted after holding still, no inertia
+        clock.advance(Duration::from_millis(500));
+        handler.handle_event(&mut map, &touch(2, TouchPhase::Ended, 450.0, 250.0));
+        assert!(!map.is_in_transition());
+    }
+
+    #[test]
+    fn double_click_eases_in() {
+        let (mut map, mut handler, clock) = setup();
+        let cursor = point(200.0, 200.0);
+        let under_cursor = ground(&map, cursor);
+        handler.handle_event(&mut map, &InputEvent::CursorMoved(cursor));
+        for _ in 0..2 {
+            handler.handle_event(&mut map, &left_pressed());
+            handler.handle_event(&mut map, &InputEvent::MouseReleased(MouseButton::Left));
+            clock.advance(Duration::from_millis(100));
+        }
+        assert!(map.is_in_transition());
+
+        clock.advance(Duration::from_millis(500));
+        assert!(!map.update_transitions());
+        assert!((map.get_zoom() - 5.0).abs() < EPSILON);
+        assert_latlng(ground(&map, cursor), under_cursor);
+    }
+
+    #[test]
+    fn slow_clicks_are_no_double_click() {
+        let (mut map, mut handler, clock) = setup();
+        handler.handle_event(&mut map, &InputEvent::CursorMoved(point(200.0, 200.0)));
+        for _ in 0..2 {
+            handler.handle_event(&mut map, &left_pressed());
+            handler.handle_event(&mut map, &InputEvent::MouseReleased(MouseButton::Left));
+            clock.advance(DOUBLE_CLICK_INTERVAL * 2);
+        }
+        assert!(!map.is_in_transition());
+        assert!((map.get_zoom() - 4.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn right_drag_rotates_and_pitches() {
+        let (mut map, mut handler, _) = setup();
+        handler.handle_event(&mut map, &InputEvent::CursorMoved(point(400.0, 300.0)));
+        handler.handle_event(
+            &mut map,
+            &InputEvent::MousePressed {
+                button: MouseButton::Right,
+                shift: false,
+            },
+        );
+        handler.handle_event(&mut map, &InputEvent::CursorMoved(point(450.0, 280.0)));
+        handler.handle_event(&mut map, &InputEvent::MouseReleased(MouseButton::Right));
+        assert!((map.get_bearing() - 50.0 * BEARING_PER_PIXEL).abs() < EPSILON);
+        assert!((map.get_pitch() - -20.0 * PITCH_PER_PIXEL).abs() < EPSILON);
+
+        // Moves after the release leave the camera alone
+        handler.handle_event(&mut map, &InputEvent::CursorMoved(point(500.0, 300.0)));
+        assert!((map.get_bearing() - 50.0 * BEARING_PER_PIXEL).abs() < EPSILON);
+    }
+
+    #[test]
+    fn keys_ease_the_camera() {
+        let (mut map, mut handler, clock) = setup();
+        handler.handle_event(
+            &mut map,
+            &InputEvent::KeyPressed {
+                key: Key::Plus,
+                shift: false,
+            },
+        );
+        clock.advance(KEY_DURATION);
+        assert!(!map.update_transitions());
+        assert!((map.get_zoom() - 5.0).abs() < EPSILON);
+
+        handler.handle_event(
+            &mut map,
+            &InputEvent::KeyPressed {
+                key: Key::Right,
+                shift: true,
+            },
+        );
+        clock.advance(KEY_DURATION);
+        map.update_transitions();
+        assert!((map.get_bearing() - KEY_BEARING_STEP).abs() < EPSILON);
+    }
+}
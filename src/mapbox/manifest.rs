@@ -43,7 +43,7 @@ impl TaskResponder for ManifestImpl {
                 if self.obs.is_some() {
                     let obs = self.obs.as_ref().unwrap();
                     let url_template = self.data.tiles[0].clone();
-                    let avail_zooms = self.data.tilezooms.clone();
+                    let avail_zooms = self.data.available_zooms();
                     obs.lock().unwrap().on_manifest_loaded(
                         self.name.clone(),
                         url_template,
@@ -70,15 +70,17 @@ impl TaskResponder for ManifestImpl {
 //
 
 pub struct Manifest {
+    name: String,
     manifest_impl: Threadable<ManifestImpl>,
     resource: Resource,
 }
 
 impl Manifest {
     pub fn new(name: String) -> Manifest {
-        let manifest_impl = ThreadableNew(ManifestImpl::new(name));
+        let manifest_impl = ThreadableNew(ManifestImpl::new(name.clone()));
         let resource = Resource::new(4);
         Manifest {
+            name,
             manifest_impl,
             resource,
         }
@@ -126,6 +128,10 @@ impl Manifest {
         url
     }
 
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
     pub fn add_manifest_observer(&self, obs: Threadable<dyn ManifestObserver>) {
         self.manifest_impl
             .lock()
@@ -1,4 +1,6 @@
 // Ref: https://docs.mapbox.com/mapbox-gl-js/style-spec/#root-sources
+use super::transform::DEFAULT_MAX_ZOOM;
+
 use serde_json::Value;
 
 #[derive(Debug, Clone)]
@@ -49,12 +51,16 @@ impl ManifestModel {
             scheme: String::from(json_value["scheme"].as_str().unwrap_or_default()),
             minzoom: json_value["minzoom"].as_i64().unwrap_or_default(),
             maxzoom: json_value["maxzoom"].as_i64().unwrap_or_default(),
+            // Optional, without it every zoom from minzoom to maxzoom has tiles
             tilezooms: json_value["tilezooms"]
                 .as_array()
-                .unwrap()
-                .iter()
-                .map(|x| x.as_u64().unwrap_or_default())
-                .collect(),
+                .map(|zooms| {
+                    zooms
+                        .iter()
+                        .map(|x| x.as_u64().unwrap_or_default())
+                        .collect()
+                })
+                .unwrap_or_default(),
             tilejson: json_value["tilejson"]
                 .as_str()
                 .unwrap_or_default()
@@ -81,4 +87,27 @@ impl ManifestModel {
                 .collect(),
         }
     }
+
+    // Zooms the source has tiles for, ascending. A maxzoom of 0 is taken as
+    // not set.
+    pub fn available_zooms(&self) -> Vec<u64> {
+        let min_zoom = self.minzoom.max(0) as u64;
+        let max_zoom = if self.maxzoom > 0 {
+            self.maxzoom as u64
+        } else {
+            DEFAULT_MAX_ZOOM as u64
+        };
+        if self.tilezooms.is_empty() {
+            return (min_zoom..=max_zoom).collect();
+        }
+        let mut zooms: Vec<u64> = self
+            .tilezooms
+            .iter()
+            .filter(|zoom| (min_zoom..=max_zoom).contains(zoom))
+            .cloned()
+            .collect();
+        zooms.sort_unstable();
+        zooms.dedup();
+        zooms
+    }
 }
@@ -1,67 +1,720 @@
 // TODO: of course, we will use Trait
 
+use super::camera::{AnimationOptions, CameraOptions, CameraTransition, Clock, SystemClock};
+use super::common::map_error::{MapError, MapErrorTag};
 use super::config;
+use super::geo::{LatLng, LatLngBounds, ProjectedMeters, ScreenCoordinate};
+use super::glyph_atlas::{GlyphAtlas, GlyphPosition};
 use super::manifest_observer::ManifestObserver;
+use super::render_tile::RenderTile;
+use super::sprite_model::{IconAtlas, SpriteImage};
 use super::style::Style;
+use super::style_diff::StyleOperation;
+use super::style_model::StyleModel;
+use super::style_observer::StyleObserver;
+use super::symbol::localization;
+use super::symbol::placement::{PlacedSymbol, Placement};
+use super::symbol::symbol_layout::{SymbolInstance, SymbolLayer};
+use super::text::shaping;
+use super::tile_cache::{TileCacheLimits, TileCacheMetrics};
+use super::transform::{Transform, DEFAULT_MAX_ZOOM, DEFAULT_MIN_ZOOM};
+use super::utils::projection::ProjectionType;
+use super::vector_tile_id::{OverscaledTileID, UnwrappedTileID, VectorTileID};
 use super::vector_tile_manager::VectorTileManager;
+use super::vector_tile_model::VectorTileModel;
 use super::vector_tile_observer::VectorTileObserver;
 
 use super::common::types::{Threadable, ThreadableNew};
 
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
 pub struct Map {
     map_impl: Threadable<MapImpl>,
     style: Style,
+    placement: Placement,
+    // Symbol layers set_language rewrote, (the style's own text-field, what
+    // it was rewritten to) by layer id
+    localized_text_fields: HashMap<String, (Value, Value)>,
+    clock: Box<dyn Clock>,
+    transition: Option<CameraTransition>,
 }
 
 impl Map {
     pub fn new() -> Map {
+        let map_impl = ThreadableNew(MapImpl::new());
+
+        let mut style = Style::new();
+        style.add_manifest_observer(map_impl.clone());
+        style.add_style_observer(map_impl.clone());
+
         Map {
-            map_impl: ThreadableNew(MapImpl::new()),
+            map_impl,
+            style,
+            placement: Placement::new(),
+            localized_text_fields: HashMap::new(),
+            clock: Box::new(SystemClock::new()),
+            transition: None,
+        }
+    }
+
+    // Loading happens in the background, errors reach the style observers
+    pub fn load_map(&mut self, stylesheet_url: String) {
+        self.style.load_style_with_url(&stylesheet_url);
+    }
+
+    pub fn load_style_json(&mut self, json: &str) -> Result<(), MapError> {
+        self.style.load_style_json(json)
+    }
 
-            style: Style::new(),
+    pub fn load_style_file<P: AsRef<Path>>(&mut self, path: P) -> Result<(), MapError> {
+        let json = fs::read_to_string(path.as_ref()).map_err(|err| {
+            MapError::new(
+                MapErrorTag::File,
+                format!("{}: {}", path.as_ref().display(), err),
+            )
+        })?;
+        self.load_style_json(&json)
+    }
+
+    // Switches to another style document in place. Sources that are identical
+    // in both styles keep their loaded tiles, and only changed layers restyle.
+    pub fn set_style(&mut self, json: &str) -> Result<(), MapError> {
+        let style: Value = serde_json::from_str(json)
+            .map_err(|err| MapError::new(MapErrorTag::Style, err.to_string()))?;
+        let style_model = StyleModel::parse(style)?;
+
+        let operations = self.style.diff(&style_model);
+        if operations.contains(&StyleOperation::SetStyle) {
+            // Nothing to keep, loaded like any new style
+            self.load_style_json(json)?;
+        } else {
+            for operation in operations {
+                self.apply_style_operation(operation)?;
+            }
+            self.style.set_root_properties(&style_model);
+            self.map_impl
+                .lock()
+                .unwrap()
+                .set_style_zoom_range(&style_model);
         }
+        self.update_placement();
+        Ok(())
+    }
+
+    fn apply_style_operation(&mut self, operation: StyleOperation) -> Result<(), MapError> {
+        match operation {
+            StyleOperation::RemoveLayer(layer_id) => self.remove_layer(&layer_id),
+            StyleOperation::AddLayer { layer, before_id } => {
+                self.add_layer(layer, before_id.as_deref())
+            }
+            StyleOperation::MoveLayer {
+                layer_id,
+                before_id,
+            } => self.move_layer(&layer_id, before_id.as_deref()),
+            StyleOperation::SetPaintProperty {
+                layer_id,
+                name,
+                value,
+            } => self.set_paint_property(&layer_id, &name, value),
+            StyleOperation::SetLayoutProperty {
+                layer_id,
+                name,
+                value,
+            } => self.set_layout_property(&layer_id, &name, value),
+            StyleOperation::SetFilter { layer_id, filter } => self.set_filter(&layer_id, filter),
+            StyleOperation::RemoveSource(source_id) => self.remove_source(&source_id),
+            StyleOperation::AddSource { source_id, source } => self.add_source(&source_id, source),
+            StyleOperation::SetSprite(sprite_url) => {
+                self.style.set_sprite_url(sprite_url);
+                Ok(())
+            }
+            StyleOperation::SetGlyphs(glyph_template_url) => {
+                self.style.set_glyph_template_url(glyph_template_url);
+                Ok(())
+            }
+            StyleOperation::SetStyle => unreachable!("set_style reloads instead"),
+        }
+    }
+
+    // Camera
+
+    pub fn get_transform(&self) -> Transform {
+        self.map_impl.lock().unwrap().get_transform()
     }
 
-    pub fn load_map(&mut self, stylesheet_url: &'static str) {
-        let obs = self.map_impl.clone();
-        self.style.add_manifest_observer(obs);
-        self.style.load_style_with_url(stylesheet_url);
+    pub fn set_center(&mut self, center: LatLng) {
+        self.cancel_transitions();
+        self.map_impl.lock().unwrap().set_center(center);
+        self.update_placement();
     }
 
-    pub fn set_center(&mut self, lat: f32, long: f32) {
-        // TODO: do some bounds checking
-        self.map_impl.lock().unwrap().set_center(lat, long);
+    pub fn get_center(&self) -> LatLng {
+        self.get_transform().get_center()
     }
 
-    pub fn get_center_point_tile_xy(&self) -> (f32, f32) {
-        self.map_impl.lock().unwrap().get_center_point_tile_xy()
+    pub fn get_covered_tiles(&self) -> Vec<UnwrappedTileID> {
+        self.map_impl.lock().unwrap().get_covered_tiles()
+    }
+
+    pub fn get_covered_source_tiles(&self, source_id: &str) -> Vec<OverscaledTileID> {
+        self.map_impl
+            .lock()
+            .unwrap()
+            .get_covered_source_tiles(source_id)
+    }
+
+    // What to draw for the source, missing tiles filled in from loaded ones
+    pub fn get_render_tiles(&self, source_id: &str) -> Vec<RenderTile> {
+        self.map_impl.lock().unwrap().get_render_tiles(source_id)
+    }
+
+    pub fn get_tile_cache_limits(&self) -> TileCacheLimits {
+        self.map_impl.lock().unwrap().get_tile_cache_limits()
+    }
+
+    // Out of view tiles are evicted, least recently used first, until the
+    // cache is back within `limits`
+    pub fn set_tile_cache_limits(&mut self, limits: TileCacheLimits) {
+        self.map_impl.lock().unwrap().set_tile_cache_limits(limits);
+    }
+
+    pub fn get_tile_cache_metrics(&self) -> TileCacheMetrics {
+        self.map_impl.lock().unwrap().get_tile_cache_metrics()
+    }
+
+    // Renderers report the GPU memory they built for a tile, so that it
+    // counts towards the cache's byte limit
+    pub fn set_tile_buffer_size(
+        &self,
+        source_id: &str,
+        vector_tile_id: &VectorTileID,
+        bytes: usize,
+    ) {
+        self.map_impl
+            .lock()
+            .unwrap()
+            .set_tile_buffer_size(source_id, vector_tile_id, bytes);
     }
 
-    pub fn get_covered_tiles_coords(&self) -> Vec<(f32, f32)> {
-        self.map_impl.lock().unwrap().get_covered_tiles_coords()
+    pub fn get_zoom(&self) -> f64 {
+        self.get_transform().get_zoom()
     }
 
-    pub fn get_zoom(&self) -> f32 {
-        self.map_impl.lock().unwrap().get_zoom()
+    pub fn get_source_ids(&self) -> Vec<String> {
+        self.map_impl.lock().unwrap().get_source_ids()
     }
 
-    pub fn set_zoom(&mut self, zoom: f32) {
-        // TODO: do some bounds checking
+    pub fn set_zoom(&mut self, zoom: f64) {
+        self.cancel_transitions();
         self.map_impl.lock().unwrap().set_zoom(zoom);
+        self.update_placement();
+    }
+
+    pub fn get_bearing(&self) -> f64 {
+        self.get_transform().get_bearing()
+    }
+
+    // Degrees clockwise from north
+    pub fn set_bearing(&mut self, bearing: f64) {
+        self.cancel_transitions();
+        self.map_impl.lock().unwrap().set_bearing(bearing);
+        self.update_placement();
+    }
+
+    pub fn get_pitch(&self) -> f64 {
+        self.get_transform().get_pitch()
+    }
+
+    // Degrees, capped at transform::MAX_PITCH
+    pub fn set_pitch(&mut self, pitch: f64) {
+        self.cancel_transitions();
+        self.map_impl.lock().unwrap().set_pitch(pitch);
+        self.update_placement();
+    }
+
+    pub fn get_min_zoom(&self) -> f64 {
+        self.get_transform().get_min_zoom()
+    }
+
+    pub fn get_max_zoom(&self) -> f64 {
+        self.get_transform().get_max_zoom()
+    }
+
+    // None falls back to the style's minzoom, then to the lowest zoom the
+    // sources have tiles for
+    pub fn set_min_zoom(&mut self, min_zoom: Option<f64>) -> Result<(), MapError> {
+        let (_, max_zoom) = self.map_impl.lock().unwrap().get_zoom_range();
+        self.set_zoom_range((min_zoom, max_zoom))
+    }
+
+    // None falls back to the style's maxzoom, then to the highest zoom the
+    // sources have tiles for
+    pub fn set_max_zoom(&mut self, max_zoom: Option<f64>) -> Result<(), MapError> {
+        let (min_zoom, _) = self.map_impl.lock().unwrap().get_zoom_range();
+        self.set_zoom_range((min_zoom, max_zoom))
+    }
+
+    fn set_zoom_range(&mut self, zoom_range: (Option<f64>, Option<f64>)) -> Result<(), MapError> {
+        let min_zoom = zoom_range.0.unwrap_or(DEFAULT_MIN_ZOOM);
+        let max_zoom = zoom_range.1.unwrap_or(DEFAULT_MAX_ZOOM);
+        if !(DEFAULT_MIN_ZOOM..=DEFAULT_MAX_ZOOM).contains(&min_zoom)
+            || !(DEFAULT_MIN_ZOOM..=DEFAULT_MAX_ZOOM).contains(&max_zoom)
+            || min_zoom > max_zoom
+        {
+            return Err(MapError::new(
+                MapErrorTag::Camera,
+                format!(
+                    "zoom range {}..{} must be ordered and within {}..{}",
+                    min_zoom, max_zoom, DEFAULT_MIN_ZOOM, DEFAULT_MAX_ZOOM
+                ),
+            ));
+        }
+        self.map_impl.lock().unwrap().set_zoom_range(zoom_range);
+        self.update_placement();
+        Ok(())
+    }
+
+    pub fn get_max_bounds(&self) -> Option<LatLngBounds> {
+        self.get_transform().get_max_bounds()
+    }
+
+    // Keeps the viewport inside `max_bounds`, None lifts the restriction
+    pub fn set_max_bounds(&mut self, max_bounds: Option<LatLngBounds>) -> Result<(), MapError> {
+        if let Some(max_bounds) = max_bounds {
+            if !max_bounds.is_valid() {
+                return Err(MapError::new(
+                    MapErrorTag::Camera,
+                    format!(
+                        "max bounds {:?} must have south below north and west left of east",
+                        max_bounds
+                    ),
+                ));
+            }
+        }
+        self.map_impl.lock().unwrap().set_max_bounds(max_bounds);
+        self.update_placement();
+        Ok(())
+    }
+
+    pub fn get_projection(&self) -> ProjectionType {
+        self.get_transform().get_projection()
+    }
+
+    // Has to match how the sources are tiled, the center stays where it is
+    pub fn set_projection(&mut self, projection: ProjectionType) {
+        self.cancel_transitions();
+        self.map_impl.lock().unwrap().set_projection(projection);
+        self.update_placement();
+    }
+
+    // Screen pixel of a place, taking bearing and pitch into account. It may
+    // lie outside of the viewport.
+    pub fn pixel_for_latlng(&self, latlng: LatLng) -> ScreenCoordinate {
+        self.get_transform().latlng_to_screen(&latlng)
+    }
+
+    // Place on the ground under a screen pixel, e.g. the one clicked. Returns
+    // Option<LatLng>: None for pixels at or above the horizon, which only a
+    // pitched camera has, as there is no ground under them.
+    pub fn latlng_for_pixel(&self, point: ScreenCoordinate) -> Option<LatLng> {
+        self.get_transform().screen_to_latlng(point)
+    }
+
+    pub fn projected_meters_for_latlng(&self, latlng: LatLng) -> ProjectedMeters {
+        ProjectedMeters::from_latlng(&latlng)
+    }
+
+    pub fn latlng_for_projected_meters(&self, meters: ProjectedMeters) -> LatLng {
+        meters.to_latlng()
+    }
+
+    // Viewport in pixels
+    pub fn set_size(&mut self, size: (u32, u32)) {
+        self.map_impl.lock().unwrap().set_size(size);
+        self.update_placement();
+    }
+
+    // Animations
+
+    // Transitions are timed with this clock, the system clock by default
+    pub fn set_clock(&mut self, clock: Box<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    // Moves the camera at once
+    pub fn jump_to(&mut self, camera: CameraOptions) {
+        self.cancel_transitions();
+        self.apply_camera(&camera);
+    }
+
+    pub fn ease_to(&mut self, camera: CameraOptions, animation: AnimationOptions) {
+        let transition =
+            CameraTransition::ease(&self.get_transform(), &camera, animation, self.clock.now());
+        self.start_transition(transition);
+    }
+
+    pub fn fly_to(&mut self, camera: CameraOptions, animation: AnimationOptions) {
+        let transition =
+            CameraTransition::fly(&self.get_transform(), &camera, animation, self.clock.now());
+        self.start_transition(transition);
+    }
+
+    pub fn is_in_transition(&self) -> bool {
+        self.transition.is_some()
+    }
+
+    // Stops the running transition where it is
+    pub fn cancel_transitions(&mut self) {
+        if let Some(transition) = self.transition.take() {
+            transition.cancel();
+        }
+    }
+
+    // Moves the camera to where the running transition is by now. Renderers
+    // call it every frame, it returns false once there is nothing to animate.
+    pub fn update_transitions(&mut self) -> bool {
+        let transition = match self.transition.take() {
+            Some(transition) => transition,
+            None => return false,
+        };
+        let now = self.clock.now();
+        self.apply_camera(&transition.frame(now));
+        if transition.is_done(now) {
+            transition.finish();
+            return false;
+        }
+        self.transition = Some(transition);
+        true
+    }
+
+    fn start_transition(&mut self, transition: CameraTransition) {
+        self.cancel_transitions();
+        self.transition = Some(transition);
+        self.update_transitions();
+    }
+
+    fn apply_camera(&mut self, camera: &CameraOptions) {
+        self.map_impl.lock().unwrap().jump_to(camera);
+        self.update_placement();
     }
 
     // User Interactions
 
-    pub fn pan(&mut self, _delta_lat: f64, _delta_long: f64) {
-        // TODO: do some bounds checking
+    // Moves the center by the given degrees, loading the tiles coming into view
+    pub fn pan(&mut self, delta_lat: f64, delta_long: f64) {
+        let center = self.get_center();
+        self.set_center(LatLng::new(
+            center.lat + delta_lat,
+            center.long + delta_long,
+        ));
+    }
+
+    pub fn zoom(&mut self, delta_zoom: f64) {
+        let zoom = self.get_zoom();
+        self.set_zoom(zoom + delta_zoom);
+    }
+
+    // Icons of the style's sprite, empty until the sprite has loaded
+    pub fn get_icon_atlas(&self) -> Threadable<IconAtlas> {
+        self.style.get_icon_atlas()
+    }
+
+    pub fn get_sprite_image(&self, name: &str) -> Option<SpriteImage> {
+        self.style.get_sprite_image(name)
+    }
+
+    // Glyph ranges are fetched in the background, `is_glyphs_ready` tells
+    // when all of those `text` needs have arrived.
+    pub fn load_glyphs(&self, fontstack: &[String], text: &str) {
+        self.style.load_glyphs(fontstack, text);
+    }
+
+    pub fn is_glyphs_ready(&self, fontstack: &[String], text: &str) -> bool {
+        self.style.is_glyphs_ready(fontstack, text)
+    }
+
+    pub fn get_glyph(&self, fontstack: &[String], codepoint: u32) -> Option<GlyphPosition> {
+        self.style.get_glyph(fontstack, codepoint)
+    }
+
+    pub fn get_glyph_atlas(&self) -> Threadable<GlyphAtlas> {
+        self.style.get_glyph_atlas()
+    }
+
+    // Runtime Style Mutations
+    // Each change goes straight into the StyleModel and only invalidates the
+    // renderables of the source the touched layer draws from.
+
+    pub fn add_layer(&mut self, layer: Value, before_id: Option<&str>) -> Result<(), MapError> {
+        let source_id = layer["source"].as_str().map(|source| source.to_owned());
+        self.style.add_layer(layer, before_id)?;
+        self.invalidate_source(source_id);
+        Ok(())
+    }
+
+    pub fn remove_layer(&mut self, layer_id: &str) -> Result<(), MapError> {
+        let layer = self.style.remove_layer(layer_id)?;
+        self.invalidate_source(layer["source"].as_str().map(|source| source.to_owned()));
+        Ok(())
+    }
+
+    // Moves the layer right below `before_id`, or to the top if it is `None`
+    pub fn move_layer(&mut self, layer_id: &str, before_id: Option<&str>) -> Result<(), MapError> {
+        self.style.move_layer(layer_id, before_id)?;
+        self.invalidate_layer(layer_id);
+        Ok(())
+    }
+
+    pub fn add_source(&mut self, source_id: &str, source: Value) -> Result<(), MapError> {
+        // Registered before the style requests the manifest, which would be
+        // dropped if it came back for a source the map doesn't know yet
+        let is_vector = source["type"].as_str() == Some("vector");
+        let is_new = !self.map_impl.lock().unwrap().has_source(source_id);
+        if is_vector && is_new {
+            self.map_impl
+                .lock()
+                .unwrap()
+                .add_source(source_id.to_owned());
+        }
+        let added = self.style.add_source(source_id, source);
+        if added.is_err() && is_vector && is_new {
+            self.map_impl.lock().unwrap().remove_source(source_id);
+        }
+        added
+    }
+
+    pub fn remove_source(&mut self, source_id: &str) -> Result<(), MapError> {
+        self.style.remove_source(source_id)?;
+        self.map_impl.lock().unwrap().remove_source(source_id);
+        Ok(())
+    }
+
+    pub fn get_paint_property(&self, layer_id: &str, name: &str) -> Option<Value> {
+        self.style.get_paint_property(layer_id, name)
+    }
+
+    pub fn set_paint_property(
+        &mut self,
+        layer_id: &str,
+        name: &str,
+        value: Value,
+    ) -> Result<(), MapError> {
+        self.style.set_paint_property(layer_id, name, value)?;
+        self.invalidate_layer(layer_id);
+        Ok(())
+    }
+
+    pub fn get_layout_property(&self, layer_id: &str, name: &str) -> Option<Value> {
+        self.style.get_layout_property(layer_id, name)
+    }
+
+    pub fn set_layout_property(
+        &mut self,
+        layer_id: &str,
+        name: &str,
+        value: Value,
+    ) -> Result<(), MapError> {
+        self.style.set_layout_property(layer_id, name, value)?;
+        self.invalidate_layer(layer_id);
+        Ok(())
+    }
+
+    pub fn get_filter(&self, layer_id: &str) -> Option<Value> {
+        self.style.get_filter(layer_id)
+    }
+
+    pub fn set_filter(&mut self, layer_id: &str, filter: Option<Value>) -> Result<(), MapError> {
+        self.style.set_filter(layer_id, filter)?;
+        self.invalidate_layer(layer_id);
+        Ok(())
+    }
+
+    // Shows labels in the first of `languages` (e.g. ["de", "en"]) a feature
+    // has a name in, falling back to its local `name`. An empty list goes back
+    // to the text-fields of the style. Only symbols are laid out again, the
+    // tiles stay as they are.
+    pub fn set_language(&mut self, languages: &[&str]) -> Result<(), MapError> {
+        let languages: Vec<String> = languages
+            .iter()
+            .map(|language| language.to_string())
+            .collect();
+        let mut changed = false;
+        for layer in self.style.get_layers() {
+            if layer["type"].as_str() != Some("symbol") {
+                continue;
+            }
+            let layer_id = match layer["id"].as_str() {
+                Some(layer_id) => layer_id,
+                None => continue,
+            };
+            let current = &layer["layout"]["text-field"];
+            // Localized from what the style shipped, unless the text-field
+            // changed since it was last localized, e.g. by a new style
+            let original = match self.localized_text_fields.get(layer_id) {
+                Some((original, localized)) if localized == current => original.clone(),
+                _ => current.clone(),
+            };
+            let text_field = if languages.is_empty() {
+                self.localized_text_fields.remove(layer_id);
+                original
+            } else {
+                match localization::localize_text_field(&original, &languages) {
+                    Some(text_field) => {
+                        self.localized_text_fields
+                            .insert(layer_id.to_owned(), (original, text_field.clone()));
+                        text_field
+                    }
+                    None => continue,
+                }
+            };
+            if text_field != *current {
+                self.style
+                    .set_layout_property(layer_id, "text-field", text_field)?;
+                self.placement.invalidate_layer(layer_id);
+                changed = true;
+            }
+        }
+        if changed {
+            self.update_placement();
+        }
+        Ok(())
+    }
+
+    fn invalidate_layer(&mut self, layer_id: &str) {
+        self.placement.invalidate_layer(layer_id);
+        self.invalidate_source(self.style.get_layer_source(layer_id));
     }
 
-    pub fn zoom(&mut self, _delta_zoom: f64) {
-        // TODO: do some bounds checking
+    // Layers without a source (e.g. background) have no tile data to invalidate
+    fn invalidate_source(&mut self, source_id: Option<String>) {
+        if let Some(source_id) = source_id {
+            self.placement.invalidate_source(&source_id);
+            self.map_impl.lock().unwrap().invalidate_source(&source_id);
+        }
+    }
+
+    // Symbols
+
+    // Lays out the symbols of newly loaded tiles and places all of them again.
+    // Runs on camera changes, renderers call it when tiles have arrived.
+    // Returns false while some tiles still wait for their glyphs, call it
+    // again later to get their labels in.
+    pub fn update_placement(&mut self) -> bool {
+        let zoom = self.get_zoom() as f32;
+        let layers: Vec<SymbolLayer> = self
+            .style
+            .get_layers()
+            .iter()
+            .filter_map(SymbolLayer::from_layer)
+            .filter(|layer| layer.is_visible_at(zoom))
+            .collect();
+        // Sources may be at different zooms, so tiles are looked up per source
+        let mut tile_ids: HashMap<String, Vec<UnwrappedTileID>> = HashMap::new();
+        for layer in layers.iter() {
+            tile_ids.entry(layer.source.clone()).or_insert_with(|| {
+                self.get_covered_source_tiles(&layer.source)
+                    .iter()
+                    .map(OverscaledTileID::to_unwrapped)
+                    .collect()
+            });
+        }
+        self.placement.retain_tiles(&tile_ids);
+        let mut complete = true;
+
+        for layer in layers.iter() {
+            for tile_id in tile_ids[&layer.source]
+                .iter()
+                .map(|tile_id| &tile_id.canonical)
+            {
+                let tile = self
+                    .map_impl
+                    .lock()
+                    .unwrap()
+                    .get_loaded_tile(&layer.source, tile_id);
+                let tile = match tile {
+                    Some(tile) => tile,
+                    None => continue,
+                };
+                let laid_out = self
+                    .placement
+                    .get_bucket(&layer.id)
+                    .is_some_and(|bucket| bucket.contains(tile_id, &tile));
+                if laid_out {
+                    continue;
+                }
+                match self.build_symbol_instances(layer, *tile_id, &tile) {
+                    Some(instances) => {
+                        self.placement
+                            .get_bucket_mut(layer)
+                            .insert(*tile_id, tile, instances);
+                    }
+                    None => complete = false,
+                }
+            }
+        }
+
+        let transform = self.get_transform();
+        self.placement.place(&layers, &tile_ids, &transform);
+        complete
+    }
+
+    // None until all glyphs the tile's labels need have loaded
+    fn build_symbol_instances(
+        &self,
+        layer: &SymbolLayer,
+        tile_id: VectorTileID,
+        tile: &VectorTileModel,
+    ) -> Option<Vec<SymbolInstance>> {
+        let features = layer.get_features(tile);
+        let text: String = features
+            .iter()
+            .filter_map(|feature| feature.text.as_ref())
+            .map(|text| text.as_str())
+            .collect();
+        self.style.load_glyphs(&layer.text_font, &text);
+        if !self.style.is_glyphs_ready(&layer.text_font, &text) {
+            return None;
+        }
+
+        let get_glyph = |c: char| self.style.get_glyph(&layer.text_font, c as u32);
+        let mut instances = vec![];
+        for feature in features {
+            let shaping = feature
+                .text
+                .as_ref()
+                .and_then(|text| shaping::shape_text(text, &layer.text_layout, &get_glyph));
+            let icon = feature
+                .icon
+                .as_ref()
+                .and_then(|icon| self.style.get_sprite_image(icon));
+            let feature = Arc::new(feature);
+            for anchor in layer.get_anchors(&feature, shaping.as_ref(), icon.as_ref()) {
+                instances.extend(SymbolInstance::new(
+                    tile_id,
+                    feature.clone(),
+                    anchor,
+                    shaping.clone(),
+                    icon.clone(),
+                ));
+            }
+        }
+        Some(instances)
+    }
+
+    pub fn get_placed_symbols(&self) -> &[PlacedSymbol] {
+        self.placement.get_placed_symbols()
     }
 
     // Observsers
 
+    pub fn add_style_observer(&mut self, style_obs: Threadable<dyn StyleObserver>) {
+        self.style.add_style_observer(style_obs);
+    }
+
     pub fn add_vector_tile_observer(
         &mut self,
         vector_tile_obs: Threadable<dyn VectorTileObserver>,
@@ -75,69 +728,268 @@ impl Map {
 
 // ManifestObserver
 
+// Where a source's tiles come from, as its manifest says
+struct SourceManifest {
+    url_template: String,
+    avail_zooms: Vec<u64>, // ascending
+}
+
+impl SourceManifest {
+    // Deepest zoom with tiles not past `tile_zoom`. Tiles of it get
+    // overscaled when the camera is deeper. None below the source's zooms.
+    fn ideal_zoom(&self, tile_zoom: u32) -> Option<u32> {
+        self.avail_zooms
+            .iter()
+            .rev()
+            .find(|zoom| **zoom <= tile_zoom as u64)
+            .map(|zoom| *zoom as u32)
+    }
+}
+
 struct MapImpl {
-    zoom: f32,
-    center: (f32, f32), // (lat, long) of the map center
+    transform: Transform,
+    // Zoom limits set through Map, then the style's. (min, max)
+    zoom_range: (Option<f64>, Option<f64>),
+    style_zoom_range: (Option<f64>, Option<f64>),
+    source_ids: Vec<String>, // in stylesheet order
+    manifests: HashMap<String, SourceManifest>,
     vector_tiles: VectorTileManager,
 }
 
 impl MapImpl {
     pub fn new() -> MapImpl {
+        let mut transform = Transform::new((
+            crate::config::GL_VIEWPORT_WIDTH,
+            crate::config::GL_VIEWPORT_HEIGHT,
+        ));
+        transform.set_zoom(config::MAP_DEFAULT_ZOOM_LEVEL as f64);
         MapImpl {
-            zoom: config::MAP_DEFAULT_ZOOM_LEVEL,
-            center: (0.0, 0.0),
+            transform,
+            zoom_range: (None, None),
+            style_zoom_range: (None, None),
+            source_ids: vec![],
+            manifests: HashMap::new(),
             vector_tiles: VectorTileManager::new(),
         }
     }
 
-    pub fn get_center_point_tile_xy(&self) -> (f32, f32) {
+    pub fn get_transform(&self) -> Transform {
+        self.transform
+    }
+
+    // Tiles of the tile zoom covering the view
+    pub fn get_covered_tiles(&self) -> Vec<UnwrappedTileID> {
         self.vector_tiles
-            .get_center_point_tile_xy(self.center, self.zoom)
+            .get_covered_tiles(&self.transform, self.transform.get_tile_zoom())
     }
 
-    pub fn get_covered_tiles_coords(&self) -> Vec<(f32, f32)> {
-        let screen_size = (
-            crate::config::GL_VIEWPORT_WIDTH,
-            crate::config::GL_VIEWPORT_HEIGHT,
-        );
-        let covered_tile_ids =
-            self.vector_tiles
-                .get_covered_tiles(&self.center, self.zoom, &screen_size);
-        covered_tile_ids
+    // Tiles of the source covering the view, at the source's ideal zoom and
+    // overscaled to the tile zoom
+    pub fn get_covered_source_tiles(&self, source_id: &str) -> Vec<OverscaledTileID> {
+        let tile_zoom = self.transform.get_tile_zoom();
+        let zoom = match self
+            .manifests
+            .get(source_id)
+            .and_then(|manifest| manifest.ideal_zoom(tile_zoom))
+        {
+            Some(zoom) => zoom,
+            None => return vec![], // Manifest still loading, or no tiles this far out
+        };
+        self.vector_tiles
+            .get_covered_tiles(&self.transform, zoom)
             .iter()
-            .map(|v| (v.x as f32, v.y as f32))
+            .map(|tile_id| tile_id.overscale_to(tile_zoom))
             .collect()
     }
 
-    pub fn set_center(&mut self, lat: f32, long: f32) {
-        // TODO: do some bounds checking
-        self.center = (lat, long);
+    pub fn get_render_tiles(&self, source_id: &str) -> Vec<RenderTile> {
+        self.vector_tiles
+            .get_render_tiles(source_id, &self.get_covered_source_tiles(source_id))
     }
 
-    pub fn get_zoom(&self) -> f32 {
-        self.zoom
+    pub fn get_tile_cache_limits(&self) -> TileCacheLimits {
+        self.vector_tiles.get_tile_cache_limits()
     }
 
-    pub fn set_zoom(&mut self, zoom: f32) {
-        // TODO: do some bounds checking
-        self.zoom = zoom;
-        self.vector_tiles.set_zoom(zoom);
+    pub fn set_tile_cache_limits(&self, limits: TileCacheLimits) {
+        self.vector_tiles.set_tile_cache_limits(limits);
     }
 
-    fn load_tiles(&mut self, vector_name: String, url_template: String) {
-        let screen_size = (
-            crate::config::GL_VIEWPORT_WIDTH,
-            crate::config::GL_VIEWPORT_HEIGHT,
-        );
-        self.vector_tiles.load_covered_tiles(
-            vector_name,
-            &self.center,
-            self.zoom,
-            screen_size,
-            url_template,
+    pub fn get_tile_cache_metrics(&self) -> TileCacheMetrics {
+        self.vector_tiles.get_tile_cache_metrics()
+    }
+
+    pub fn set_tile_buffer_size(
+        &self,
+        source_id: &str,
+        vector_tile_id: &VectorTileID,
+        bytes: usize,
+    ) {
+        self.vector_tiles
+            .set_tile_buffer_size(source_id, vector_tile_id, bytes);
+    }
+
+    // Camera setters request the tiles coming into view
+
+    pub fn set_center(&mut self, center: LatLng) {
+        self.transform.set_center(center);
+        self.load_all_tiles();
+    }
+
+    pub fn set_zoom(&mut self, zoom: f64) {
+        self.transform.set_zoom(zoom);
+        self.load_all_tiles();
+    }
+
+    pub fn set_bearing(&mut self, bearing: f64) {
+        self.transform.set_bearing(bearing);
+        self.load_all_tiles();
+    }
+
+    pub fn set_pitch(&mut self, pitch: f64) {
+        self.transform.set_pitch(pitch);
+        self.load_all_tiles();
+    }
+
+    pub fn set_size(&mut self, size: (u32, u32)) {
+        self.transform.set_size(size);
+        self.load_all_tiles();
+    }
+
+    pub fn set_zoom_range(&mut self, zoom_range: (Option<f64>, Option<f64>)) {
+        self.zoom_range = zoom_range;
+        self.update_zoom_range();
+    }
+
+    // A limit of 0 in the style means there is none
+    pub fn set_style_zoom_range(&mut self, style_model: &StyleModel) {
+        let limit = |zoom: i64| if zoom > 0 { Some(zoom as f64) } else { None };
+        self.style_zoom_range = (limit(style_model.min_zoom), limit(style_model.max_zoom));
+        self.update_zoom_range();
+    }
+
+    pub fn get_zoom_range(&self) -> (Option<f64>, Option<f64>) {
+        self.zoom_range
+    }
+
+    pub fn set_max_bounds(&mut self, max_bounds: Option<LatLngBounds>) {
+        self.transform.set_max_bounds(max_bounds);
+        self.load_all_tiles();
+    }
+
+    pub fn set_projection(&mut self, projection: ProjectionType) {
+        self.transform.set_projection(projection);
+        self.load_all_tiles();
+    }
+
+    // Limits set through Map win over the style's, which win over the lowest
+    // zoom the sources have tiles for. There is no such limit on the max zoom,
+    // sources get overscaled past their deepest tiles.
+    fn update_zoom_range(&mut self) {
+        let source_zooms = || {
+            self.manifests
+                .values()
+                .flat_map(|manifest| manifest.avail_zooms.iter().map(|zoom| *zoom as f64))
+        };
+        let min_zoom = self
+            .zoom_range
+            .0
+            .or(self.style_zoom_range.0)
+            .or_else(|| source_zooms().reduce(f64::min))
+            .unwrap_or(DEFAULT_MIN_ZOOM);
+        let max_zoom = self
+            .zoom_range
+            .1
+            .or(self.style_zoom_range.1)
+            .unwrap_or(DEFAULT_MAX_ZOOM);
+        self.transform.set_zoom_range(min_zoom, max_zoom);
+        self.load_all_tiles();
+    }
+
+    pub fn jump_to(&mut self, camera: &CameraOptions) {
+        if let Some(center) = camera.center {
+            self.transform.set_center(center);
+        }
+        if let Some(zoom) = camera.zoom {
+            self.transform.set_zoom(zoom);
+        }
+        if let Some(bearing) = camera.bearing {
+            self.transform.set_bearing(bearing);
+        }
+        if let Some(pitch) = camera.pitch {
+            self.transform.set_pitch(pitch);
+        }
+        self.load_all_tiles();
+    }
+
+    pub fn get_source_ids(&self) -> Vec<String> {
+        self.source_ids.clone()
+    }
+
+    pub fn has_source(&self, source_id: &str) -> bool {
+        self.source_ids.iter().any(|id| id == source_id)
+    }
+
+    pub fn add_source(&mut self, source_id: String) {
+        if !self.source_ids.contains(&source_id) {
+            self.source_ids.push(source_id.clone());
+        }
+        self.vector_tiles.register_source(source_id);
+    }
+
+    pub fn remove_source(&mut self, source_id: &str) {
+        self.source_ids.retain(|id| id != source_id);
+        self.vector_tiles.unregister_source(source_id);
+        if self.manifests.remove(source_id).is_some() {
+            self.update_zoom_range();
+        }
+    }
+
+    pub fn invalidate_source(&self, source_id: &str) {
+        self.vector_tiles.invalidate_source(source_id);
+    }
+
+    pub fn get_loaded_tile(
+        &self,
+        source_id: &str,
+        vector_tile_id: &VectorTileID,
+    ) -> Option<Arc<VectorTileModel>> {
+        self.vector_tiles.get_loaded_tile(source_id, vector_tile_id)
+    }
+
+    // Tiles already loaded or on their way are skipped
+    fn load_tiles(&self, source_id: &str) {
+        let manifest = match self.manifests.get(source_id) {
+            Some(manifest) => manifest,
+            None => return, // Manifest still loading
+        };
+        // Copies of the world share their tiles
+        let tile_ids: Vec<VectorTileID> = self
+            .get_covered_source_tiles(source_id)
+            .iter()
+            .map(|tile_id| tile_id.canonical)
+            .collect();
+        // Tiles standing in for the ones still loading stay cached too
+        let in_use = self
+            .get_render_tiles(source_id)
+            .iter()
+            .map(|render_tile| render_tile.tile_id.canonical)
+            .chain(tile_ids.iter().cloned())
+            .collect();
+        self.vector_tiles.set_tiles_in_use(source_id, in_use);
+        self.vector_tiles.load_tiles(
+            source_id.to_owned(),
+            &tile_ids,
+            manifest.url_template.clone(),
         );
     }
 
+    fn load_all_tiles(&self) {
+        for source_id in self.source_ids.iter() {
+            self.load_tiles(source_id);
+        }
+    }
+
     pub fn add_vector_tile_observer(
         &mut self,
         vector_tile_obs: Threadable<dyn VectorTileObserver>,
@@ -148,17 +1000,217 @@ impl MapImpl {
 
 impl ManifestObserver for MapImpl {
     fn on_manifest_loaded(&mut self, name: String, url_template: String, avail_zooms: Vec<u64>) {
-        let current_zoom = self.get_zoom();
+        let current_zoom = self.transform.get_tile_zoom();
         println!(
             "== Manifest URL: {}, Curr Zoom: {}, Avail zooms: {:?}",
             url_template, current_zoom, avail_zooms
         );
-        if avail_zooms.contains(&(current_zoom as u64)) {
-            self.load_tiles(name.to_uppercase(), url_template);
+        if !self.source_ids.contains(&name) {
+            // Not a vector source, or removed while its manifest was loading
+            return;
         }
+        self.manifests.insert(
+            name.clone(),
+            SourceManifest {
+                url_template,
+                avail_zooms,
+            },
+        );
+        // Also loads the tiles of the source
+        self.update_zoom_range();
     }
 
     fn on_manifest_failed(&self, name: String) {
         println!("===== map heard it failed {}", name);
     }
 }
+
+impl StyleObserver for MapImpl {
+    fn on_style_loaded(&mut self, style_model: &StyleModel) {
+        // Sources of the previous style go, along with their tiles
+        let source_ids = style_model.vector_source_ids();
+        let removed: Vec<String> = self
+            .source_ids
+            .iter()
+            .filter(|source_id| !source_ids.contains(source_id))
+            .cloned()
+            .collect();
+        for source_id in removed.iter() {
+            self.remove_source(source_id);
+        }
+        for source_id in source_ids {
+            self.add_source(source_id);
+        }
+        self.set_style_zoom_range(style_model);
+    }
+
+    fn on_style_failed(&mut self, map_error: &MapError) {
+        println!("===== map heard style failed {}", map_error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mapbox::camera::CameraOptions;
+    use serde_json::json;
+
+    const SIZE: (u32, u32) = (800, 600);
+
+    fn map_at(zoom: f64, bearing: f64, pitch: f64) -> Map {
+        let mut map = Map::new();
+        map.set_size(SIZE);
+        map.jump_to(CameraOptions {
+            center: Some(LatLng::new(37.77, -122.42)),
+            zoom: Some(zoom),
+            bearing: Some(bearing),
+            pitch: Some(pitch),
+        });
+        map
+    }
+
+    // Zoomed in far enough for the pitched view to end short of the poles,
+    // where latitudes are clamped and pixels can't make it back
+    fn cameras() -> Vec<Map> {
+        let mut maps = vec![];
+        for zoom in [4.0, 8.0, 16.25].iter() {
+            for bearing in [0.0, 45.0, -120.0].iter() {
+                for pitch in [0.0, 30.0, 60.0].iter() {
+                    maps.push(map_at(*zoom, *bearing, *pitch));
+                }
+            }
+        }
+        maps
+    }
+
+    // Corners, edges and the middle of the viewport
+    fn pixels() -> Vec<ScreenCoordinate> {
+        let (width, height) = (SIZE.0 as f64, SIZE.1 as f64);
+        let mut pixels = vec![];
+        for x in [0.0, 0.25, 0.5, 0.9, 1.0].iter() {
+            for y in [0.0, 0.1, 0.5, 0.75, 1.0].iter() {
+                pixels.push(ScreenCoordinate::new(x * width, y * height));
+            }
+        }
+        pixels
+    }
+
+    #[test]
+    fn center_pixel_is_center() {
+        for map in cameras() {
+            let middle = ScreenCoordinate::new(SIZE.0 as f64 / 2.0, SIZE.1 as f64 / 2.0);
+            let center = map.latlng_for_pixel(middle).unwrap();
+            assert!((center.lat - 37.77).abs() < 1e-9);
+            assert!((center.long - -122.42).abs() < 1e-9);
+
+            let pixel = map.pixel_for_latlng(map.get_center());
+            assert!(pixel.distance(&middle) < 1e-6);
+        }
+    }
+
+    #[test]
+    fn pixel_round_trips() {
+        for map in cameras() {
+            for pixel in pixels() {
+                let latlng = map.latlng_for_pixel(pixel).unwrap();
+                let back = map.pixel_for_latlng(latlng);
+                assert!(
+                    back.distance(&pixel) < 1e-3,
+                    "{:?} came back as {:?} at zoom {}, bearing {}, pitch {}",
+                    pixel,
+                    back,
+                    map.get_zoom(),
+                    map.get_bearing(),
+                    map.get_pitch()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn latlng_round_trips() {
+        for map in cameras() {
+            let offsets = [(0.0, 0.0), (1.0, 1.0), (-2.5, 3.0), (10.0, -20.0)];
+            for (lat, long) in offsets.iter() {
+                // Shrunk with the zoom to stay in view
+                let scale = 2f64.powf(-map.get_zoom());
+                let latlng = LatLng::new(37.77 + lat * scale, -122.42 + long * scale);
+                let pixel = map.pixel_for_latlng(latlng);
+                let back = map.latlng_for_pixel(pixel).unwrap();
+                assert!((back.lat - latlng.lat).abs() < 1e-9, "{:?}", back);
+                assert!((back.long - latlng.long).abs() < 1e-9, "{:?}", back);
+            }
+        }
+    }
+
+    #[test]
+    fn no_latlng_above_horizon() {
+        let above_horizon = ScreenCoordinate::new(SIZE.0 as f64 / 2.0, -3.0 * SIZE.1 as f64);
+        for bearing in [0.0, 90.0].iter() {
+            let map = map_at(4.0, *bearing, 60.0);
+            assert_eq!(map.latlng_for_pixel(above_horizon), None);
+            // The top of the view still shows ground
+            assert!(map
+                .latlng_for_pixel(ScreenCoordinate::new(400.0, 0.0))
+                .is_some());
+        }
+        // Looking straight down, the ground goes on past the viewport
+        let map = map_at(4.0, 0.0, 0.0);
+        assert!(map.latlng_for_pixel(above_horizon).is_some());
+    }
+
+    fn text_field(map: &Map, layer_id: &str) -> Value {
+        map.get_layout_property(layer_id, "text-field").unwrap()
+    }
+
+    #[test]
+    fn set_language_restores_style_text_fields() {
+        let mut map = Map::new();
+        map.load_style_json(
+            &json!({
+                "version": 8,
+                "sources": {},
+                "layers": [
+                    {"id": "places", "type": "symbol", "source": "streets",
+                     "source-layer": "place", "layout": {"text-field": "{name_en}"}},
+                    {"id": "shields", "type": "symbol", "source": "streets",
+                     "source-layer": "road", "layout": {"text-field": "{ref}"}}
+                ]
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        map.set_language(&["de"]).unwrap();
+        assert_eq!(
+            text_field(&map, "places"),
+            json!(["coalesce", ["get", "name_de"], ["get", "name"]])
+        );
+        map.set_language(&["fr", "de"]).unwrap();
+        assert_eq!(
+            text_field(&map, "places"),
+            json!([
+                "coalesce",
+                ["get", "name_fr"],
+                ["get", "name_de"],
+                ["get", "name"]
+            ])
+        );
+        assert_eq!(text_field(&map, "shields"), json!("{ref}"));
+
+        map.set_language(&[]).unwrap();
+        assert_eq!(text_field(&map, "places"), json!("{name_en}"));
+
+        // A text-field set since is what gets localized from then on
+        map.set_language(&["de"]).unwrap();
+        map.set_layout_property("places", "text-field", json!("{name:en}"))
+            .unwrap();
+        map.set_language(&["it"]).unwrap();
+        assert_eq!(
+            text_field(&map, "places"),
+            json!(["coalesce", ["get", "name:it"], ["get", "name"]])
+        );
+        map.set_language(&[]).unwrap();
+        assert_eq!(text_field(&map, "places"), json!("{name:en}"));
+    }
+}
@@ -1,16 +1,32 @@
+pub mod camera;
 pub mod common;
 pub mod config;
+pub mod geo;
+pub mod input_handler;
 pub mod map;
+pub mod render_tile;
+pub mod style_observer;
+pub mod style_spec;
+pub mod symbol;
+pub mod text;
+pub mod tile_cache;
+pub mod transform;
 pub mod vector_tile_id;
 pub mod vector_tile_model;
 pub mod vector_tile_observer;
 
 mod io;
-mod utils;
+pub mod utils;
 
+mod glyph;
+pub mod glyph_atlas;
+pub mod glyph_model;
 mod manifest;
 mod manifest_model;
 mod manifest_observer;
+mod sprite;
+pub mod sprite_model;
 mod style;
+mod style_diff;
 mod style_model;
 mod vector_tile_manager;
@@ -0,0 +1,255 @@
+// Ref: https://github.com/mapbox/mapbox-gl-native/blob/master/src/mbgl/renderer/tile_pyramid.cpp
+//
+// What actually gets drawn for a source. Ideal tiles that haven't loaded yet
+// are stood in for by loaded children or the nearest loaded ancestor, so that
+// zooming never leaves holes.
+use super::vector_tile_id::{OverscaledTileID, UnwrappedTileID, VectorTileID};
+
+// Levels of children looked at for a missing tile. Deeper ones rarely cover
+// a tile completely and are costly to check.
+const MAX_CHILD_DEPTH: u32 = 2;
+
+// A loaded tile and the part of it to draw, in normalized tile coords
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RenderTile {
+    pub tile_id: UnwrappedTileID,
+    pub clip: TileClip,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TileClip {
+    pub left: f64,
+    pub top: f64,
+    pub right: f64,
+    pub bottom: f64,
+}
+
+impl TileClip {
+    pub fn full() -> TileClip {
+        TileClip {
+            left: 0.0,
+            top: 0.0,
+            right: 1.0,
+            bottom: 1.0,
+        }
+    }
+
+    // Part of `ancestor` the area of `tile_id` takes up
+    fn of_descendant(ancestor: &VectorTileID, tile_id: &VectorTileID) -> TileClip {
+        let scale = 2f64.powi(tile_id.z as i32 - ancestor.z as i32);
+        let left = tile_id.x as f64 / scale - ancestor.x as f64;
+        let top = tile_id.y as f64 / scale - ancestor.y as f64;
+        TileClip {
+            left,
+            top,
+            right: left + 1.0 / scale,
+            bottom: top + 1.0 / scale,
+        }
+    }
+}
+
+// Tiles to draw for `ideal_tiles`, given which tiles are loaded. Ideal tiles
+// nothing stands in for yet are left out. Ancestors come first so that
+// anything drawn at a higher zoom ends up on top.
+pub fn render_tiles(
+    ideal_tiles: &[OverscaledTileID],
+    is_loaded: impl Fn(&VectorTileID) -> bool,
+) -> Vec<RenderTile> {
+    let mut render_tiles = vec![];
+    for ideal in ideal_tiles.iter() {
+        let wrap = ideal.wrap;
+        let ideal = ideal.canonical;
+        if is_loaded(&ideal) {
+            render_tiles.push(RenderTile {
+                tile_id: UnwrappedTileID {
+                    wrap,
+                    canonical: ideal,
+                },
+                clip: TileClip::full(),
+            });
+            continue;
+        }
+
+        let mut children = vec![];
+        if covered_by_children(&ideal, MAX_CHILD_DEPTH, &is_loaded, &mut children) {
+            render_tiles.extend(children.into_iter().map(|canonical| RenderTile {
+                tile_id: UnwrappedTileID { wrap, canonical },
+                clip: TileClip::full(),
+            }));
+            continue;
+        }
+
+        let mut ancestor = ideal.parent();
+        while let Some(tile_id) = ancestor {
+            if is_loaded(&tile_id) {
+                render_tiles.push(RenderTile {
+                    tile_id: UnwrappedTileID {
+                        wrap,
+                        canonical: tile_id,
+                    },
+                    clip: TileClip::of_descendant(&tile_id, &ideal),
+                });
+                break;
+            }
+            ancestor = tile_id.parent();
+        }
+    }
+    render_tiles.sort_by_key(|render_tile| render_tile.tile_id.canonical.z);
+    render_tiles
+}
+
+// Whether loaded descendants down to `depth` levels cover all of `tile_id`,
+// collecting them into `children`
+fn covered_by_children(
+    tile_id: &VectorTileID,
+    depth: u32,
+    is_loaded: &impl Fn(&VectorTileID) -> bool,
+    children: &mut Vec<VectorTileID>,
+) -> bool {
+    if depth == 0 {
+        return false;
+    }
+    let found = children.len();
+    for child in tile_id.children().iter() {
+        if is_loaded(child) {
+            children.push(*child);
+        } else if !covered_by_children(child, depth - 1, is_loaded, children) {
+            children.truncate(found);
+            return false;
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashSet;
+
+    const WRAP: i32 = -1;
+
+    fn ideal() -> OverscaledTileID {
+        OverscaledTileID::new(3, WRAP, VectorTileID::new(2, 3, 3))
+    }
+
+    fn render(loaded: &[VectorTileID]) -> Vec<RenderTile> {
+        let loaded: HashSet<VectorTileID> = loaded.iter().copied().collect();
+        render_tiles(&[ideal()], |tile_id| loaded.contains(tile_id))
+    }
+
+    fn full(canonical: VectorTileID) -> RenderTile {
+        RenderTile {
+            tile_id: UnwrappedTileID {
+                wrap: WRAP,
+                canonical,
+            },
+            clip: TileClip::full(),
+        }
+    }
+
+    fn clipped(
+        canonical: VectorTileID,
+        left: f64,
+        top: f64,
+        right: f64,
+        bottom: f64,
+    ) -> RenderTile {
+        RenderTile {
+            clip: TileClip {
+                left,
+                top,
+                right,
+                bottom,
+            },
+            ..full(canonical)
+        }
+    }
+
+    #[test]
+    fn draws_loaded_ideal_tiles() {
+        let ideal = ideal().canonical;
+        // Loaded relatives don't matter
+        let loaded = [ideal, ideal.parent().unwrap(), ideal.children()[0]];
+        assert_eq!(render(&loaded), vec![full(ideal)]);
+        assert!(render(&[]).is_empty());
+    }
+
+    #[test]
+    fn children_stand_in_for_missing_tiles() {
+        let children = ideal().canonical.children();
+        assert_eq!(
+            render(&children),
+            children.iter().copied().map(full).collect::<Vec<_>>()
+        );
+
+        // A missing child can be made up for by its own children, but not by
+        // theirs
+        let grandchildren = children[3].children();
+        let mut loaded = children[..3].to_vec();
+        loaded.extend(grandchildren.iter());
+        let mut expected: Vec<RenderTile> = children[..3].iter().copied().map(full).collect();
+        expected.extend(grandchildren.iter().copied().map(full));
+        assert_eq!(render(&loaded), expected);
+
+        let mut loaded = children[..3].to_vec();
+        loaded.extend(grandchildren[0].children().iter());
+        assert!(render(&loaded).is_empty());
+    }
+
+    #[test]
+    fn partial_children_give_way_to_ancestors() {
+        let ideal = ideal().canonical;
+        let children = ideal.children();
+        let parent = ideal.parent().unwrap();
+
+        // Three out of four children would leave a hole
+        assert!(render(&children[..3]).is_empty());
+
+        let mut loaded = children[..3].to_vec();
+        loaded.push(parent);
+        // (2, 3) is the bottom left quarter of (1, 1)
+        assert_eq!(render(&loaded), vec![clipped(parent, 0.0, 0.5, 0.5, 1.0)]);
+    }
+
+    #[test]
+    fn nearest_ancestor_is_clipped_to_the_tile() {
+        let ideal = ideal().canonical;
+        let parent = ideal.parent().unwrap();
+        let grandparent = parent.parent().unwrap();
+        assert_eq!(grandparent, VectorTileID::new(0, 0, 1));
+
+        // Two levels up the tile is a sixteenth of (0, 0, 1), in the third
+        // column and the fourth row
+        assert_eq!(
+            render(&[grandparent]),
+            vec![clipped(grandparent, 0.5, 0.75, 0.75, 1.0)]
+        );
+        // The closer one wins
+        assert_eq!(
+            render(&[grandparent, parent]),
+            vec![clipped(parent, 0.0, 0.5, 0.5, 1.0)]
+        );
+        let root = VectorTileID::new(0, 0, 0);
+        assert_eq!(
+            render(&[root]),
+            vec![clipped(root, 0.25, 0.375, 0.375, 0.5)]
+        );
+    }
+
+    #[test]
+    fn ancestors_are_drawn_first() {
+        let first = OverscaledTileID::new(3, WRAP, VectorTileID::new(2, 3, 3));
+        let second = OverscaledTileID::new(3, WRAP, VectorTileID::new(3, 3, 3));
+        let loaded: HashSet<VectorTileID> = [second.canonical, VectorTileID::new(0, 0, 1)]
+            .iter()
+            .copied()
+            .collect();
+        let render_tiles = render_tiles(&[second, first], |tile_id| loaded.contains(tile_id));
+        let zooms: Vec<u32> = render_tiles
+            .iter()
+            .map(|render_tile| render_tile.tile_id.canonical.z)
+            .collect();
+        assert_eq!(zooms, vec![1, 3]);
+    }
+}
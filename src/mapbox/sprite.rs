@@ -0,0 +1,170 @@
+use super::common::map_error::MapError;
+use super::common::task_responder::TaskResponder;
+use super::common::types::{Threadable, ThreadableNew};
+use super::config;
+use super::io::resource::Resource;
+use super::sprite_model::{IconAtlas, SpriteImage};
+
+// Sprites only come in 1x and 2x
+fn sprite_pixel_ratio() -> f32 {
+    if config::PIXEL_RATIO > 1.0 {
+        2.0
+    } else {
+        1.0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SpriteResource {
+    Index, // .json
+    Atlas, // .png
+}
+
+struct SpriteImpl {
+    base_url: String,
+    index: Option<Vec<u8>>,
+    png: Option<Vec<u8>>,
+    icon_atlas: Threadable<IconAtlas>,
+}
+
+impl SpriteImpl {
+    fn new() -> SpriteImpl {
+        SpriteImpl {
+            base_url: String::new(),
+            index: None,
+            png: None,
+            icon_atlas: ThreadableNew(IconAtlas::new()),
+        }
+    }
+
+    fn on_sprite_resource(&mut self, base_url: &str, resource: SpriteResource, data: Vec<u8>) {
+        if base_url != self.base_url {
+            // Response of a sprite which has been replaced in the meantime
+            return;
+        }
+        match resource {
+            SpriteResource::Index => self.index = Some(data),
+            SpriteResource::Atlas => self.png = Some(data),
+        }
+
+        // Both halves are needed before anything can be decoded
+        if self.index.is_none() || self.png.is_none() {
+            return;
+        }
+        let index = self.index.take().unwrap();
+        let png = self.png.take().unwrap();
+        match IconAtlas::parse(&index, &png, sprite_pixel_ratio()) {
+            Ok(icon_atlas) => {
+                println!("== Sprite loaded with {} icons", icon_atlas.images.len());
+                *self.icon_atlas.lock().unwrap() = icon_atlas;
+            }
+            Err(map_error) => println!("Error: Sprite Parse Failed {}", map_error),
+        }
+    }
+}
+
+// One of the two requests a sprite needs
+struct SpriteRequest {
+    base_url: String,
+    resource: SpriteResource,
+    sprite_impl: Threadable<SpriteImpl>,
+}
+
+impl TaskResponder for SpriteRequest {
+    fn on_task_success(&mut self, url: String, data: Option<Vec<u8>>) {
+        match data {
+            Some(bytes) => {
+                self.sprite_impl.lock().unwrap().on_sprite_resource(
+                    &self.base_url,
+                    self.resource,
+                    bytes,
+                );
+            }
+            None => {
+                println!("Error: empty sprite loaded from {}", url);
+            }
+        }
+    }
+
+    fn on_task_failure(&self, map_error: MapError) {
+        println!(
+            "Error: Sprite {:?} Load Failed {}",
+            self.resource, map_error
+        );
+    }
+}
+
+//
+
+pub struct Sprite {
+    sprite_impl: Threadable<SpriteImpl>,
+    resource: Resource,
+}
+
+impl Sprite {
+    pub fn new() -> Sprite {
+        Sprite {
+            sprite_impl: ThreadableNew(SpriteImpl::new()),
+            resource: Resource::new(2),
+        }
+    }
+
+    // `base_url` is the style's "sprite" value, ".json" and ".png" (with "@2x"
+    // on high density screens) get appended to it.
+    pub fn load(&self, base_url: &str) {
+        {
+            let mut sprite_impl = self.sprite_impl.lock().unwrap();
+            sprite_impl.base_url = base_url.to_owned();
+            sprite_impl.index = None;
+            sprite_impl.png = None;
+            *sprite_impl.icon_atlas.lock().unwrap() = IconAtlas::new();
+        }
+        if base_url.is_empty() {
+            return;
+        }
+
+        for (resource, extension) in [
+            (SpriteResource::Index, ".json"),
+            (SpriteResource::Atlas, ".png"),
+        ]
+        .iter()
+        {
+            let responder = ThreadableNew(SpriteRequest {
+                base_url: base_url.to_owned(),
+                resource: *resource,
+                sprite_impl: self.sprite_impl.clone(),
+            });
+            self.resource
+                .get(&Sprite::get_request_url(base_url, extension), responder);
+        }
+    }
+
+    fn get_request_url(base_url: &str, extension: &str) -> String {
+        let ratio = if sprite_pixel_ratio() > 1.0 {
+            "@2x"
+        } else {
+            ""
+        };
+        // Keep query strings (e.g. access tokens) at the end
+        match base_url.find('?') {
+            Some(inx) => format!(
+                "{}{}{}{}",
+                &base_url[..inx],
+                ratio,
+                extension,
+                &base_url[inx..]
+            ),
+            None => format!("{}{}{}", base_url, ratio, extension),
+        }
+    }
+
+    pub fn get_icon_atlas(&self) -> Threadable<IconAtlas> {
+        self.sprite_impl.lock().unwrap().icon_atlas.clone()
+    }
+
+    pub fn get_image(&self, name: &str) -> Option<SpriteImage> {
+        let icon_atlas = self.get_icon_atlas();
+        let icon_atlas = icon_atlas.lock().unwrap();
+        icon_atlas.get(name).cloned()
+    }
+}
@@ -0,0 +1,151 @@
+// Ref: https://docs.mapbox.com/mapbox-gl-js/style-spec/sprite/
+//
+// A sprite is a PNG atlas plus a JSON index describing where every icon lives
+// in it. The atlas is kept as one RGBA buffer so that it can be uploaded as a
+// single texture, icons only keep their rectangle and metadata.
+use super::common::map_error::{MapError, MapErrorTag};
+
+use serde_json::Value;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpriteImage {
+    // Rectangle in the atlas, in atlas pixels
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub pixel_ratio: f32,
+    // Signed distance field icons can be recolored with icon-color
+    pub sdf: bool,
+    // Ranges of the icon which may be stretched for icon-text-fit
+    pub stretch_x: Vec<(f32, f32)>,
+    pub stretch_y: Vec<(f32, f32)>,
+    // (left, top, right, bottom) of the area text is fit into
+    pub content: Option<(f32, f32, f32, f32)>,
+}
+
+#[derive(Debug)]
+pub struct IconAtlas {
+    pub width: u32,
+    pub height: u32,
+    pub pixel_ratio: f32,
+    pub pixels: Vec<u8>, // RGBA, row by row
+    pub images: HashMap<String, SpriteImage>,
+}
+
+impl IconAtlas {
+    pub fn new() -> IconAtlas {
+        IconAtlas {
+            width: 0,
+            height: 0,
+            pixel_ratio: 1.0,
+            pixels: vec![],
+            images: HashMap::new(),
+        }
+    }
+
+    pub fn parse(index: &[u8], png: &[u8], pixel_ratio: f32) -> Result<IconAtlas, MapError> {
+        let index: Value = serde_json::from_slice(index)
+            .map_err(|err| sprite_error(format!("bad sprite index: {}", err)))?;
+        let index = index
+            .as_object()
+            .ok_or_else(|| sprite_error("sprite index must be an object".to_owned()))?;
+
+        let atlas = image::load_from_memory(png)
+            .map_err(|err| sprite_error(format!("bad sprite image: {}", err)))?
+            .to_rgba();
+        let (width, height) = atlas.dimensions();
+
+        let mut images = HashMap::new();
+        for (name, entry) in index.iter() {
+            match SpriteImage::parse(entry) {
+                Some(image)
+                    if image.x + image.width <= width && image.y + image.height <= height =>
+                {
+                    images.insert(name.clone(), image);
+                }
+                _ => println!("Warning: sprite image {} is malformed - skipped", name),
+            }
+        }
+
+        Ok(IconAtlas {
+            width,
+            height,
+            pixel_ratio,
+            pixels: atlas.into_raw(),
+            images,
+        })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&SpriteImage> {
+        self.images.get(name)
+    }
+
+    // Copies the RGBA pixels of a single icon out of the atlas
+    pub fn get_pixels(&self, name: &str) -> Option<Vec<u8>> {
+        let image = self.get(name)?;
+        let mut pixels = Vec::with_capacity((image.width * image.height * 4) as usize);
+        for row in image.y..image.y + image.height {
+            let start = ((row * self.width + image.x) * 4) as usize;
+            pixels.extend_from_slice(&self.pixels[start..start + (image.width * 4) as usize]);
+        }
+        Some(pixels)
+    }
+}
+
+impl Default for IconAtlas {
+    fn default() -> IconAtlas {
+        IconAtlas::new()
+    }
+}
+
+impl SpriteImage {
+    fn parse(entry: &Value) -> Option<SpriteImage> {
+        let dimension = |key: &str| entry[key].as_u64().map(|v| v as u32);
+        Some(SpriteImage {
+            x: dimension("x")?,
+            y: dimension("y")?,
+            width: dimension("width")?,
+            height: dimension("height")?,
+            pixel_ratio: entry["pixelRatio"].as_f64().unwrap_or(1.0) as f32,
+            sdf: entry["sdf"].as_bool().unwrap_or_default(),
+            stretch_x: parse_stretch(&entry["stretchX"]),
+            stretch_y: parse_stretch(&entry["stretchY"]),
+            content: parse_content(&entry["content"]),
+        })
+    }
+}
+
+// [[from, to], ...]
+fn parse_stretch(value: &Value) -> Vec<(f32, f32)> {
+    value
+        .as_array()
+        .map(|stops| {
+            stops
+                .iter()
+                .filter_map(|stop| {
+                    let stop = stop.as_array()?;
+                    Some((
+                        stop.first()?.as_f64()? as f32,
+                        stop.get(1)?.as_f64()? as f32,
+                    ))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// [left, top, right, bottom]
+fn parse_content(value: &Value) -> Option<(f32, f32, f32, f32)> {
+    let content = value.as_array()?;
+    if content.len() != 4 {
+        return None;
+    }
+    let v = |inx: usize| content[inx].as_f64().map(|v| v as f32);
+    Some((v(0)?, v(1)?, v(2)?, v(3)?))
+}
+
+fn sprite_error(msg: String) -> MapError {
+    MapError::new(MapErrorTag::Style, msg)
+}
@@ -1,11 +1,17 @@
-use super::common::map_error::MapError;
+use super::common::map_error::{MapError, MapErrorTag};
 use super::common::task_responder::TaskResponder;
+use super::glyph::Glyphs;
+use super::glyph_atlas::{GlyphAtlas, GlyphPosition};
 use super::io::resource::Resource;
+use super::sprite::Sprite;
+use super::sprite_model::{IconAtlas, SpriteImage};
+use super::style_diff::{self, StyleOperation};
 use super::style_model::StyleModel;
 
 use super::manifest::Manifest;
 
 use crate::mapbox::manifest_observer::ManifestObserver;
+use crate::mapbox::style_observer::StyleObserver;
 use serde_json::{Map, Value};
 use std::cell::RefCell;
 
@@ -14,7 +20,10 @@ use super::common::types::{Threadable, ThreadableNew};
 struct StyleImpl {
     style_model: StyleModel,
     manifests: RefCell<Vec<Manifest>>,
+    sprite: Sprite,
+    glyphs: Glyphs,
     obs: Option<Threadable<dyn ManifestObserver>>,
+    style_obs: Vec<Threadable<dyn StyleObserver>>,
 }
 
 impl StyleImpl {
@@ -22,24 +31,68 @@ impl StyleImpl {
         StyleImpl {
             style_model: StyleModel::new(),
             manifests: RefCell::new(vec![]),
+            sprite: Sprite::new(),
+            glyphs: Glyphs::new(),
             obs: None,
+            style_obs: vec![],
+        }
+    }
+
+    fn load_style_json(&mut self, json: &str) -> Result<(), MapError> {
+        let style: Value = serde_json::from_str(json)
+            .map_err(|err| MapError::new(MapErrorTag::Style, err.to_string()))?;
+        self.style_model = StyleModel::parse(style)?;
+
+        // Sources have to be registered before any of their tiles come back
+        for style_obs in self.style_obs.iter() {
+            style_obs.lock().unwrap().on_style_loaded(&self.style_model);
+        }
+
+        self.sprite.load(&self.style_model.sprite_url);
+        self.glyphs.set_url(&self.style_model.glyph_template_url);
+
+        // Load Sources
+        self.manifests.borrow_mut().clear();
+        self.load_manifest_items(&self.style_model.sources);
+        Ok(())
+    }
+
+    fn notify_style_failed(&self, map_error: &MapError) {
+        for style_obs in self.style_obs.iter() {
+            style_obs.lock().unwrap().on_style_failed(map_error);
         }
     }
 
     fn load_manifest_items(&self, sources: &Map<String, Value>) {
         for i in sources.iter() {
-            println!("> {:?}", i);
+            self.load_manifest_item(i.0, i.1);
+        }
+    }
 
-            let manifest_instance = Manifest::new(i.0.to_string());
-            manifest_instance.load_manifest(i.0.to_string(), i.1);
+    fn load_manifest_item(&self, source_id: &str, source: &Value) {
+        // Observed before the request goes out, so that a fast response isn't
+        // missed
+        let manifest_instance = Manifest::new(source_id.to_string());
+        if let Some(obs) = self.obs.as_ref() {
+            manifest_instance.add_manifest_observer(obs.clone());
+        }
+        manifest_instance.load_manifest(source_id.to_string(), source);
 
-            if self.obs.is_some() {
-                let obs = self.obs.as_ref().unwrap().clone();
-                manifest_instance.add_manifest_observer(obs);
-            }
+        self.manifests.borrow_mut().push(manifest_instance);
+    }
 
-            self.manifests.borrow_mut().push(manifest_instance);
-        }
+    fn add_source(&mut self, source_id: &str, source: Value) -> Result<(), MapError> {
+        self.style_model.add_source(source_id, source.clone())?;
+        self.load_manifest_item(source_id, &source);
+        Ok(())
+    }
+
+    fn remove_source(&mut self, source_id: &str) -> Result<(), MapError> {
+        self.style_model.remove_source(source_id)?;
+        self.manifests
+            .borrow_mut()
+            .retain(|manifest| manifest.get_name() != source_id);
+        Ok(())
     }
 
     pub fn add_manifest_observer(&mut self, obs: Threadable<dyn ManifestObserver>) {
@@ -52,30 +105,35 @@ impl StyleImpl {
             }
         }
     }
+
+    pub fn add_style_observer(&mut self, obs: Threadable<dyn StyleObserver>) {
+        self.style_obs.push(obs);
+    }
 }
 
 impl TaskResponder for StyleImpl {
     fn on_task_success(&mut self, _url: String, data: Option<Vec<u8>>) {
         println!("Yikes: Style Load Succeeded");
 
-        match data {
-            Some(str) => {
-                // Parse stylesheet
-                let str_ret = String::from_utf8(str).unwrap();
-                let style: Value = serde_json::from_str(&str_ret).unwrap();
-                self.style_model = StyleModel::parse(style);
+        let ret = match data {
+            Some(bytes) => String::from_utf8(bytes)
+                .map_err(|err| MapError::new(MapErrorTag::Style, err.to_string()))
+                .and_then(|json| self.load_style_json(&json)),
+            None => Err(MapError::new(
+                MapErrorTag::Style,
+                "empty stylesheet loaded".to_owned(),
+            )),
+        };
 
-                // Load Sources
-                self.load_manifest_items(&self.style_model.sources);
-            }
-            None => {
-                println!("Error: empty stylesheet loaded");
-            }
+        if let Err(map_error) = ret {
+            println!("Error: Style Parse Failed {}", map_error);
+            self.notify_style_failed(&map_error);
         }
     }
 
     fn on_task_failure(&self, map_error: MapError) {
         println!("Error: Style Load Failed {}", map_error);
+        self.notify_style_failed(&map_error);
     }
 }
 
@@ -96,12 +154,189 @@ impl Style {
         }
     }
 
-    pub fn load_style_with_url(&self, url: &'static str) {
+    // Parse errors are reported to the style observers
+    pub fn load_style_with_url(&self, url: &str) {
         let responder = self.style_impl.clone();
         self.resource.get(url, responder);
     }
 
+    pub fn load_style_json(&self, json: &str) -> Result<(), MapError> {
+        self.style_impl.lock().unwrap().load_style_json(json)
+    }
+
     pub fn add_manifest_observer(&mut self, obs: Threadable<dyn ManifestObserver>) {
         self.style_impl.lock().unwrap().add_manifest_observer(obs);
     }
+
+    pub fn add_style_observer(&mut self, obs: Threadable<dyn StyleObserver>) {
+        self.style_impl.lock().unwrap().add_style_observer(obs);
+    }
+
+    pub fn diff(&self, after: &StyleModel) -> Vec<StyleOperation> {
+        style_diff::diff_styles(&self.style_impl.lock().unwrap().style_model, after)
+    }
+
+    // Root properties which don't affect any source or layer
+    pub fn set_root_properties(&mut self, after: &StyleModel) {
+        let style_model = &mut self.style_impl.lock().unwrap().style_model;
+        style_model.name = after.name.clone();
+        style_model.min_zoom = after.min_zoom;
+        style_model.max_zoom = after.max_zoom;
+    }
+
+    pub fn set_sprite_url(&mut self, sprite_url: String) {
+        let mut style_impl = self.style_impl.lock().unwrap();
+        style_impl.sprite.load(&sprite_url);
+        style_impl.style_model.sprite_url = sprite_url;
+    }
+
+    pub fn get_icon_atlas(&self) -> Threadable<IconAtlas> {
+        self.style_impl.lock().unwrap().sprite.get_icon_atlas()
+    }
+
+    pub fn get_sprite_image(&self, name: &str) -> Option<SpriteImage> {
+        self.style_impl.lock().unwrap().sprite.get_image(name)
+    }
+
+    pub fn set_glyph_template_url(&mut self, glyph_template_url: String) {
+        let mut style_impl = self.style_impl.lock().unwrap();
+        style_impl.glyphs.set_url(&glyph_template_url);
+        style_impl.style_model.glyph_template_url = glyph_template_url;
+    }
+
+    pub fn load_glyphs(&self, fontstack: &[String], text: &str) {
+        self.style_impl
+            .lock()
+            .unwrap()
+            .glyphs
+            .load_glyphs(fontstack, text);
+    }
+
+    pub fn is_glyphs_ready(&self, fontstack: &[String], text: &str) -> bool {
+        self.style_impl
+            .lock()
+            .unwrap()
+            .glyphs
+            .is_ready(fontstack, text)
+    }
+
+    pub fn get_glyph(&self, fontstack: &[String], codepoint: u32) -> Option<GlyphPosition> {
+        self.style_impl
+            .lock()
+            .unwrap()
+            .glyphs
+            .get_glyph(fontstack, codepoint)
+    }
+
+    pub fn get_glyph_atlas(&self) -> Threadable<GlyphAtlas> {
+        self.style_impl.lock().unwrap().glyphs.get_glyph_atlas()
+    }
+
+    // Layers in paint order
+    pub fn get_layers(&self) -> Vec<Value> {
+        self.style_impl.lock().unwrap().style_model.layers.clone()
+    }
+
+    // Runtime style mutations
+
+    pub fn get_layer_source(&self, layer_id: &str) -> Option<String> {
+        self.style_impl
+            .lock()
+            .unwrap()
+            .style_model
+            .get_layer_source(layer_id)
+    }
+
+    pub fn add_layer(&mut self, layer: Value, before_id: Option<&str>) -> Result<(), MapError> {
+        self.style_impl
+            .lock()
+            .unwrap()
+            .style_model
+            .add_layer(layer, before_id)
+    }
+
+    pub fn remove_layer(&mut self, layer_id: &str) -> Result<Value, MapError> {
+        self.style_impl
+            .lock()
+            .unwrap()
+            .style_model
+            .remove_layer(layer_id)
+    }
+
+    pub fn move_layer(&mut self, layer_id: &str, before_id: Option<&str>) -> Result<(), MapError> {
+        self.style_impl
+            .lock()
+            .unwrap()
+            .style_model
+            .move_layer(layer_id, before_id)
+    }
+
+    pub fn add_source(&mut self, source_id: &str, source: Value) -> Result<(), MapError> {
+        self.style_impl
+            .lock()
+            .unwrap()
+            .add_source(source_id, source)
+    }
+
+    pub fn remove_source(&mut self, source_id: &str) -> Result<(), MapError> {
+        self.style_impl.lock().unwrap().remove_source(source_id)
+    }
+
+    pub fn get_paint_property(&self, layer_id: &str, name: &str) -> Option<Value> {
+        self.style_impl
+            .lock()
+            .unwrap()
+            .style_model
+            .get_paint_property(layer_id, name)
+    }
+
+    pub fn set_paint_property(
+        &mut self,
+        layer_id: &str,
+        name: &str,
+        value: Value,
+    ) -> Result<(), MapError> {
+        self.style_impl
+            .lock()
+            .unwrap()
+            .style_model
+            .set_paint_property(layer_id, name, value)
+    }
+
+    pub fn get_layout_property(&self, layer_id: &str, name: &str) -> Option<Value> {
+        self.style_impl
+            .lock()
+            .unwrap()
+            .style_model
+            .get_layout_property(layer_id, name)
+    }
+
+    pub fn set_layout_property(
+        &mut self,
+        layer_id: &str,
+        name: &str,
+        value: Value,
+    ) -> Result<(), MapError> {
+        self.style_impl
+            .lock()
+            .unwrap()
+            .style_model
+            .set_layout_property(layer_id, name, value)
+    }
+
+    pub fn get_filter(&self, layer_id: &str) -> Option<Value> {
+        self.style_impl
+            .lock()
+            .unwrap()
+            .style_model
+            .get_filter(layer_id)
+    }
+
+    pub fn set_filter(&mut self, layer_id: &str, filter: Option<Value>) -> Result<(), MapError> {
+        self.style_impl
+            .lock()
+            .unwrap()
+            .style_model
+            .set_filter(layer_id, filter)
+    }
 }
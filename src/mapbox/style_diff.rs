@@ -0,0 +1,498 @@
+// Ref: https://github.com/mapbox/mapbox-gl-js/blob/master/src/style-spec/diff.js
+//
+// Turns two style documents into the minimal list of operations needed to get
+// from one to the other, so that a style swap keeps unchanged sources (and their
+// loaded tiles) around and only restyles the layers that actually changed.
+use super::style_model::StyleModel;
+
+use serde_json::Value;
+use std::collections::HashSet;
+
+#[derive(Debug, PartialEq)]
+pub enum StyleOperation {
+    RemoveLayer(String),
+    // The layer goes right below `before_id`, or on top if there is none
+    AddLayer {
+        layer: Value,
+        before_id: Option<String>,
+    },
+    MoveLayer {
+        layer_id: String,
+        before_id: Option<String>,
+    },
+    SetPaintProperty {
+        layer_id: String,
+        name: String,
+        value: Value,
+    },
+    SetLayoutProperty {
+        layer_id: String,
+        name: String,
+        value: Value,
+    },
+    SetFilter {
+        layer_id: String,
+        filter: Option<Value>,
+    },
+    RemoveSource(String),
+    AddSource {
+        source_id: String,
+        source: Value,
+    },
+    SetSprite(String),
+    SetGlyphs(String),
+    // The styles can't be diffed, the new one has to be loaded from scratch.
+    // Comes alone.
+    SetStyle,
+}
+
+// Changing any of these means the layer needs a new bucket, there is no
+// cheaper way than removing and adding it again.
+const LAYER_IDENTITY_KEYS: [&str; 5] = ["type", "source", "source-layer", "minzoom", "maxzoom"];
+
+pub fn diff_styles(before: &StyleModel, after: &StyleModel) -> Vec<StyleOperation> {
+    // Layers are matched up by id, which duplicates make ambiguous
+    if has_duplicate_layer_ids(before) || has_duplicate_layer_ids(after) {
+        return vec![StyleOperation::SetStyle];
+    }
+
+    let mut operations = vec![];
+
+    if before.sprite_url != after.sprite_url {
+        operations.push(StyleOperation::SetSprite(after.sprite_url.clone()));
+    }
+    if before.glyph_template_url != after.glyph_template_url {
+        operations.push(StyleOperation::SetGlyphs(after.glyph_template_url.clone()));
+    }
+
+    // Sources which are gone or whose definition changed. A changed source is
+    // removed and added again, and so are all of its layers.
+    let stale_sources: HashSet<&String> = before
+        .sources
+        .iter()
+        .filter(|(id, source)| after.sources.get(*id) != Some(*source))
+        .map(|(id, _)| id)
+        .collect();
+
+    // 1. Remove layers that are gone or have to be re-created
+    let mut tracker: Vec<String> = vec![];
+    for layer in before.layers.iter() {
+        let layer_id = layer_id(layer);
+        let needs_removal = match after.get_layer(&layer_id) {
+            None => true,
+            Some(after_layer) => {
+                let source_changed = layer["source"]
+                    .as_str()
+                    .map(|source| stale_sources.contains(&source.to_owned()))
+                    .unwrap_or(false);
+                source_changed
+                    || LAYER_IDENTITY_KEYS
+                        .iter()
+                        .any(|key| layer.get(*key) != after_layer.get(*key))
+            }
+        };
+
+        if needs_removal {
+            operations.push(StyleOperation::RemoveLayer(layer_id));
+        } else {
+            tracker.push(layer_id);
+        }
+    }
+
+    // 2. Swap sources now that no layer references the stale ones
+    for source_id in before.sources.keys() {
+        if stale_sources.contains(source_id) {
+            operations.push(StyleOperation::RemoveSource(source_id.clone()));
+        }
+    }
+    for (source_id, source) in after.sources.iter() {
+        if before.sources.get(source_id) != Some(source) {
+            operations.push(StyleOperation::AddSource {
+                source_id: source_id.clone(),
+                source: source.clone(),
+            });
+        }
+    }
+
+    // 3. Add and reorder layers. Walking backwards, the tail of `tracker`
+    //    always matches the tail of the new layer order already processed.
+    let after_order: Vec<String> = after.layers.iter().map(layer_id).collect();
+    let mut added: HashSet<String> = HashSet::new();
+    let count = after_order.len();
+    for i in (0..count).rev() {
+        let layer_id = &after_order[i];
+        let placed = count - 1 - i; // layers already at the tail of the tracker
+        if tracker.len() > placed && &tracker[tracker.len() - 1 - placed] == layer_id {
+            continue;
+        }
+
+        let before_id = after_order.get(i + 1).cloned();
+        match tracker.iter().position(|id| id == layer_id) {
+            Some(inx) => {
+                tracker.remove(inx);
+                operations.push(StyleOperation::MoveLayer {
+                    layer_id: layer_id.clone(),
+                    before_id,
+                });
+            }
+            None => {
+                added.insert(layer_id.clone());
+                operations.push(StyleOperation::AddLayer {
+                    layer: after.layers[i].clone(),
+                    before_id,
+                });
+            }
+        }
+        let inx = tracker.len() - placed;
+        tracker.insert(inx, layer_id.clone());
+    }
+
+    // 4. Property changes on the layers which were kept
+    for after_layer in after.layers.iter() {
+        let layer_id = layer_id(after_layer);
+        if added.contains(&layer_id) {
+            continue;
+        }
+        let before_layer = before.get_layer(&layer_id).unwrap();
+
+        for (name, value) in diff_properties(&before_layer["paint"], &after_layer["paint"]) {
+            operations.push(StyleOperation::SetPaintProperty {
+                layer_id: layer_id.clone(),
+                name,
+                value,
+            });
+        }
+        for (name, value) in diff_properties(&before_layer["layout"], &after_layer["layout"]) {
+            operations.push(StyleOperation::SetLayoutProperty {
+                layer_id: layer_id.clone(),
+                name,
+                value,
+            });
+        }
+        if before_layer.get("filter") != after_layer.get("filter") {
+            operations.push(StyleOperation::SetFilter {
+                layer_id: layer_id.clone(),
+                filter: after_layer.get("filter").cloned(),
+            });
+        }
+    }
+
+    operations
+}
+
+fn layer_id(layer: &Value) -> String {
+    layer["id"].as_str().unwrap_or_default().to_owned()
+}
+
+fn has_duplicate_layer_ids(style: &StyleModel) -> bool {
+    let mut layer_ids = HashSet::new();
+    !style
+        .layers
+        .iter()
+        .all(|layer| layer_ids.insert(layer_id(layer)))
+}
+
+// Changed properties of a paint or layout object. A property that is gone
+// maps to `Value::Null`, which resets it to its default.
+fn diff_properties(before: &Value, after: &Value) -> Vec<(String, Value)> {
+    let mut changes = vec![];
+
+    if let Some(after) = after.as_object() {
+        for (name, value) in after.iter() {
+            if before.get(name) != Some(value) {
+                changes.push((name.clone(), value.clone()));
+            }
+        }
+    }
+    if let Some(before) = before.as_object() {
+        for name in before.keys() {
+            if after.get(name).is_none() {
+                changes.push((name.clone(), Value::Null));
+            }
+        }
+    }
+
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn streets() -> Value {
+        json!({ "type": "vector", "url": "mapbox://mapbox.mapbox-streets-v8" })
+    }
+
+    fn fill(id: &str) -> Value {
+        json!({
+            "id": id,
+            "type": "fill",
+            "source": "streets",
+            "source-layer": id,
+            "paint": { "fill-color": "#fff" }
+        })
+    }
+
+    fn style(sources: Value, layers: Vec<Value>) -> StyleModel {
+        StyleModel::parse(json!({
+            "version": 8,
+            "sprite": "mapbox://sprites/streets",
+            "glyphs": "mapbox://fonts/{fontstack}/{range}.pbf",
+            "sources": sources,
+            "layers": layers
+        }))
+        .unwrap()
+    }
+
+    fn layers_style(layers: Vec<Value>) -> StyleModel {
+        style(json!({ "streets": streets() }), layers)
+    }
+
+    #[test]
+    fn same_style_has_no_operations() {
+        let before = layers_style(vec![fill("water"), fill("land")]);
+        let after = layers_style(vec![fill("water"), fill("land")]);
+        assert!(diff_styles(&before, &after).is_empty());
+    }
+
+    #[test]
+    fn adds_layers_below_the_next_one() {
+        let before = layers_style(vec![fill("water")]);
+        let after = layers_style(vec![fill("land"), fill("water"), fill("roads")]);
+        assert_eq!(
+            diff_styles(&before, &after),
+            vec![
+                StyleOperation::AddLayer {
+                    layer: fill("roads"),
+                    before_id: None,
+                },
+                StyleOperation::AddLayer {
+                    layer: fill("land"),
+                    before_id: Some("water".to_owned()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn removes_layers() {
+        let before = layers_style(vec![fill("water"), fill("land"), fill("roads")]);
+        let after = layers_style(vec![fill("water"), fill("roads")]);
+        assert_eq!(
+            diff_styles(&before, &after),
+            vec![StyleOperation::RemoveLayer("land".to_owned())]
+        );
+    }
+
+    // Layer order after applying the moves of `operations` to `before`
+    fn replay_moves(before: &StyleModel, operations: &[StyleOperation]) -> Vec<String> {
+        let mut order: Vec<String> = before.layers.iter().map(layer_id).collect();
+        for operation in operations.iter() {
+            match operation {
+                StyleOperation::MoveLayer {
+                    layer_id,
+                    before_id,
+                } => {
+                    order.retain(|id| id != layer_id);
+                    let inx = match before_id {
+                        Some(before_id) => order.iter().position(|id| id == before_id).unwrap(),
+                        None => order.len(),
+                    };
+                    order.insert(inx, layer_id.clone());
+                }
+                _ => panic!("unexpected {:?}", operation),
+            }
+        }
+        order
+    }
+
+    #[test]
+    fn moves_reordered_layers() {
+        let before = layers_style(vec![fill("water"), fill("land"), fill("roads")]);
+        let after = layers_style(vec![fill("land"), fill("roads"), fill("water")]);
+        assert_eq!(
+            diff_styles(&before, &after),
+            vec![StyleOperation::MoveLayer {
+                layer_id: "water".to_owned(),
+                before_id: None,
+            }]
+        );
+
+        for order in [
+            ["roads", "water", "land"],
+            ["roads", "land", "water"],
+            ["land", "water", "roads"],
+        ]
+        .iter()
+        {
+            let after = layers_style(order.iter().map(|id| fill(id)).collect());
+            let operations = diff_styles(&before, &after);
+            assert!(!operations.is_empty());
+            assert_eq!(replay_moves(&before, &operations), order.to_vec());
+        }
+    }
+
+    #[test]
+    fn sets_changed_properties() {
+        let before = layers_style(vec![json!({
+            "id": "water",
+            "type": "fill",
+            "source": "streets",
+            "source-layer": "water",
+            "filter": ["==", "class", "sea"],
+            "layout": { "visibility": "visible" },
+            "paint": { "fill-color": "#00f", "fill-opacity": 0.5 }
+        })]);
+        let after = layers_style(vec![json!({
+            "id": "water",
+            "type": "fill",
+            "source": "streets",
+            "source-layer": "water",
+            "filter": ["==", "class", "lake"],
+            "layout": { "visibility": "none" },
+            "paint": { "fill-color": "#0af", "fill-antialias": false }
+        })]);
+
+        let operations = diff_styles(&before, &after);
+        assert_eq!(operations.len(), 5);
+        for operation in [
+            StyleOperation::SetPaintProperty {
+                layer_id: "water".to_owned(),
+                name: "fill-color".to_owned(),
+                value: json!("#0af"),
+            },
+            StyleOperation::SetPaintProperty {
+                layer_id: "water".to_owned(),
+                name: "fill-antialias".to_owned(),
+                value: json!(false),
+            },
+            // Gone, back to the default
+            StyleOperation::SetPaintProperty {
+                layer_id: "water".to_owned(),
+                name: "fill-opacity".to_owned(),
+                value: Value::Null,
+            },
+            StyleOperation::SetLayoutProperty {
+                layer_id: "water".to_owned(),
+                name: "visibility".to_owned(),
+                value: json!("none"),
+            },
+            StyleOperation::SetFilter {
+                layer_id: "water".to_owned(),
+                filter: Some(json!(["==", "class", "lake"])),
+            },
+        ]
+        .iter()
+        {
+            assert!(operations.contains(operation), "{:?}", operation);
+        }
+    }
+
+    #[test]
+    fn removes_filter() {
+        let mut filtered = fill("water");
+        filtered["filter"] = json!(["==", "class", "sea"]);
+        let before = layers_style(vec![filtered]);
+        let after = layers_style(vec![fill("water")]);
+        assert_eq!(
+            diff_styles(&before, &after),
+            vec![StyleOperation::SetFilter {
+                layer_id: "water".to_owned(),
+                filter: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn recreates_layer_with_new_type() {
+        let before = layers_style(vec![fill("water"), fill("land")]);
+        let mut line = fill("water");
+        line["type"] = json!("line");
+        let after = layers_style(vec![line.clone(), fill("land")]);
+        assert_eq!(
+            diff_styles(&before, &after),
+            vec![
+                StyleOperation::RemoveLayer("water".to_owned()),
+                StyleOperation::AddLayer {
+                    layer: line,
+                    before_id: Some("land".to_owned()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn swaps_changed_source_with_its_layers() {
+        let mut terrain = fill("hillshade");
+        terrain["source"] = json!("terrain");
+        let terrain_source = json!({ "type": "vector", "url": "mapbox://mapbox.terrain-v2" });
+        let before = style(
+            json!({ "streets": streets(), "terrain": terrain_source }),
+            vec![fill("water"), terrain.clone()],
+        );
+        let moved_source = json!({ "type": "vector", "url": "mapbox://mapbox.streets-v9" });
+        let after = style(
+            json!({ "streets": moved_source, "terrain": terrain_source }),
+            vec![fill("water"), terrain],
+        );
+
+        assert_eq!(
+            diff_styles(&before, &after),
+            vec![
+                StyleOperation::RemoveLayer("water".to_owned()),
+                StyleOperation::RemoveSource("streets".to_owned()),
+                StyleOperation::AddSource {
+                    source_id: "streets".to_owned(),
+                    source: moved_source,
+                },
+                StyleOperation::AddLayer {
+                    layer: fill("water"),
+                    before_id: Some("hillshade".to_owned()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn adds_and_removes_sources() {
+        let terrain_source = json!({ "type": "vector", "url": "mapbox://mapbox.terrain-v2" });
+        let before = style(json!({ "streets": streets() }), vec![]);
+        let after = style(json!({ "terrain": terrain_source }), vec![]);
+        assert_eq!(
+            diff_styles(&before, &after),
+            vec![
+                StyleOperation::RemoveSource("streets".to_owned()),
+                StyleOperation::AddSource {
+                    source_id: "terrain".to_owned(),
+                    source: terrain_source,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn sets_sprite_and_glyphs() {
+        let before = layers_style(vec![]);
+        let mut after = layers_style(vec![]);
+        after.sprite_url = "mapbox://sprites/dark".to_owned();
+        after.glyph_template_url = "https://fonts.example.com/{fontstack}/{range}.pbf".to_owned();
+        assert_eq!(
+            diff_styles(&before, &after),
+            vec![
+                StyleOperation::SetSprite("mapbox://sprites/dark".to_owned()),
+                StyleOperation::SetGlyphs(
+                    "https://fonts.example.com/{fontstack}/{range}.pbf".to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn duplicate_layer_ids_reload_style() {
+        let before = layers_style(vec![fill("water"), fill("land")]);
+        let after = layers_style(vec![fill("land"), fill("water"), fill("land")]);
+        assert_eq!(diff_styles(&before, &after), vec![StyleOperation::SetStyle]);
+        assert_eq!(diff_styles(&after, &before), vec![StyleOperation::SetStyle]);
+    }
+}
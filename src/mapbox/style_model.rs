@@ -1,4 +1,5 @@
 // Ref: https://docs.mapbox.com/mapbox-gl-js/style-spec/
+use super::common::map_error::{MapError, MapErrorTag};
 use serde_json::{Map, Value};
 
 #[derive(Debug)]
@@ -25,10 +26,14 @@ impl StyleModel {
         }
     }
 
-    pub fn parse(json_value: Value) -> StyleModel {
-        let layers = json_value["layers"].as_array().unwrap();
-        let sources = json_value["sources"].as_object().unwrap();
-        StyleModel {
+    pub fn parse(json_value: Value) -> Result<StyleModel, MapError> {
+        let layers = json_value["layers"]
+            .as_array()
+            .ok_or_else(|| style_error("style must have a layers array".to_owned()))?;
+        let sources = json_value["sources"]
+            .as_object()
+            .ok_or_else(|| style_error("style must have a sources object".to_owned()))?;
+        Ok(StyleModel {
             name: String::from(json_value["name"].as_str().unwrap_or_default()),
             min_zoom: json_value["minzoom"].as_i64().unwrap_or_default(),
             max_zoom: json_value["maxzoom"].as_i64().unwrap_or_default(),
@@ -36,6 +41,208 @@ impl StyleModel {
             glyph_template_url: String::from(json_value["glyphs"].as_str().unwrap_or_default()),
             layers: layers.to_owned(),
             sources: sources.to_owned(),
+        })
+    }
+
+    // Only vector sources are routed through the VectorTileManager for now.
+    pub fn vector_source_ids(&self) -> Vec<String> {
+        self.sources
+            .iter()
+            .filter(|(_, source)| source["type"].as_str() == Some("vector"))
+            .map(|(id, _)| id.to_owned())
+            .collect()
+    }
+
+    // Runtime mutations
+    // Ref: https://docs.mapbox.com/mapbox-gl-js/api/map/#map#addlayer
+
+    pub fn get_layer(&self, layer_id: &str) -> Option<&Value> {
+        self.layers
+            .iter()
+            .find(|layer| layer["id"].as_str() == Some(layer_id))
+    }
+
+    pub fn get_layer_source(&self, layer_id: &str) -> Option<String> {
+        self.get_layer(layer_id)
+            .and_then(|layer| layer["source"].as_str())
+            .map(|source| source.to_owned())
+    }
+
+    fn layer_index(&self, layer_id: &str) -> Result<usize, MapError> {
+        self.layers
+            .iter()
+            .position(|layer| layer["id"].as_str() == Some(layer_id))
+            .ok_or_else(|| style_error(format!("layer {} does not exist", layer_id)))
+    }
+
+    // Index to insert at so that the layer ends up right below `before_id`,
+    // or on top of all other layers if there is none.
+    fn insert_index(&self, before_id: Option<&str>) -> Result<usize, MapError> {
+        match before_id {
+            Some(before_id) => self.layer_index(before_id),
+            None => Ok(self.layers.len()),
+        }
+    }
+
+    pub fn add_layer(&mut self, layer: Value, before_id: Option<&str>) -> Result<(), MapError> {
+        let layer_id = match layer["id"].as_str() {
+            Some(layer_id) => layer_id.to_owned(),
+            None => return Err(style_error("layer must have a string id".to_owned())),
+        };
+        if self.get_layer(&layer_id).is_some() {
+            return Err(style_error(format!("layer {} already exists", layer_id)));
         }
+        if let Some(source_id) = layer["source"].as_str() {
+            if !self.sources.contains_key(source_id) {
+                return Err(style_error(format!(
+                    "layer {} references unknown source {}",
+                    layer_id, source_id
+                )));
+            }
+        }
+
+        let inx = self.insert_index(before_id)?;
+        self.layers.insert(inx, layer);
+        Ok(())
     }
+
+    pub fn remove_layer(&mut self, layer_id: &str) -> Result<Value, MapError> {
+        let inx = self.layer_index(layer_id)?;
+        Ok(self.layers.remove(inx))
+    }
+
+    pub fn move_layer(&mut self, layer_id: &str, before_id: Option<&str>) -> Result<(), MapError> {
+        if before_id == Some(layer_id) {
+            return Ok(());
+        }
+        // Validate the target before touching anything
+        self.insert_index(before_id)?;
+
+        let layer = self.remove_layer(layer_id)?;
+        let inx = self.insert_index(before_id)?;
+        self.layers.insert(inx, layer);
+        Ok(())
+    }
+
+    pub fn add_source(&mut self, source_id: &str, source: Value) -> Result<(), MapError> {
+        if self.sources.contains_key(source_id) {
+            return Err(style_error(format!("source {} already exists", source_id)));
+        }
+        if !source["type"].is_string() {
+            return Err(style_error(format!(
+                "source {} must have a type",
+                source_id
+            )));
+        }
+        self.sources.insert(source_id.to_owned(), source);
+        Ok(())
+    }
+
+    pub fn remove_source(&mut self, source_id: &str) -> Result<Value, MapError> {
+        if let Some(layer) = self
+            .layers
+            .iter()
+            .find(|layer| layer["source"].as_str() == Some(source_id))
+        {
+            return Err(style_error(format!(
+                "source {} is still used by layer {}",
+                source_id, layer["id"]
+            )));
+        }
+        self.sources
+            .remove(source_id)
+            .ok_or_else(|| style_error(format!("source {} does not exist", source_id)))
+    }
+
+    pub fn get_paint_property(&self, layer_id: &str, name: &str) -> Option<Value> {
+        self.get_property(layer_id, "paint", name)
+    }
+
+    pub fn set_paint_property(
+        &mut self,
+        layer_id: &str,
+        name: &str,
+        value: Value,
+    ) -> Result<(), MapError> {
+        self.set_property(layer_id, "paint", name, value)
+    }
+
+    pub fn get_layout_property(&self, layer_id: &str, name: &str) -> Option<Value> {
+        self.get_property(layer_id, "layout", name)
+    }
+
+    pub fn set_layout_property(
+        &mut self,
+        layer_id: &str,
+        name: &str,
+        value: Value,
+    ) -> Result<(), MapError> {
+        self.set_property(layer_id, "layout", name, value)
+    }
+
+    pub fn get_filter(&self, layer_id: &str) -> Option<Value> {
+        self.get_layer(layer_id)
+            .and_then(|layer| layer.get("filter"))
+            .map(|filter| filter.to_owned())
+    }
+
+    // `None` removes the filter so that all features of the source layer are drawn
+    pub fn set_filter(&mut self, layer_id: &str, filter: Option<Value>) -> Result<(), MapError> {
+        let inx = self.layer_index(layer_id)?;
+        let layer = self.layers[inx].as_object_mut().unwrap();
+        match filter {
+            Some(filter) => {
+                layer.insert("filter".to_owned(), filter);
+            }
+            None => {
+                layer.remove("filter");
+            }
+        }
+        Ok(())
+    }
+
+    fn get_property(&self, layer_id: &str, group: &str, name: &str) -> Option<Value> {
+        self.get_layer(layer_id)
+            .and_then(|layer| layer.get(group))
+            .and_then(|properties| properties.get(name))
+            .map(|value| value.to_owned())
+    }
+
+    // Setting `Value::Null` resets the property to its default
+    fn set_property(
+        &mut self,
+        layer_id: &str,
+        group: &str,
+        name: &str,
+        value: Value,
+    ) -> Result<(), MapError> {
+        let inx = self.layer_index(layer_id)?;
+        let layer = match self.layers[inx].as_object_mut() {
+            Some(layer) => layer,
+            None => return Err(style_error(format!("layer {} is malformed", layer_id))),
+        };
+        let properties = layer
+            .entry(group.to_owned())
+            .or_insert_with(|| Value::Object(Map::new()));
+        let properties = match properties.as_object_mut() {
+            Some(properties) => properties,
+            None => {
+                return Err(style_error(format!(
+                    "{} of layer {} is not an object",
+                    group, layer_id
+                )))
+            }
+        };
+
+        if value.is_null() {
+            properties.remove(name);
+        } else {
+            properties.insert(name.to_owned(), value);
+        }
+        Ok(())
+    }
+}
+
+fn style_error(msg: String) -> MapError {
+    MapError::new(MapErrorTag::Style, msg)
 }
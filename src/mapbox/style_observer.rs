@@ -0,0 +1,7 @@
+use super::common::map_error::MapError;
+use super::style_model::StyleModel;
+
+pub trait StyleObserver: Send {
+    fn on_style_loaded(&mut self, style_model: &StyleModel);
+    fn on_style_failed(&mut self, map_error: &MapError);
+}
@@ -0,0 +1,330 @@
+// Ref: https://docs.mapbox.com/mapbox-gl-js/style-spec/types/#color
+//
+// CSS color strings: named colors, #rgb(a), #rrggbb(aa), rgb(a)() and hsl(a)().
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+impl Color {
+    pub fn new(r: f32, g: f32, b: f32, a: f32) -> Color {
+        Color { r, g, b, a }
+    }
+
+    pub fn parse(color: &str) -> Option<Color> {
+        let color = color.trim().to_lowercase();
+
+        if let Some(hex) = color.strip_prefix('#') {
+            return Color::parse_hex(hex);
+        }
+        if let Some(args) = function_args(&color, "rgba").or_else(|| function_args(&color, "rgb")) {
+            return Color::parse_rgb(&args);
+        }
+        if let Some(args) = function_args(&color, "hsla").or_else(|| function_args(&color, "hsl")) {
+            return Color::parse_hsl(&args);
+        }
+
+        NAMED_COLORS
+            .iter()
+            .find(|(name, _)| *name == color)
+            .map(|(_, rgb)| {
+                Color::new(
+                    ((rgb >> 16) & 0xFF) as f32 / 255.0,
+                    ((rgb >> 8) & 0xFF) as f32 / 255.0,
+                    (rgb & 0xFF) as f32 / 255.0,
+                    1.0,
+                )
+            })
+            .or_else(|| {
+                if color == "transparent" {
+                    Some(Color::new(0.0, 0.0, 0.0, 0.0))
+                } else {
+                    None
+                }
+            })
+    }
+
+    // Premultiplied RGBA, ready for the shaders
+    pub fn to_array(&self) -> [f32; 4] {
+        [self.r * self.a, self.g * self.a, self.b * self.a, self.a]
+    }
+
+    fn parse_hex(hex: &str) -> Option<Color> {
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let digit = |inx: usize| u8::from_str_radix(&hex[inx..inx + 1], 16).unwrap() as f32;
+        let byte = |inx: usize| u8::from_str_radix(&hex[inx..inx + 2], 16).unwrap() as f32;
+
+        match hex.len() {
+            3 | 4 => Some(Color::new(
+                digit(0) * 17.0 / 255.0,
+                digit(1) * 17.0 / 255.0,
+                digit(2) * 17.0 / 255.0,
+                if hex.len() == 4 {
+                    digit(3) * 17.0 / 255.0
+                } else {
+                    1.0
+                },
+            )),
+            6 | 8 => Some(Color::new(
+                byte(0) / 255.0,
+                byte(2) / 255.0,
+                byte(4) / 255.0,
+                if hex.len() == 8 { byte(6) / 255.0 } else { 1.0 },
+            )),
+            _ => None,
+        }
+    }
+
+    fn parse_rgb(args: &[&str]) -> Option<Color> {
+        if args.len() != 3 && args.len() != 4 {
+            return None;
+        }
+        let mut channels = [0.0; 3];
+        for (inx, arg) in args[..3].iter().enumerate() {
+            channels[inx] = match parse_percentage(arg) {
+                Some(percentage) => percentage,
+                None => arg.parse::<f32>().ok()? / 255.0,
+            };
+        }
+        Some(Color::new(
+            clamp(channels[0]),
+            clamp(channels[1]),
+            clamp(channels[2]),
+            parse_alpha(args.get(3))?,
+        ))
+    }
+
+    fn parse_hsl(args: &[&str]) -> Option<Color> {
+        if args.len() != 3 && args.len() != 4 {
+            return None;
+        }
+        let h = (args[0].trim_end_matches("deg").parse::<f32>().ok()? % 360.0 + 360.0) % 360.0;
+        let s = clamp(parse_percentage(args[1])?);
+        let l = clamp(parse_percentage(args[2])?);
+
+        // Ref: https://www.w3.org/TR/css-color-3/#hsl-color
+        let m2 = if l <= 0.5 {
+            l * (s + 1.0)
+        } else {
+            l + s - l * s
+        };
+        let m1 = l * 2.0 - m2;
+        let hue = |h: f32| {
+            let h = if h < 0.0 {
+                h + 1.0
+            } else if h > 1.0 {
+                h - 1.0
+            } else {
+                h
+            };
+            if h * 6.0 < 1.0 {
+                m1 + (m2 - m1) * h * 6.0
+            } else if h * 2.0 < 1.0 {
+                m2
+            } else if h * 3.0 < 2.0 {
+                m1 + (m2 - m1) * (2.0 / 3.0 - h) * 6.0
+            } else {
+                m1
+            }
+        };
+        let h = h / 360.0;
+        Some(Color::new(
+            hue(h + 1.0 / 3.0),
+            hue(h),
+            hue(h - 1.0 / 3.0),
+            parse_alpha(args.get(3))?,
+        ))
+    }
+}
+
+fn function_args<'a>(color: &'a str, name: &str) -> Option<Vec<&'a str>> {
+    if !color.starts_with(name) || !color.ends_with(')') {
+        return None;
+    }
+    let args = color[name.len()..].trim_start();
+    if !args.starts_with('(') {
+        return None;
+    }
+    Some(
+        args[1..args.len() - 1]
+            .split(',')
+            .map(|arg| arg.trim())
+            .collect(),
+    )
+}
+
+fn parse_percentage(arg: &str) -> Option<f32> {
+    arg.strip_suffix('%')?
+        .parse::<f32>()
+        .ok()
+        .map(|percentage| percentage / 100.0)
+}
+
+fn parse_alpha(arg: Option<&&str>) -> Option<f32> {
+    match arg {
+        Some(arg) => Some(clamp(arg.parse::<f32>().ok()?)),
+        None => Some(1.0),
+    }
+}
+
+fn clamp(v: f32) -> f32 {
+    v.clamp(0.0, 1.0)
+}
+
+// Ref: https://www.w3.org/TR/css-color-4/#named-colors
+const NAMED_COLORS: &[(&str, u32)] = &[
+    ("aliceblue", 0xF0F8FF),
+    ("antiquewhite", 0xFAEBD7),
+    ("aqua", 0x00FFFF),
+    ("aquamarine", 0x7FFFD4),
+    ("azure", 0xF0FFFF),
+    ("beige", 0xF5F5DC),
+    ("bisque", 0xFFE4C4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xFFEBCD),
+    ("blue", 0x0000FF),
+    ("blueviolet", 0x8A2BE2),
+    ("brown", 0xA52A2A),
+    ("burlywood", 0xDEB887),
+    ("cadetblue", 0x5F9EA0),
+    ("chartreuse", 0x7FFF00),
+    ("chocolate", 0xD2691E),
+    ("coral", 0xFF7F50),
+    ("cornflowerblue", 0x6495ED),
+    ("cornsilk", 0xFFF8DC),
+    ("crimson", 0xDC143C),
+    ("cyan", 0x00FFFF),
+    ("darkblue", 0x00008B),
+    ("darkcyan", 0x008B8B),
+    ("darkgoldenrod", 0xB8860B),
+    ("darkgray", 0xA9A9A9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xA9A9A9),
+    ("darkkhaki", 0xBDB76B),
+    ("darkmagenta", 0x8B008B),
+    ("darkolivegreen", 0x556B2F),
+    ("darkorange", 0xFF8C00),
+    ("darkorchid", 0x9932CC),
+    ("darkred", 0x8B0000),
+    ("darksalmon", 0xE9967A),
+    ("darkseagreen", 0x8FBC8F),
+    ("darkslateblue", 0x483D8B),
+    ("darkslategray", 0x2F4F4F),
+    ("darkslategrey", 0x2F4F4F),
+    ("darkturquoise", 0x00CED1),
+    ("darkviolet", 0x9400D3),
+    ("deeppink", 0xFF1493),
+    ("deepskyblue", 0x00BFFF),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1E90FF),
+    ("firebrick", 0xB22222),
+    ("floralwhite", 0xFFFAF0),
+    ("forestgreen", 0x228B22),
+    ("fuchsia", 0xFF00FF),
+    ("gainsboro", 0xDCDCDC),
+    ("ghostwhite", 0xF8F8FF),
+    ("gold", 0xFFD700),
+    ("goldenrod", 0xDAA520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xADFF2F),
+    ("grey", 0x808080),
+    ("honeydew", 0xF0FFF0),
+    ("hotpink", 0xFF69B4),
+    ("indianred", 0xCD5C5C),
+    ("indigo", 0x4B0082),
+    ("ivory", 0xFFFFF0),
+    ("khaki", 0xF0E68C),
+    ("lavender", 0xE6E6FA),
+    ("lavenderblush", 0xFFF0F5),
+    ("lawngreen", 0x7CFC00),
+    ("lemonchiffon", 0xFFFACD),
+    ("lightblue", 0xADD8E6),
+    ("lightcoral", 0xF08080),
+    ("lightcyan", 0xE0FFFF),
+    ("lightgoldenrodyellow", 0xFAFAD2),
+    ("lightgray", 0xD3D3D3),
+    ("lightgreen", 0x90EE90),
+    ("lightgrey", 0xD3D3D3),
+    ("lightpink", 0xFFB6C1),
+    ("lightsalmon", 0xFFA07A),
+    ("lightseagreen", 0x20B2AA),
+    ("lightskyblue", 0x87CEFA),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xB0C4DE),
+    ("lightyellow", 0xFFFFE0),
+    ("lime", 0x00FF00),
+    ("limegreen", 0x32CD32),
+    ("linen", 0xFAF0E6),
+    ("magenta", 0xFF00FF),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66CDAA),
+    ("mediumblue", 0x0000CD),
+    ("mediumorchid", 0xBA55D3),
+    ("mediumpurple", 0x9370DB),
+    ("mediumseagreen", 0x3CB371),
+    ("mediumslateblue", 0x7B68EE),
+    ("mediumspringgreen", 0x00FA9A),
+    ("mediumturquoise", 0x48D1CC),
+    ("mediumvioletred", 0xC71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xF5FFFA),
+    ("mistyrose", 0xFFE4E1),
+    ("moccasin", 0xFFE4B5),
+    ("navajowhite", 0xFFDEAD),
+    ("navy", 0x000080),
+    ("oldlace", 0xFDF5E6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6B8E23),
+    ("orange", 0xFFA500),
+    ("orangered", 0xFF4500),
+    ("orchid", 0xDA70D6),
+    ("palegoldenrod", 0xEEE8AA),
+    ("palegreen", 0x98FB98),
+    ("paleturquoise", 0xAFEEEE),
+    ("palevioletred", 0xDB7093),
+    ("papayawhip", 0xFFEFD5),
+    ("peachpuff", 0xFFDAB9),
+    ("peru", 0xCD853F),
+    ("pink", 0xFFC0CB),
+    ("plum", 0xDDA0DD),
+    ("powderblue", 0xB0E0E6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xFF0000),
+    ("rosybrown", 0xBC8F8F),
+    ("royalblue", 0x4169E1),
+    ("saddlebrown", 0x8B4513),
+    ("salmon", 0xFA8072),
+    ("sandybrown", 0xF4A460),
+    ("seagreen", 0x2E8B57),
+    ("seashell", 0xFFF5EE),
+    ("sienna", 0xA0522D),
+    ("silver", 0xC0C0C0),
+    ("skyblue", 0x87CEEB),
+    ("slateblue", 0x6A5ACD),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xFFFAFA),
+    ("springgreen", 0x00FF7F),
+    ("steelblue", 0x4682B4),
+    ("tan", 0xD2B48C),
+    ("teal", 0x008080),
+    ("thistle", 0xD8BFD8),
+    ("tomato", 0xFF6347),
+    ("turquoise", 0x40E0D0),
+    ("violet", 0xEE82EE),
+    ("wheat", 0xF5DEB3),
+    ("white", 0xFFFFFF),
+    ("whitesmoke", 0xF5F5F5),
+    ("yellow", 0xFFFF00),
+    ("yellowgreen", 0x9ACD32),
+];
@@ -0,0 +1,458 @@
+// Ref: https://docs.mapbox.com/mapbox-gl-js/style-spec/expressions/
+//
+// Structural checks of expressions and filters: known operators, argument
+// counts and the shape of the operators with special syntax. Nothing is
+// evaluated here and result types are not inferred.
+use super::validator::Diagnostic;
+
+use serde_json::Value;
+
+// (operator, min args, max args)
+const OPERATORS: &[(&str, usize, Option<usize>)] = &[
+    // Variable binding
+    ("let", 3, None),
+    ("var", 1, Some(1)),
+    // Types
+    ("literal", 1, Some(1)),
+    ("array", 1, Some(3)),
+    ("typeof", 1, Some(1)),
+    ("string", 1, None),
+    ("number", 1, None),
+    ("boolean", 1, None),
+    ("object", 1, None),
+    ("collator", 1, Some(1)),
+    ("format", 1, None),
+    ("image", 1, Some(1)),
+    ("number-format", 2, Some(2)),
+    ("to-string", 1, Some(1)),
+    ("to-number", 1, None),
+    ("to-boolean", 1, Some(1)),
+    ("to-rgba", 1, Some(1)),
+    ("to-color", 1, None),
+    // Lookup
+    ("at", 2, Some(2)),
+    ("in", 2, Some(2)),
+    ("index-of", 2, Some(3)),
+    ("slice", 2, Some(3)),
+    ("get", 1, Some(2)),
+    ("has", 1, Some(2)),
+    ("length", 1, Some(1)),
+    // Feature data
+    ("properties", 0, Some(0)),
+    ("feature-state", 1, Some(1)),
+    ("geometry-type", 0, Some(0)),
+    ("id", 0, Some(0)),
+    ("accumulated", 0, Some(0)),
+    ("line-progress", 0, Some(0)),
+    // Camera and heatmap
+    ("zoom", 0, Some(0)),
+    ("heatmap-density", 0, Some(0)),
+    // Decision
+    ("case", 3, None),
+    ("match", 4, None),
+    ("coalesce", 1, None),
+    ("==", 2, Some(3)),
+    ("!=", 2, Some(3)),
+    (">", 2, Some(3)),
+    ("<", 2, Some(3)),
+    (">=", 2, Some(3)),
+    ("<=", 2, Some(3)),
+    ("all", 0, None),
+    ("any", 0, None),
+    ("!", 1, Some(1)),
+    ("within", 1, Some(1)),
+    // Ramps, scales, curves
+    ("step", 4, None),
+    ("interpolate", 4, None),
+    ("interpolate-hcl", 4, None),
+    ("interpolate-lab", 4, None),
+    // Math
+    ("ln2", 0, Some(0)),
+    ("pi", 0, Some(0)),
+    ("e", 0, Some(0)),
+    ("+", 2, None),
+    ("*", 2, None),
+    ("-", 1, Some(2)),
+    ("/", 2, Some(2)),
+    ("%", 2, Some(2)),
+    ("^", 2, Some(2)),
+    ("sqrt", 1, Some(1)),
+    ("log10", 1, Some(1)),
+    ("ln", 1, Some(1)),
+    ("log2", 1, Some(1)),
+    ("sin", 1, Some(1)),
+    ("cos", 1, Some(1)),
+    ("tan", 1, Some(1)),
+    ("asin", 1, Some(1)),
+    ("acos", 1, Some(1)),
+    ("atan", 1, Some(1)),
+    ("min", 1, None),
+    ("max", 1, None),
+    ("round", 1, Some(1)),
+    ("abs", 1, Some(1)),
+    ("ceil", 1, Some(1)),
+    ("floor", 1, Some(1)),
+    ("distance", 1, Some(1)),
+    // Color
+    ("rgb", 3, Some(3)),
+    ("rgba", 4, Some(4)),
+    // String
+    ("is-supported-script", 1, Some(1)),
+    ("upcase", 1, Some(1)),
+    ("downcase", 1, Some(1)),
+    ("concat", 1, None),
+    ("resolved-locale", 1, Some(1)),
+];
+
+pub fn is_operator(name: &str) -> bool {
+    OPERATORS.iter().any(|(operator, _, _)| *operator == name)
+}
+
+// Anything shaped like ["operator", ...] with a known operator
+pub fn is_expression(value: &Value) -> bool {
+    match value.as_array().and_then(|array| array.first()) {
+        Some(Value::String(operator)) => is_operator(operator),
+        _ => false,
+    }
+}
+
+pub fn validate_expression(value: &Value, pointer: &str, diagnostics: &mut Vec<Diagnostic>) {
+    let array = match value.as_array() {
+        Some(array) if !array.is_empty() => array,
+        _ => {
+            diagnostics.push(Diagnostic::new(
+                pointer,
+                "expected an expression array".to_owned(),
+            ));
+            return;
+        }
+    };
+    let operator = match array[0].as_str() {
+        Some(operator) => operator,
+        None => {
+            diagnostics.push(Diagnostic::new(
+                pointer,
+                "expression must start with an operator name; wrap array values in [\"literal\", ...]"
+                    .to_owned(),
+            ));
+            return;
+        }
+    };
+    let (min_args, max_args) = match OPERATORS.iter().find(|(name, _, _)| *name == operator) {
+        Some((_, min_args, max_args)) => (*min_args, *max_args),
+        None => {
+            diagnostics.push(Diagnostic::new(
+                &format!("{}/0", pointer),
+                format!("unknown expression operator \"{}\"", operator),
+            ));
+            return;
+        }
+    };
+
+    let args = &array[1..];
+    if args.len() < min_args || max_args.is_some_and(|max_args| args.len() > max_args) {
+        let expected = match max_args {
+            Some(max_args) if max_args == min_args => format!("{}", min_args),
+            Some(max_args) => format!("{} to {}", min_args, max_args),
+            None => format!("at least {}", min_args),
+        };
+        diagnostics.push(Diagnostic::new(
+            pointer,
+            format!(
+                "\"{}\" expects {} arguments, found {}",
+                operator,
+                expected,
+                args.len()
+            ),
+        ));
+        return;
+    }
+
+    let arg_pointer = |inx: usize| format!("{}/{}", pointer, inx + 1);
+    match operator {
+        "literal" => {}
+        "let" => {
+            if args.len() % 2 == 0 {
+                diagnostics.push(Diagnostic::new(
+                    pointer,
+                    "\"let\" expects name/value pairs followed by an expression".to_owned(),
+                ));
+                return;
+            }
+            for (inx, arg) in args.iter().enumerate() {
+                if inx % 2 == 0 && inx + 1 < args.len() {
+                    expect_string(arg, &arg_pointer(inx), diagnostics);
+                } else {
+                    validate_argument(operator, arg, &arg_pointer(inx), diagnostics);
+                }
+            }
+        }
+        "var" => expect_string(&args[0], &arg_pointer(0), diagnostics),
+        "case" => {
+            if args.len() % 2 == 0 {
+                diagnostics.push(Diagnostic::new(
+                    pointer,
+                    "\"case\" expects condition/output pairs followed by a fallback".to_owned(),
+                ));
+                return;
+            }
+            validate_arguments(operator, args, pointer, diagnostics);
+        }
+        "match" => {
+            if args.len() % 2 == 1 {
+                diagnostics.push(Diagnostic::new(
+                    pointer,
+                    "\"match\" expects an input, label/output pairs and a fallback".to_owned(),
+                ));
+                return;
+            }
+            for (inx, arg) in args.iter().enumerate() {
+                let is_label = inx % 2 == 1 && inx + 1 < args.len();
+                if is_label {
+                    validate_match_label(arg, &arg_pointer(inx), diagnostics);
+                } else {
+                    validate_argument(operator, arg, &arg_pointer(inx), diagnostics);
+                }
+            }
+        }
+        "step" => {
+            if args.len() % 2 == 1 {
+                diagnostics.push(Diagnostic::new(
+                    pointer,
+                    "\"step\" expects an input, a base output and stop/output pairs".to_owned(),
+                ));
+                return;
+            }
+            validate_argument(operator, &args[0], &arg_pointer(0), diagnostics);
+            validate_argument(operator, &args[1], &arg_pointer(1), diagnostics);
+            validate_stops(operator, args, 2, pointer, diagnostics);
+        }
+        "interpolate" | "interpolate-hcl" | "interpolate-lab" => {
+            if args.len() % 2 == 1 {
+                diagnostics.push(Diagnostic::new(
+                    pointer,
+                    format!(
+                        "\"{}\" expects an interpolation type, an input and stop/output pairs",
+                        operator
+                    ),
+                ));
+                return;
+            }
+            validate_interpolation_type(&args[0], &arg_pointer(0), diagnostics);
+            validate_argument(operator, &args[1], &arg_pointer(1), diagnostics);
+            validate_stops(operator, args, 2, pointer, diagnostics);
+        }
+        _ => validate_arguments(operator, args, pointer, diagnostics),
+    }
+}
+
+fn validate_arguments(
+    operator: &str,
+    args: &[Value],
+    pointer: &str,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    for (inx, arg) in args.iter().enumerate() {
+        validate_argument(
+            operator,
+            arg,
+            &format!("{}/{}", pointer, inx + 1),
+            diagnostics,
+        );
+    }
+}
+
+fn validate_argument(
+    operator: &str,
+    arg: &Value,
+    pointer: &str,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    match arg {
+        Value::Array(_) => validate_expression(arg, pointer, diagnostics),
+        // Option objects
+        Value::Object(_) if ["format", "collator", "number-format"].contains(&operator) => {}
+        Value::Object(_) => diagnostics.push(Diagnostic::new(
+            pointer,
+            "object values must be wrapped in [\"literal\", ...]".to_owned(),
+        )),
+        _ => {}
+    }
+}
+
+fn validate_match_label(label: &Value, pointer: &str, diagnostics: &mut Vec<Diagnostic>) {
+    let is_scalar = |v: &Value| v.is_string() || v.is_number();
+    let valid = match label {
+        Value::Array(labels) => !labels.is_empty() && labels.iter().all(is_scalar),
+        _ => is_scalar(label),
+    };
+    if !valid {
+        diagnostics.push(Diagnostic::new(
+            pointer,
+            "match labels must be strings, numbers or arrays of them".to_owned(),
+        ));
+    }
+}
+
+fn validate_interpolation_type(value: &Value, pointer: &str, diagnostics: &mut Vec<Diagnostic>) {
+    let array = value.as_array();
+    let name = array
+        .and_then(|array| array.first())
+        .and_then(|v| v.as_str());
+    let valid = match (name, array) {
+        (Some("linear"), Some(array)) => array.len() == 1,
+        (Some("exponential"), Some(array)) => array.len() == 2 && array[1].is_number(),
+        (Some("cubic-bezier"), Some(array)) => {
+            array.len() == 5 && array[1..].iter().all(|v| v.is_number())
+        }
+        _ => false,
+    };
+    if !valid {
+        diagnostics.push(Diagnostic::new(
+            pointer,
+            "interpolation type must be [\"linear\"], [\"exponential\", base] or [\"cubic-bezier\", x1, y1, x2, y2]"
+                .to_owned(),
+        ));
+    }
+}
+
+// Stop inputs must be number literals in strictly ascending order
+fn validate_stops(
+    operator: &str,
+    args: &[Value],
+    first: usize,
+    pointer: &str,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let mut last_stop: Option<f64> = None;
+    for inx in (first..args.len()).step_by(2) {
+        let stop_pointer = format!("{}/{}", pointer, inx + 1);
+        match args[inx].as_f64() {
+            Some(stop) => {
+                if last_stop.is_some_and(|last_stop| stop <= last_stop) {
+                    diagnostics.push(Diagnostic::new(
+                        &stop_pointer,
+                        format!("\"{}\" stops must be in strictly ascending order", operator),
+                    ));
+                }
+                last_stop = Some(stop);
+            }
+            None => diagnostics.push(Diagnostic::new(
+                &stop_pointer,
+                format!("\"{}\" stop inputs must be number literals", operator),
+            )),
+        }
+        if inx + 1 < args.len() {
+            validate_argument(
+                operator,
+                &args[inx + 1],
+                &format!("{}/{}", pointer, inx + 2),
+                diagnostics,
+            );
+        }
+    }
+}
+
+fn expect_string(value: &Value, pointer: &str, diagnostics: &mut Vec<Diagnostic>) {
+    if !value.is_string() {
+        diagnostics.push(Diagnostic::new(pointer, "expected a string".to_owned()));
+    }
+}
+
+// Filters
+// Ref: https://docs.mapbox.com/mapbox-gl-js/style-spec/other/#other-filter
+
+pub fn validate_filter(filter: &Value, pointer: &str, diagnostics: &mut Vec<Diagnostic>) {
+    if filter.is_boolean() {
+        return;
+    }
+    if is_expression_filter(filter) {
+        validate_expression(filter, pointer, diagnostics);
+    } else {
+        validate_legacy_filter(filter, pointer, diagnostics);
+    }
+}
+
+// Same heuristic as mapbox-gl-js to tell expressions from legacy filters,
+// which share several operator names.
+fn is_expression_filter(filter: &Value) -> bool {
+    let array = match filter.as_array() {
+        Some(array) if !array.is_empty() => array,
+        _ => return false,
+    };
+    match array[0].as_str() {
+        Some("has") => array.len() >= 2 && array[1] != "$id" && array[1] != "$type",
+        Some("in") => array.len() >= 3 && (!array[1].is_string() || array[2].is_array()),
+        Some("!in") | Some("!has") | Some("none") => false,
+        Some("==") | Some("!=") | Some(">") | Some(">=") | Some("<") | Some("<=") => {
+            array.len() != 3 || array[1].is_array() || array[2].is_array()
+        }
+        Some("any") | Some("all") => array[1..]
+            .iter()
+            .all(|f| is_expression_filter(f) || f.is_boolean()),
+        _ => true,
+    }
+}
+
+fn validate_legacy_filter(filter: &Value, pointer: &str, diagnostics: &mut Vec<Diagnostic>) {
+    let array = match filter.as_array() {
+        Some(array) if !array.is_empty() => array,
+        _ => {
+            diagnostics.push(Diagnostic::new(
+                pointer,
+                "filter must be an array".to_owned(),
+            ));
+            return;
+        }
+    };
+    let operator = array[0].as_str().unwrap_or_default();
+    let args = &array[1..];
+    match operator {
+        "all" | "any" | "none" => {
+            for (inx, f) in args.iter().enumerate() {
+                validate_filter(f, &format!("{}/{}", pointer, inx + 1), diagnostics);
+            }
+        }
+        "==" | "!=" | ">" | ">=" | "<" | "<=" => {
+            if args.len() != 2 {
+                diagnostics.push(Diagnostic::new(
+                    pointer,
+                    format!("filter \"{}\" expects a key and a value", operator),
+                ));
+            }
+            validate_legacy_key(args, pointer, diagnostics);
+        }
+        "in" | "!in" => validate_legacy_key(args, pointer, diagnostics),
+        "has" | "!has" => {
+            if args.len() != 1 {
+                diagnostics.push(Diagnostic::new(
+                    pointer,
+                    format!("filter \"{}\" expects a single key", operator),
+                ));
+            }
+            validate_legacy_key(args, pointer, diagnostics);
+        }
+        _ => diagnostics.push(Diagnostic::new(
+            &format!("{}/0", pointer),
+            format!("unknown filter operator \"{}\"", operator),
+        )),
+    }
+}
+
+fn validate_legacy_key(args: &[Value], pointer: &str, diagnostics: &mut Vec<Diagnostic>) {
+    match args.first() {
+        Some(Value::String(_)) => {}
+        _ => diagnostics.push(Diagnostic::new(
+            &format!("{}/1", pointer),
+            "filter key must be a string".to_owned(),
+        )),
+    }
+    for (inx, value) in args.iter().enumerate().skip(1) {
+        if value.is_array() || value.is_object() {
+            diagnostics.push(Diagnostic::new(
+                &format!("{}/{}", pointer, inx + 1),
+                "filter values must be strings, numbers, booleans or null".to_owned(),
+            ));
+        }
+    }
+}
@@ -0,0 +1,414 @@
+// Ref: https://docs.mapbox.com/mapbox-gl-js/style-spec/
+//
+// Typed description of the style spec (v8): which keys are allowed where and
+// what kind of value each property takes.
+pub mod color;
+pub mod expression;
+pub mod validator;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LayerType {
+    Background,
+    Fill,
+    Line,
+    Symbol,
+    Raster,
+    Circle,
+    FillExtrusion,
+    Heatmap,
+    Hillshade,
+}
+
+impl LayerType {
+    pub fn parse(layer_type: &str) -> Option<LayerType> {
+        match layer_type {
+            "background" => Some(LayerType::Background),
+            "fill" => Some(LayerType::Fill),
+            "line" => Some(LayerType::Line),
+            "symbol" => Some(LayerType::Symbol),
+            "raster" => Some(LayerType::Raster),
+            "circle" => Some(LayerType::Circle),
+            "fill-extrusion" => Some(LayerType::FillExtrusion),
+            "heatmap" => Some(LayerType::Heatmap),
+            "hillshade" => Some(LayerType::Hillshade),
+            _ => None,
+        }
+    }
+
+    pub fn needs_source(self) -> bool {
+        self != LayerType::Background
+    }
+
+    pub fn paint_properties(self) -> &'static [(&'static str, PropertyType)] {
+        match self {
+            LayerType::Background => BACKGROUND_PAINT,
+            LayerType::Fill => FILL_PAINT,
+            LayerType::Line => LINE_PAINT,
+            LayerType::Symbol => SYMBOL_PAINT,
+            LayerType::Raster => RASTER_PAINT,
+            LayerType::Circle => CIRCLE_PAINT,
+            LayerType::FillExtrusion => FILL_EXTRUSION_PAINT,
+            LayerType::Heatmap => HEATMAP_PAINT,
+            LayerType::Hillshade => HILLSHADE_PAINT,
+        }
+    }
+
+    pub fn layout_properties(self) -> &'static [(&'static str, PropertyType)] {
+        match self {
+            LayerType::Fill => FILL_LAYOUT,
+            LayerType::Line => LINE_LAYOUT,
+            LayerType::Symbol => SYMBOL_LAYOUT,
+            LayerType::Circle => CIRCLE_LAYOUT,
+            _ => VISIBILITY_LAYOUT,
+        }
+    }
+
+    pub fn paint_property(self, name: &str) -> Option<PropertyType> {
+        find_property(self.paint_properties(), name)
+    }
+
+    pub fn layout_property(self, name: &str) -> Option<PropertyType> {
+        find_property(self.layout_properties(), name)
+    }
+}
+
+fn find_property(
+    properties: &'static [(&'static str, PropertyType)],
+    name: &str,
+) -> Option<PropertyType> {
+    properties
+        .iter()
+        .find(|(property, _)| *property == name)
+        .map(|(_, property_type)| *property_type)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PropertyType {
+    Color,
+    Number,
+    Boolean,
+    String,
+    Enum(&'static [&'static str]),
+    EnumArray(&'static [&'static str]),
+    // Fixed length if given, e.g. [x, y] for translations
+    NumberArray(Option<usize>),
+    StringArray,
+    Formatted,
+    ResolvedImage,
+}
+
+pub const ROOT_KEYS: &[&str] = &[
+    "version",
+    "name",
+    "metadata",
+    "center",
+    "zoom",
+    "bearing",
+    "pitch",
+    "light",
+    "sources",
+    "sprite",
+    "glyphs",
+    "transition",
+    "layers",
+    // Not part of the spec, but read by StyleModel
+    "minzoom",
+    "maxzoom",
+];
+
+pub const LAYER_KEYS: &[&str] = &[
+    "id",
+    "type",
+    "metadata",
+    "source",
+    "source-layer",
+    "minzoom",
+    "maxzoom",
+    "filter",
+    "layout",
+    "paint",
+];
+
+pub fn source_keys(source_type: &str) -> Option<&'static [&'static str]> {
+    match source_type {
+        "vector" => Some(&[
+            "type",
+            "url",
+            "tiles",
+            "bounds",
+            "scheme",
+            "minzoom",
+            "maxzoom",
+            "attribution",
+            "promoteId",
+            "volatile",
+            // Inline tilejson fields read by ManifestModel
+            "name",
+            "tilejson",
+            "tilezooms",
+            "prefetchable",
+            "priority",
+            "center",
+        ]),
+        "raster" => Some(&[
+            "type",
+            "url",
+            "tiles",
+            "bounds",
+            "minzoom",
+            "maxzoom",
+            "tileSize",
+            "scheme",
+            "attribution",
+            "volatile",
+        ]),
+        "raster-dem" => Some(&[
+            "type",
+            "url",
+            "tiles",
+            "bounds",
+            "minzoom",
+            "maxzoom",
+            "tileSize",
+            "attribution",
+            "encoding",
+            "volatile",
+        ]),
+        "geojson" => Some(&[
+            "type",
+            "data",
+            "maxzoom",
+            "attribution",
+            "buffer",
+            "filter",
+            "tolerance",
+            "cluster",
+            "clusterRadius",
+            "clusterMaxZoom",
+            "clusterMinPoints",
+            "clusterProperties",
+            "lineMetrics",
+            "generateId",
+            "promoteId",
+        ]),
+        "image" => Some(&["type", "url", "coordinates"]),
+        "video" => Some(&["type", "urls", "coordinates"]),
+        _ => None,
+    }
+}
+
+const TRANSLATE_ANCHOR: PropertyType = PropertyType::Enum(&["map", "viewport"]);
+const ALIGNMENT: PropertyType = PropertyType::Enum(&["map", "viewport", "auto"]);
+const ANCHORS: &[&str] = &[
+    "center",
+    "left",
+    "right",
+    "top",
+    "bottom",
+    "top-left",
+    "top-right",
+    "bottom-left",
+    "bottom-right",
+];
+
+const VISIBILITY: (&str, PropertyType) = ("visibility", PropertyType::Enum(&["visible", "none"]));
+
+const VISIBILITY_LAYOUT: &[(&str, PropertyType)] = &[VISIBILITY];
+
+const BACKGROUND_PAINT: &[(&str, PropertyType)] = &[
+    ("background-color", PropertyType::Color),
+    ("background-pattern", PropertyType::ResolvedImage),
+    ("background-opacity", PropertyType::Number),
+];
+
+const FILL_LAYOUT: &[(&str, PropertyType)] = &[("fill-sort-key", PropertyType::Number), VISIBILITY];
+
+const FILL_PAINT: &[(&str, PropertyType)] = &[
+    ("fill-antialias", PropertyType::Boolean),
+    ("fill-opacity", PropertyType::Number),
+    ("fill-color", PropertyType::Color),
+    ("fill-outline-color", PropertyType::Color),
+    ("fill-translate", PropertyType::NumberArray(Some(2))),
+    ("fill-translate-anchor", TRANSLATE_ANCHOR),
+    ("fill-pattern", PropertyType::ResolvedImage),
+];
+
+const LINE_LAYOUT: &[(&str, PropertyType)] = &[
+    ("line-cap", PropertyType::Enum(&["butt", "round", "square"])),
+    (
+        "line-join",
+        PropertyType::Enum(&["bevel", "round", "miter"]),
+    ),
+    ("line-miter-limit", PropertyType::Number),
+    ("line-round-limit", PropertyType::Number),
+    ("line-sort-key", PropertyType::Number),
+    VISIBILITY,
+];
+
+const LINE_PAINT: &[(&str, PropertyType)] = &[
+    ("line-opacity", PropertyType::Number),
+    ("line-color", PropertyType::Color),
+    ("line-translate", PropertyType::NumberArray(Some(2))),
+    ("line-translate-anchor", TRANSLATE_ANCHOR),
+    ("line-width", PropertyType::Number),
+    ("line-gap-width", PropertyType::Number),
+    ("line-offset", PropertyType::Number),
+    ("line-blur", PropertyType::Number),
+    ("line-dasharray", PropertyType::NumberArray(None)),
+    ("line-pattern", PropertyType::ResolvedImage),
+    ("line-gradient", PropertyType::Color),
+];
+
+const SYMBOL_LAYOUT: &[(&str, PropertyType)] = &[
+    (
+        "symbol-placement",
+        PropertyType::Enum(&["point", "line", "line-center"]),
+    ),
+    ("symbol-spacing", PropertyType::Number),
+    ("symbol-avoid-edges", PropertyType::Boolean),
+    ("symbol-sort-key", PropertyType::Number),
+    (
+        "symbol-z-order",
+        PropertyType::Enum(&["auto", "viewport-y", "source"]),
+    ),
+    ("icon-allow-overlap", PropertyType::Boolean),
+    ("icon-ignore-placement", PropertyType::Boolean),
+    ("icon-optional", PropertyType::Boolean),
+    ("icon-rotation-alignment", ALIGNMENT),
+    ("icon-size", PropertyType::Number),
+    (
+        "icon-text-fit",
+        PropertyType::Enum(&["none", "width", "height", "both"]),
+    ),
+    ("icon-text-fit-padding", PropertyType::NumberArray(Some(4))),
+    ("icon-image", PropertyType::ResolvedImage),
+    ("icon-rotate", PropertyType::Number),
+    ("icon-padding", PropertyType::Number),
+    ("icon-keep-upright", PropertyType::Boolean),
+    ("icon-offset", PropertyType::NumberArray(Some(2))),
+    ("icon-anchor", PropertyType::Enum(ANCHORS)),
+    ("icon-pitch-alignment", ALIGNMENT),
+    ("text-pitch-alignment", ALIGNMENT),
+    (
+        "text-rotation-alignment",
+        PropertyType::Enum(&["map", "viewport", "viewport-glyph", "auto"]),
+    ),
+    ("text-field", PropertyType::Formatted),
+    ("text-font", PropertyType::StringArray),
+    ("text-size", PropertyType::Number),
+    ("text-max-width", PropertyType::Number),
+    ("text-line-height", PropertyType::Number),
+    ("text-letter-spacing", PropertyType::Number),
+    (
+        "text-justify",
+        PropertyType::Enum(&["auto", "left", "center", "right"]),
+    ),
+    ("text-radial-offset", PropertyType::Number),
+    ("text-variable-anchor", PropertyType::EnumArray(ANCHORS)),
+    ("text-anchor", PropertyType::Enum(ANCHORS)),
+    ("text-max-angle", PropertyType::Number),
+    (
+        "text-writing-mode",
+        PropertyType::EnumArray(&["horizontal", "vertical"]),
+    ),
+    ("text-rotate", PropertyType::Number),
+    ("text-padding", PropertyType::Number),
+    ("text-keep-upright", PropertyType::Boolean),
+    (
+        "text-transform",
+        PropertyType::Enum(&["none", "uppercase", "lowercase"]),
+    ),
+    ("text-offset", PropertyType::NumberArray(Some(2))),
+    ("text-allow-overlap", PropertyType::Boolean),
+    ("text-ignore-placement", PropertyType::Boolean),
+    ("text-optional", PropertyType::Boolean),
+    VISIBILITY,
+];
+
+const SYMBOL_PAINT: &[(&str, PropertyType)] = &[
+    ("icon-opacity", PropertyType::Number),
+    ("icon-color", PropertyType::Color),
+    ("icon-halo-color", PropertyType::Color),
+    ("icon-halo-width", PropertyType::Number),
+    ("icon-halo-blur", PropertyType::Number),
+    ("icon-translate", PropertyType::NumberArray(Some(2))),
+    ("icon-translate-anchor", TRANSLATE_ANCHOR),
+    ("text-opacity", PropertyType::Number),
+    ("text-color", PropertyType::Color),
+    ("text-halo-color", PropertyType::Color),
+    ("text-halo-width", PropertyType::Number),
+    ("text-halo-blur", PropertyType::Number),
+    ("text-translate", PropertyType::NumberArray(Some(2))),
+    ("text-translate-anchor", TRANSLATE_ANCHOR),
+];
+
+const RASTER_PAINT: &[(&str, PropertyType)] = &[
+    ("raster-opacity", PropertyType::Number),
+    ("raster-hue-rotate", PropertyType::Number),
+    ("raster-brightness-min", PropertyType::Number),
+    ("raster-brightness-max", PropertyType::Number),
+    ("raster-saturation", PropertyType::Number),
+    ("raster-contrast", PropertyType::Number),
+    (
+        "raster-resampling",
+        PropertyType::Enum(&["linear", "nearest"]),
+    ),
+    ("raster-fade-duration", PropertyType::Number),
+];
+
+const CIRCLE_LAYOUT: &[(&str, PropertyType)] =
+    &[("circle-sort-key", PropertyType::Number), VISIBILITY];
+
+const CIRCLE_PAINT: &[(&str, PropertyType)] = &[
+    ("circle-radius", PropertyType::Number),
+    ("circle-color", PropertyType::Color),
+    ("circle-blur", PropertyType::Number),
+    ("circle-opacity", PropertyType::Number),
+    ("circle-translate", PropertyType::NumberArray(Some(2))),
+    ("circle-translate-anchor", TRANSLATE_ANCHOR),
+    (
+        "circle-pitch-scale",
+        PropertyType::Enum(&["map", "viewport"]),
+    ),
+    (
+        "circle-pitch-alignment",
+        PropertyType::Enum(&["map", "viewport"]),
+    ),
+    ("circle-stroke-width", PropertyType::Number),
+    ("circle-stroke-color", PropertyType::Color),
+    ("circle-stroke-opacity", PropertyType::Number),
+];
+
+const FILL_EXTRUSION_PAINT: &[(&str, PropertyType)] = &[
+    ("fill-extrusion-opacity", PropertyType::Number),
+    ("fill-extrusion-color", PropertyType::Color),
+    (
+        "fill-extrusion-translate",
+        PropertyType::NumberArray(Some(2)),
+    ),
+    ("fill-extrusion-translate-anchor", TRANSLATE_ANCHOR),
+    ("fill-extrusion-pattern", PropertyType::ResolvedImage),
+    ("fill-extrusion-height", PropertyType::Number),
+    ("fill-extrusion-base", PropertyType::Number),
+    ("fill-extrusion-vertical-gradient", PropertyType::Boolean),
+];
+
+const HEATMAP_PAINT: &[(&str, PropertyType)] = &[
+    ("heatmap-radius", PropertyType::Number),
+    ("heatmap-weight", PropertyType::Number),
+    ("heatmap-intensity", PropertyType::Number),
+    ("heatmap-color", PropertyType::Color),
+    ("heatmap-opacity", PropertyType::Number),
+];
+
+const HILLSHADE_PAINT: &[(&str, PropertyType)] = &[
+    ("hillshade-illumination-direction", PropertyType::Number),
+    (
+        "hillshade-illumination-anchor",
+        PropertyType::Enum(&["map", "viewport"]),
+    ),
+    ("hillshade-exaggeration", PropertyType::Number),
+    ("hillshade-shadow-color", PropertyType::Color),
+    ("hillshade-highlight-color", PropertyType::Color),
+    ("hillshade-accent-color", PropertyType::Color),
+];
@@ -0,0 +1,653 @@
+// Checks a style document against the typed spec in `style_spec`. Every problem
+// is reported with a JSON pointer (RFC 6901) to the offending value, e.g.
+// `/layers/3/paint/fill-color`.
+use super::color::Color;
+use super::expression::{is_expression, validate_expression, validate_filter};
+use super::{source_keys, LayerType, PropertyType, LAYER_KEYS, ROOT_KEYS};
+
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter, Result};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub pointer: String,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn new(pointer: &str, message: String) -> Diagnostic {
+        Diagnostic {
+            pointer: pointer.to_owned(),
+            message,
+        }
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        // The empty pointer refers to the whole document
+        let pointer = if self.pointer.is_empty() {
+            "/"
+        } else {
+            &self.pointer
+        };
+        write!(f, "{}: {}", pointer, &self.message)
+    }
+}
+
+pub fn validate_style_json(json: &str) -> Vec<Diagnostic> {
+    match serde_json::from_str::<Value>(json) {
+        Ok(style) => validate_style(&style),
+        Err(err) => vec![Diagnostic::new("", format!("invalid JSON: {}", err))],
+    }
+}
+
+pub fn validate_style(style: &Value) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+
+    let root = match style.as_object() {
+        Some(root) => root,
+        None => {
+            diagnostics.push(Diagnostic::new("", "style must be an object".to_owned()));
+            return diagnostics;
+        }
+    };
+
+    check_unknown_keys(root, ROOT_KEYS, "", &mut diagnostics);
+    if root.get("version") != Some(&Value::from(8)) {
+        diagnostics.push(Diagnostic::new(
+            "/version",
+            "style version must be 8".to_owned(),
+        ));
+    }
+    for key in ["name", "sprite"].iter() {
+        expect_type(root, key, "", PropertyType::String, &mut diagnostics);
+    }
+    if let Some(glyphs) = root.get("glyphs") {
+        match glyphs.as_str() {
+            Some(glyphs) if glyphs.contains("{fontstack}") && glyphs.contains("{range}") => {}
+            _ => diagnostics.push(Diagnostic::new(
+                "/glyphs",
+                "glyphs must be a URL template with {fontstack} and {range}".to_owned(),
+            )),
+        }
+    }
+    for key in ["zoom", "bearing", "pitch"].iter() {
+        expect_type(root, key, "", PropertyType::Number, &mut diagnostics);
+    }
+    expect_type(
+        root,
+        "center",
+        "",
+        PropertyType::NumberArray(Some(2)),
+        &mut diagnostics,
+    );
+    check_zoom_range(root, "", 0.0, &mut diagnostics);
+
+    let sources = match root.get("sources").map(|sources| sources.as_object()) {
+        Some(Some(sources)) => {
+            for (source_id, source) in sources.iter() {
+                validate_source(
+                    source,
+                    &format!("/sources/{}", escape(source_id)),
+                    &mut diagnostics,
+                );
+            }
+            sources.to_owned()
+        }
+        _ => {
+            diagnostics.push(Diagnostic::new(
+                "/sources",
+                "style must have a sources object".to_owned(),
+            ));
+            Map::new()
+        }
+    };
+
+    match root.get("layers").map(|layers| layers.as_array()) {
+        Some(Some(layers)) => {
+            let mut layer_ids = HashSet::new();
+            for (inx, layer) in layers.iter().enumerate() {
+                validate_layer(
+                    layer,
+                    &format!("/layers/{}", inx),
+                    &sources,
+                    &mut layer_ids,
+                    &mut diagnostics,
+                );
+            }
+        }
+        _ => diagnostics.push(Diagnostic::new(
+            "/layers",
+            "style must have a layers array".to_owned(),
+        )),
+    }
+
+    diagnostics
+}
+
+fn validate_source(source: &Value, pointer: &str, diagnostics: &mut Vec<Diagnostic>) {
+    let source = match source.as_object() {
+        Some(source) => source,
+        None => {
+            diagnostics.push(Diagnostic::new(
+                pointer,
+                "source must be an object".to_owned(),
+            ));
+            return;
+        }
+    };
+    let source_type = source
+        .get("type")
+        .and_then(|t| t.as_str())
+        .unwrap_or_default();
+    let keys = match source_keys(source_type) {
+        Some(keys) => keys,
+        None => {
+            diagnostics.push(Diagnostic::new(
+                &format!("{}/type", pointer),
+                format!("unknown source type \"{}\"", source_type),
+            ));
+            return;
+        }
+    };
+
+    check_unknown_keys(source, keys, pointer, diagnostics);
+    expect_type(source, "url", pointer, PropertyType::String, diagnostics);
+    expect_type(
+        source,
+        "tiles",
+        pointer,
+        PropertyType::StringArray,
+        diagnostics,
+    );
+    expect_type(
+        source,
+        "bounds",
+        pointer,
+        PropertyType::NumberArray(Some(4)),
+        diagnostics,
+    );
+    expect_type(
+        source,
+        "scheme",
+        pointer,
+        PropertyType::Enum(&["xyz", "tms"]),
+        diagnostics,
+    );
+    expect_type(
+        source,
+        "tileSize",
+        pointer,
+        PropertyType::Number,
+        diagnostics,
+    );
+    check_zoom_range(source, pointer, 0.0, diagnostics);
+
+    let has_tiles = ["url", "tiles", "data", "urls"]
+        .iter()
+        .any(|key| source.contains_key(*key));
+    if !has_tiles {
+        diagnostics.push(Diagnostic::new(
+            pointer,
+            format!("{} source needs a url or tiles", source_type),
+        ));
+    }
+}
+
+fn validate_layer(
+    layer: &Value,
+    pointer: &str,
+    sources: &Map<String, Value>,
+    layer_ids: &mut HashSet<String>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let layer = match layer.as_object() {
+        Some(layer) => layer,
+        None => {
+            diagnostics.push(Diagnostic::new(
+                pointer,
+                "layer must be an object".to_owned(),
+            ));
+            return;
+        }
+    };
+
+    check_unknown_keys(layer, LAYER_KEYS, pointer, diagnostics);
+
+    match layer.get("id").and_then(|id| id.as_str()) {
+        Some(id) => {
+            if !layer_ids.insert(id.to_owned()) {
+                diagnostics.push(Diagnostic::new(
+                    &format!("{}/id", pointer),
+                    format!("duplicate layer id \"{}\"", id),
+                ));
+            }
+        }
+        None => diagnostics.push(Diagnostic::new(
+            &format!("{}/id", pointer),
+            "layer must have a string id".to_owned(),
+        )),
+    }
+
+    let type_name = match layer.get("type") {
+        Some(type_name) => type_name.as_str().unwrap_or_default(),
+        None => {
+            diagnostics.push(Diagnostic::new(
+                &format!("{}/type", pointer),
+                "layer is missing the required \"type\"".to_owned(),
+            ));
+            return;
+        }
+    };
+    let layer_type = match LayerType::parse(type_name) {
+        Some(layer_type) => layer_type,
+        None => {
+            diagnostics.push(Diagnostic::new(
+                &format!("{}/type", pointer),
+                format!("unknown layer type \"{}\"", type_name),
+            ));
+            return;
+        }
+    };
+
+    if layer_type.needs_source() {
+        match layer.get("source").and_then(|source| source.as_str()) {
+            Some(source_id) => match sources.get(source_id) {
+                Some(source) => {
+                    let is_vector = source["type"].as_str() == Some("vector");
+                    if is_vector && !layer.contains_key("source-layer") {
+                        diagnostics.push(Diagnostic::new(
+                            pointer,
+                            "layers of a vector source must have a source-layer".to_owned(),
+                        ));
+                    }
+                }
+                None => diagnostics.push(Diagnostic::new(
+                    &format!("{}/source", pointer),
+                    format!("source \"{}\" is not defined", source_id),
+                )),
+            },
+            None => diagnostics.push(Diagnostic::new(
+                &format!("{}/source", pointer),
+                format!("{} layer must have a source", type_name),
+            )),
+        }
+    }
+    expect_type(
+        layer,
+        "source-layer",
+        pointer,
+        PropertyType::String,
+        diagnostics,
+    );
+    check_zoom_range(layer, pointer, 24.0, diagnostics);
+
+    if let Some(filter) = layer.get("filter") {
+        validate_filter(filter, &format!("{}/filter", pointer), diagnostics);
+    }
+
+    for group in ["layout", "paint"].iter() {
+        let group_pointer = format!("{}/{}", pointer, group);
+        let properties = match layer.get(*group) {
+            Some(Value::Object(properties)) => properties,
+            Some(_) => {
+                diagnostics.push(Diagnostic::new(
+                    &group_pointer,
+                    format!("{} must be an object", group),
+                ));
+                continue;
+            }
+            None => continue,
+        };
+
+        for (name, value) in properties.iter() {
+            let property_pointer = format!("{}/{}", group_pointer, escape(name));
+            let is_paint = *group == "paint";
+            let property_type = if is_paint {
+                layer_type.paint_property(name)
+            } else {
+                layer_type.layout_property(name)
+            };
+
+            match property_type {
+                Some(property_type) => {
+                    validate_property_value(value, property_type, &property_pointer, diagnostics)
+                }
+                None if is_paint && is_transition_of(layer_type, name) => {
+                    validate_transition(value, &property_pointer, diagnostics)
+                }
+                None => diagnostics.push(Diagnostic::new(
+                    &property_pointer,
+                    format!(
+                        "unknown {} property \"{}\" for {} layer",
+                        group, name, type_name
+                    ),
+                )),
+            }
+        }
+    }
+}
+
+fn is_transition_of(layer_type: LayerType, name: &str) -> bool {
+    name.ends_with("-transition")
+        && layer_type
+            .paint_property(&name[..name.len() - "-transition".len()])
+            .is_some()
+}
+
+fn validate_transition(value: &Value, pointer: &str, diagnostics: &mut Vec<Diagnostic>) {
+    match value.as_object() {
+        Some(transition) => {
+            check_unknown_keys(transition, &["duration", "delay"], pointer, diagnostics);
+            for key in ["duration", "delay"].iter() {
+                expect_type(transition, key, pointer, PropertyType::Number, diagnostics);
+            }
+        }
+        None => diagnostics.push(Diagnostic::new(
+            pointer,
+            "transition must be an object".to_owned(),
+        )),
+    }
+}
+
+pub fn validate_property_value(
+    value: &Value,
+    property_type: PropertyType,
+    pointer: &str,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    if is_expression(value) {
+        validate_expression(value, pointer, diagnostics);
+        return;
+    }
+
+    // Legacy functions: {"stops": [[input, output], ...], "base": ..., ...}
+    if let Some(function) = value.as_object() {
+        check_unknown_keys(
+            function,
+            &["type", "property", "base", "stops", "default", "colorSpace"],
+            pointer,
+            diagnostics,
+        );
+        if let Some(stops) = function.get("stops") {
+            let stops_pointer = format!("{}/stops", pointer);
+            match stops.as_array() {
+                Some(stops) => {
+                    for (inx, stop) in stops.iter().enumerate() {
+                        let stop_pointer = format!("{}/{}", stops_pointer, inx);
+                        match stop.as_array() {
+                            Some(stop) if stop.len() == 2 => validate_property_value(
+                                &stop[1],
+                                property_type,
+                                &format!("{}/1", stop_pointer),
+                                diagnostics,
+                            ),
+                            _ => diagnostics.push(Diagnostic::new(
+                                &stop_pointer,
+                                "function stop must be an [input, output] pair".to_owned(),
+                            )),
+                        }
+                    }
+                }
+                None => diagnostics.push(Diagnostic::new(
+                    &stops_pointer,
+                    "function stops must be an array".to_owned(),
+                )),
+            }
+        }
+        return;
+    }
+
+    if value.is_array() && !matches_literal_array(value, property_type) {
+        // Not a literal of this type, so it can only be a broken expression
+        validate_expression(value, pointer, diagnostics);
+        return;
+    }
+
+    if let Some(message) = type_mismatch(value, property_type) {
+        diagnostics.push(Diagnostic::new(pointer, message));
+    }
+}
+
+fn matches_literal_array(value: &Value, property_type: PropertyType) -> bool {
+    match property_type {
+        PropertyType::NumberArray(_) => value.as_array().unwrap().iter().all(|v| v.is_number()),
+        PropertyType::StringArray | PropertyType::EnumArray(_) => {
+            value.as_array().unwrap().iter().all(|v| v.is_string())
+        }
+        _ => false,
+    }
+}
+
+fn type_mismatch(value: &Value, property_type: PropertyType) -> Option<String> {
+    match property_type {
+        PropertyType::Color => match value.as_str() {
+            Some(color) if Color::parse(color).is_some() => None,
+            Some(color) => Some(format!("\"{}\" is not a valid color", color)),
+            None => Some("expected a color string".to_owned()),
+        },
+        PropertyType::Number if !value.is_number() => Some("expected a number".to_owned()),
+        PropertyType::Boolean if !value.is_boolean() => Some("expected a boolean".to_owned()),
+        PropertyType::String | PropertyType::Formatted | PropertyType::ResolvedImage
+            if !value.is_string() =>
+        {
+            Some("expected a string".to_owned())
+        }
+        PropertyType::Enum(options) => match value.as_str() {
+            Some(option) if options.contains(&option) => None,
+            _ => Some(format!("expected one of {:?}", options)),
+        },
+        PropertyType::EnumArray(options) => match value.as_array() {
+            Some(array)
+                if array
+                    .iter()
+                    .all(|v| v.as_str().is_some_and(|v| options.contains(&v))) =>
+            {
+                None
+            }
+            _ => Some(format!("expected an array of {:?}", options)),
+        },
+        PropertyType::NumberArray(len) => match value.as_array() {
+            Some(array) if array.iter().all(|v| v.is_number()) => match len {
+                Some(len) if array.len() != len => {
+                    Some(format!("expected an array of {} numbers", len))
+                }
+                _ => None,
+            },
+            _ => Some("expected an array of numbers".to_owned()),
+        },
+        PropertyType::StringArray => match value.as_array() {
+            Some(array) if array.iter().all(|v| v.is_string()) => None,
+            _ => Some("expected an array of strings".to_owned()),
+        },
+        _ => None,
+    }
+}
+
+fn expect_type(
+    object: &Map<String, Value>,
+    key: &str,
+    pointer: &str,
+    property_type: PropertyType,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    if let Some(value) = object.get(key) {
+        if let Some(message) = type_mismatch(value, property_type) {
+            diagnostics.push(Diagnostic::new(
+                &format!("{}/{}", pointer, escape(key)),
+                message,
+            ));
+        }
+    }
+}
+
+fn check_unknown_keys(
+    object: &Map<String, Value>,
+    known_keys: &[&str],
+    pointer: &str,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    for key in object.keys() {
+        if !known_keys.contains(&key.as_str()) {
+            diagnostics.push(Diagnostic::new(
+                &format!("{}/{}", pointer, escape(key)),
+                format!("unknown property \"{}\"", key),
+            ));
+        }
+    }
+}
+
+// `max_zoom_limit` of 0.0 means there's no upper limit to check against
+fn check_zoom_range(
+    object: &Map<String, Value>,
+    pointer: &str,
+    max_zoom_limit: f64,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    for key in ["minzoom", "maxzoom"].iter() {
+        expect_type(object, key, pointer, PropertyType::Number, diagnostics);
+        if let Some(zoom) = object.get(*key).and_then(|zoom| zoom.as_f64()) {
+            if zoom < 0.0 || (max_zoom_limit > 0.0 && zoom > max_zoom_limit) {
+                diagnostics.push(Diagnostic::new(
+                    &format!("{}/{}", pointer, key),
+                    format!("{} {} is out of range", key, zoom),
+                ));
+            }
+        }
+    }
+
+    let min_zoom = object.get("minzoom").and_then(|zoom| zoom.as_f64());
+    let max_zoom = object.get("maxzoom").and_then(|zoom| zoom.as_f64());
+    if let (Some(min_zoom), Some(max_zoom)) = (min_zoom, max_zoom) {
+        if min_zoom > max_zoom {
+            diagnostics.push(Diagnostic::new(
+                &format!("{}/minzoom", pointer),
+                format!("minzoom {} is greater than maxzoom {}", min_zoom, max_zoom),
+            ));
+        }
+    }
+}
+
+// Ref: https://tools.ietf.org/html/rfc6901#section-3
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn validate_layer_json(layer: Value) -> Vec<Diagnostic> {
+        validate_style(&json!({
+            "version": 8,
+            "sources": {},
+            "layers": [layer],
+        }))
+    }
+
+    fn validate_opacity(expression: Value) -> Vec<Diagnostic> {
+        validate_layer_json(json!({
+            "id": "background",
+            "type": "background",
+            "paint": { "background-opacity": expression },
+        }))
+    }
+
+    #[test]
+    fn accepts_step() {
+        assert_eq!(
+            validate_opacity(json!(["step", ["zoom"], 1, 10, 0.5])),
+            vec![]
+        );
+        assert_eq!(
+            validate_opacity(json!(["step", ["zoom"], 1, 10, 0.5, 12, 0])),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn rejects_broken_step() {
+        // No stop/output pair
+        assert_eq!(validate_opacity(json!(["step", ["zoom"], 1])).len(), 1);
+        // Stop without an output
+        assert_eq!(validate_opacity(json!(["step", ["zoom"], 1, 10])).len(), 1);
+        assert_eq!(
+            validate_opacity(json!(["step", ["zoom"], 1, 10, 0.5, 12])).len(),
+            1
+        );
+        // Stops out of order
+        let diagnostics = validate_opacity(json!(["step", ["zoom"], 1, 10, 0.5, 8, 0]));
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].pointer,
+            "/layers/0/paint/background-opacity/5"
+        );
+    }
+
+    #[test]
+    fn accepts_interpolate() {
+        assert_eq!(
+            validate_opacity(json!(["interpolate", ["linear"], ["zoom"], 10, 0, 14, 1])),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn rejects_broken_interpolate() {
+        assert_eq!(
+            validate_opacity(json!(["interpolate", ["linear"], ["zoom"], 10, 0, 14])).len(),
+            1
+        );
+        assert_eq!(
+            validate_opacity(json!(["interpolate", ["linear"], ["zoom"], 10])).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn accepts_match() {
+        assert_eq!(
+            validate_opacity(json!([
+                "match",
+                ["get", "class"],
+                "park",
+                1,
+                ["a", "b"],
+                0.5,
+                0
+            ])),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn rejects_broken_match() {
+        // No fallback
+        assert_eq!(
+            validate_opacity(json!(["match", ["get", "class"], "park", 1, "lake", 0.5])).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn reports_missing_layer_type() {
+        let diagnostics = validate_layer_json(json!({ "id": "untyped" }));
+        assert_eq!(
+            diagnostics,
+            vec![Diagnostic::new(
+                "/layers/0/type",
+                "layer is missing the required \"type\"".to_owned()
+            )]
+        );
+    }
+
+    #[test]
+    fn reports_unknown_layer_type() {
+        let diagnostics = validate_layer_json(json!({ "id": "odd", "type": "odd" }));
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "unknown layer type \"odd\"");
+    }
+}
@@ -0,0 +1,102 @@
+// Ref: https://github.com/mapbox/mapbox-gl-js/blob/main/src/symbol/collision_index.js
+//
+// Screen space index of everything placed so far. Boxes are bucketed into a
+// grid of cells so that a collision check only looks at its neighbours.
+
+// Labels a bit off screen are still placed so that they don't pop in
+// while panning
+const VIEWPORT_PADDING: f32 = 100.0;
+const CELL_SIZE: f32 = 64.0;
+
+// Axis aligned box in screen pixels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CollisionBox {
+    pub x1: f32,
+    pub y1: f32,
+    pub x2: f32,
+    pub y2: f32,
+}
+
+impl CollisionBox {
+    pub fn padded(self, padding: f32) -> CollisionBox {
+        CollisionBox {
+            x1: self.x1 - padding,
+            y1: self.y1 - padding,
+            x2: self.x2 + padding,
+            y2: self.y2 + padding,
+        }
+    }
+
+    pub fn intersects(&self, other: &CollisionBox) -> bool {
+        self.x1 < other.x2 && other.x1 < self.x2 && self.y1 < other.y2 && other.y1 < self.y2
+    }
+}
+
+pub struct CollisionIndex {
+    width: f32,
+    height: f32,
+    cols: usize,
+    rows: usize,
+    cells: Vec<Vec<usize>>,
+    boxes: Vec<CollisionBox>,
+}
+
+impl CollisionIndex {
+    pub fn new(width: f32, height: f32) -> CollisionIndex {
+        let cols = ((width + 2.0 * VIEWPORT_PADDING) / CELL_SIZE).ceil() as usize;
+        let rows = ((height + 2.0 * VIEWPORT_PADDING) / CELL_SIZE).ceil() as usize;
+        CollisionIndex {
+            width,
+            height,
+            cols,
+            rows,
+            cells: vec![vec![]; cols * rows],
+            boxes: vec![],
+        }
+    }
+
+    pub fn is_on_screen(&self, collision_box: &CollisionBox) -> bool {
+        collision_box.x2 >= -VIEWPORT_PADDING
+            && collision_box.y2 >= -VIEWPORT_PADDING
+            && collision_box.x1 <= self.width + VIEWPORT_PADDING
+            && collision_box.y1 <= self.height + VIEWPORT_PADDING
+    }
+
+    // Whether the box can go in without overlapping anything placed before
+    pub fn is_free(&self, collision_box: &CollisionBox) -> bool {
+        for cell in self.covered_cells(collision_box) {
+            for inx in self.cells[cell].iter() {
+                if self.boxes[*inx].intersects(collision_box) {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    // Makes the box an obstacle for everything placed afterwards
+    pub fn insert(&mut self, collision_box: CollisionBox) {
+        let inx = self.boxes.len();
+        for cell in self.covered_cells(&collision_box) {
+            self.cells[cell].push(inx);
+        }
+        self.boxes.push(collision_box);
+    }
+
+    fn covered_cells(&self, collision_box: &CollisionBox) -> Vec<usize> {
+        let col = |x: f32| {
+            (((x + VIEWPORT_PADDING) / CELL_SIZE).floor().max(0.0) as usize).min(self.cols - 1)
+        };
+        let row = |y: f32| {
+            (((y + VIEWPORT_PADDING) / CELL_SIZE).floor().max(0.0) as usize).min(self.rows - 1)
+        };
+
+        let mut cells = vec![];
+        for r in row(collision_box.y1)..=row(collision_box.y2) {
+            for c in col(collision_box.x1)..=col(collision_box.x2) {
+                cells.push(r * self.cols + c);
+            }
+        }
+        cells
+    }
+}
@@ -0,0 +1,335 @@
+// Ref: https://github.com/mapbox/mapbox-gl-js/blob/main/src/symbol/get_anchors.js
+// Ref: https://github.com/mapbox/mapbox-gl-js/blob/main/src/symbol/check_max_angle.js
+//
+// Anchors for labels following a line. All lengths are in normalized tile
+// coords, like the geometry.
+use super::symbol_layout::distance;
+
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+#[derive(Debug, Clone)]
+pub struct LineAnchor {
+    pub line: Arc<Vec<(f32, f32)>>,
+    // The anchor lies on the segment from line[segment] to line[segment + 1]
+    pub segment: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Anchor {
+    pub point: (f32, f32),
+    pub line: Option<LineAnchor>,
+}
+
+// One anchor every `spacing` along the line where the label fits and doesn't
+// bend by more than `max_angle` (radians) within `angle_window`
+pub fn get_line_anchors(
+    line: &Arc<Vec<(f32, f32)>>,
+    spacing: f32,
+    label_length: f32,
+    angle_window: f32,
+    max_angle: f32,
+) -> Vec<Anchor> {
+    if line.len() < 2 {
+        return vec![];
+    }
+
+    // Labels must not run into each other
+    let spacing = if spacing - label_length < spacing / 4.0 {
+        label_length + spacing / 4.0
+    } else {
+        spacing
+    };
+
+    // Lines which go on in the neighbouring tile continue their rhythm there
+    let first = line[0];
+    let is_continued = first.0 == 0.0 || first.0 == 1.0 || first.1 == 0.0 || first.1 == 1.0;
+    let offset = if is_continued {
+        (spacing / 2.0) % spacing
+    } else {
+        (label_length / 2.0 + label_length / 10.0) % spacing
+    };
+
+    let anchors = resample(line, offset, spacing, label_length, angle_window, max_angle);
+    if !anchors.is_empty() || is_continued {
+        return anchors;
+    }
+
+    // Short lines may still fit one label in their middle
+    let length = line_length(line);
+    resample(
+        line,
+        length / 2.0,
+        length,
+        label_length,
+        angle_window,
+        max_angle,
+    )
+}
+
+// Single anchor in the middle of the line, for "line-center"
+pub fn get_center_anchor(
+    line: &Arc<Vec<(f32, f32)>>,
+    label_length: f32,
+    angle_window: f32,
+    max_angle: f32,
+) -> Option<Anchor> {
+    let length = line_length(line);
+    if length < label_length {
+        return None;
+    }
+
+    let mut remaining = length / 2.0;
+    for segment in 0..line.len() - 1 {
+        let (a, b) = (line[segment], line[segment + 1]);
+        let segment_length = distance(a, b);
+        if remaining <= segment_length && segment_length > 0.0 {
+            let t = remaining / segment_length;
+            let anchor = Anchor {
+                point: (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t),
+                line: Some(LineAnchor {
+                    line: line.clone(),
+                    segment,
+                }),
+            };
+            if check_max_angle(line, &anchor, label_length, angle_window, max_angle) {
+                return Some(anchor);
+            }
+            return None;
+        }
+        remaining -= segment_length;
+    }
+    None
+}
+
+fn resample(
+    line: &Arc<Vec<(f32, f32)>>,
+    offset: f32,
+    spacing: f32,
+    label_length: f32,
+    angle_window: f32,
+    max_angle: f32,
+) -> Vec<Anchor> {
+    let length = line_length(line);
+    let half_label_length = label_length / 2.0;
+    let mut anchors = vec![];
+    if spacing <= 0.0 {
+        return anchors;
+    }
+
+    let mut walked = 0.0;
+    let mut marked = offset - spacing;
+    for segment in 0..line.len() - 1 {
+        let (a, b) = (line[segment], line[segment + 1]);
+        let segment_length = distance(a, b);
+
+        while marked + spacing < walked + segment_length {
+            marked += spacing;
+            let t = (marked - walked) / segment_length;
+            let point = (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t);
+
+            // Anchors outside of the tile are placed by the neighbouring tile
+            let in_tile = point.0 >= 0.0 && point.0 < 1.0 && point.1 >= 0.0 && point.1 < 1.0;
+            let fits = marked - half_label_length >= 0.0 && marked + half_label_length <= length;
+            if !in_tile || !fits {
+                continue;
+            }
+
+            let anchor = Anchor {
+                point,
+                line: Some(LineAnchor {
+                    line: line.clone(),
+                    segment,
+                }),
+            };
+            if check_max_angle(line, &anchor, label_length, angle_window, max_angle) {
+                anchors.push(anchor);
+            }
+        }
+        walked += segment_length;
+    }
+    anchors
+}
+
+// Whether the line bends by less than `max_angle` within any `angle_window`
+// long stretch under the label
+fn check_max_angle(
+    line: &[(f32, f32)],
+    anchor: &Anchor,
+    label_length: f32,
+    angle_window: f32,
+    max_angle: f32,
+) -> bool {
+    let segment = match anchor.line.as_ref() {
+        Some(line_anchor) => line_anchor.segment,
+        None => return true,
+    };
+    if label_length <= 0.0 {
+        return true;
+    }
+
+    // Move backwards to the first vertex under the label
+    let mut point = anchor.point;
+    let mut index = segment as isize + 1;
+    let mut anchor_distance = 0.0;
+    while anchor_distance > -label_length / 2.0 {
+        index -= 1;
+        if index < 0 {
+            return false;
+        }
+        anchor_distance -= distance(line[index as usize], point);
+        point = line[index as usize];
+    }
+    let mut index = index as usize;
+    anchor_distance += distance(line[index], line[index + 1]);
+    index += 1;
+
+    // Move forwards along the label, summing up the corners
+    let mut recent_corners: Vec<(f32, f32)> = vec![]; // (distance, angle delta)
+    let mut recent_angle_delta = 0.0;
+    while anchor_distance < label_length / 2.0 {
+        if index + 1 >= line.len() {
+            return false;
+        }
+        let (prev, current, next) = (line[index - 1], line[index], line[index + 1]);
+        let angle_delta = angle_to(prev, current) - angle_to(current, next);
+        let angle_delta = (((angle_delta + 3.0 * PI) % (2.0 * PI)) - PI).abs();
+
+        recent_corners.push((anchor_distance, angle_delta));
+        recent_angle_delta += angle_delta;
+        while anchor_distance - recent_corners[0].0 > angle_window {
+            recent_angle_delta -= recent_corners.remove(0).1;
+        }
+        if recent_angle_delta > max_angle {
+            return false;
+        }
+
+        index += 1;
+        anchor_distance += distance(current, next);
+    }
+    true
+}
+
+pub fn angle_to(from: (f32, f32), to: (f32, f32)) -> f32 {
+    (to.1 - from.1).atan2(to.0 - from.0)
+}
+
+pub fn line_length(line: &[(f32, f32)]) -> f32 {
+    line.windows(2).map(|w| distance(w[0], w[1])).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-5;
+
+    fn line(points: &[(f32, f32)]) -> Arc<Vec<(f32, f32)>> {
+        Arc::new(points.to_vec())
+    }
+
+    // A right angle 0.4 along the line, which is 0.8 long
+    fn bent_line() -> Arc<Vec<(f32, f32)>> {
+        line(&[(0.1, 0.5), (0.5, 0.5), (0.5, 0.9)])
+    }
+
+    fn assert_points(anchors: &[Anchor], expected: &[(f32, f32)]) {
+        let points: Vec<(f32, f32)> = anchors.iter().map(|anchor| anchor.point).collect();
+        assert_eq!(points.len(), expected.len(), "{:?}", points);
+        for (point, expected) in points.iter().zip(expected.iter()) {
+            assert!(
+                (point.0 - expected.0).abs() < EPSILON && (point.1 - expected.1).abs() < EPSILON,
+                "{:?} != {:?}",
+                points,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn spaces_anchors_along_a_straight_line() {
+        let straight = line(&[(0.05, 0.5), (0.95, 0.5)]);
+        // The first anchor leaves room for half a label and a bit
+        let anchors = get_line_anchors(&straight, 0.25, 0.1, 0.05, PI / 4.0);
+        assert_points(
+            &anchors,
+            &[(0.11, 0.5), (0.36, 0.5), (0.61, 0.5), (0.86, 0.5)],
+        );
+        for anchor in anchors.iter() {
+            assert_eq!(anchor.line.as_ref().unwrap().segment, 0);
+        }
+
+        // Lines coming in from the neighbouring tile keep its rhythm, the
+        // last anchor would leave the label hanging off the end
+        let continued = line(&[(0.0, 0.5), (0.9, 0.5)]);
+        let anchors = get_line_anchors(&continued, 0.25, 0.1, 0.05, PI / 4.0);
+        assert_points(&anchors, &[(0.125, 0.5), (0.375, 0.5), (0.625, 0.5)]);
+    }
+
+    #[test]
+    fn spacing_makes_room_for_long_labels() {
+        let line = line(&[(0.05, 0.5), (0.95, 0.5)]);
+        // Spaced a quarter of the spacing more than the label is long
+        let anchors = get_line_anchors(&line, 0.25, 0.3, 0.05, PI / 4.0);
+        assert_points(&anchors, &[(0.23, 0.5), (0.5925, 0.5)]);
+
+        // Longer than the line
+        assert!(get_line_anchors(&line, 0.25, 1.0, 0.05, PI / 4.0).is_empty());
+    }
+
+    #[test]
+    fn short_lines_get_a_label_in_their_middle() {
+        let line = line(&[(0.4, 0.5), (0.6, 0.5)]);
+        let anchors = get_line_anchors(&line, 0.5, 0.19, 0.05, PI / 4.0);
+        assert_points(&anchors, &[(0.5, 0.5)]);
+    }
+
+    #[test]
+    fn skips_anchors_on_sharp_bends() {
+        // Every anchor, including the one 0.04 before the corner
+        let anchors = get_line_anchors(&bent_line(), 0.15, 0.1, 0.05, 100f32.to_radians());
+        assert_points(
+            &anchors,
+            &[
+                (0.16, 0.5),
+                (0.31, 0.5),
+                (0.46, 0.5),
+                (0.5, 0.61),
+                (0.5, 0.76),
+            ],
+        );
+        assert_eq!(anchors[3].line.as_ref().unwrap().segment, 1);
+
+        // The label around the corner would bend by 90 degrees
+        let anchors = get_line_anchors(&bent_line(), 0.15, 0.1, 0.05, PI / 4.0);
+        assert_points(
+            &anchors,
+            &[(0.16, 0.5), (0.31, 0.5), (0.5, 0.61), (0.5, 0.76)],
+        );
+    }
+
+    #[test]
+    fn sums_up_corners_within_the_angle_window() {
+        // Two bends of 30 degrees, 0.05 apart, around the middle
+        let bend = 30f32.to_radians();
+        let corner = (0.4 + 0.05 * bend.cos(), 0.5 + 0.05 * bend.sin());
+        let line = line(&[(0.1, 0.5), (0.4, 0.5), corner, (corner.0 + 0.3, corner.1)]);
+
+        assert!(get_center_anchor(&line, 0.2, 0.01, PI / 4.0).is_some());
+        assert!(get_center_anchor(&line, 0.2, 0.1, PI / 4.0).is_none());
+    }
+
+    #[test]
+    fn centers_one_anchor() {
+        let straight = line(&[(0.2, 0.2), (0.2, 0.4), (0.2, 0.8)]);
+        let anchor = get_center_anchor(&straight, 0.2, 0.05, PI / 4.0).unwrap();
+        assert_points(&[anchor.clone()], &[(0.2, 0.5)]);
+        assert_eq!(anchor.line.unwrap().segment, 1);
+        // The label doesn't fit
+        assert!(get_center_anchor(&straight, 0.7, 0.05, PI / 4.0).is_none());
+
+        // Right on the corner of the bent line
+        assert!(get_center_anchor(&bent_line(), 0.1, 0.05, PI / 4.0).is_none());
+        assert!(get_center_anchor(&bent_line(), 0.1, 0.05, 100f32.to_radians()).is_some());
+    }
+}
@@ -0,0 +1,239 @@
+// Ref: https://github.com/mapbox/mapbox-gl-language
+//
+// Vector tiles carry the name of a feature in several languages, as `name`
+// (the local name) plus `name_en`, `name_de`, ... in Mapbox Streets or
+// `name:en`, `name:de`, ... in OpenMapTiles. Localizing a symbol layer swaps
+// every name its text-field refers to for a coalesce over the preferred
+// languages.
+use serde_json::{json, Value};
+
+fn is_name_property(key: &str) -> bool {
+    key == "name" || key.starts_with("name_") || key.starts_with("name:")
+}
+
+// ["coalesce", ["get", "name_de"], ["get", "name_en"], ["get", "name"]], with
+// `separator` between "name" and the language
+fn localized_name(languages: &[String], separator: char) -> Value {
+    let mut gets: Vec<Value> = languages
+        .iter()
+        .map(|language| json!(["get", format!("name{}{}", separator, language)]))
+        .collect();
+    gets.push(json!(["get", "name"]));
+    if gets.len() == 1 {
+        return gets.remove(0);
+    }
+    let mut coalesce = vec![json!("coalesce")];
+    coalesce.extend(gets);
+    Value::Array(coalesce)
+}
+
+fn is_name_get(value: &Value) -> bool {
+    match value.as_array().map(|expression| expression.as_slice()) {
+        Some([get, key]) => get == "get" && key.as_str().is_some_and(is_name_property),
+        _ => false,
+    }
+}
+
+// Rewrites a text-field for `languages`, most preferred first. None if it
+// doesn't show a name, e.g. "{ref}" on road shields.
+pub fn localize_text_field(text_field: &Value, languages: &[String]) -> Option<Value> {
+    let mut keys = vec![];
+    name_keys(text_field, &mut keys);
+    if keys.is_empty() {
+        return None;
+    }
+    // Names in other languages are spelled the way the style's own are
+    let separator = if keys.iter().any(|key| key.starts_with("name:")) {
+        ':'
+    } else {
+        '_'
+    };
+    Some(localize(text_field, &localized_name(languages, separator)))
+}
+
+// Collects the name properties `value` refers to
+fn name_keys<'a>(value: &'a Value, keys: &mut Vec<&'a str>) {
+    match value {
+        Value::String(template) => keys.extend(
+            split_tokens(template)
+                .into_iter()
+                .filter(|(token, is_token)| *is_token && is_name_property(token))
+                .map(|(token, _)| token),
+        ),
+        Value::Array(expression) if is_name_get(value) => keys.extend(expression[1].as_str()),
+        Value::Array(expression) => {
+            for arg in expression.iter() {
+                name_keys(arg, keys);
+            }
+        }
+        _ => (),
+    }
+}
+
+fn localize(value: &Value, name: &Value) -> Value {
+    match value {
+        Value::String(template) => localize_template(template, name),
+        Value::Array(expression) => {
+            // A coalesce over names only is the result of an earlier
+            // localization, replace it as a whole
+            let is_localized = expression.first().is_some_and(|op| op == "coalesce")
+                && expression.len() > 1
+                && expression[1..].iter().all(is_name_get);
+            if is_name_get(value) || is_localized {
+                return name.clone();
+            }
+            match expression.first().and_then(|op| op.as_str()) {
+                // Literals are never evaluated
+                Some("literal") => value.clone(),
+                _ => Value::Array(expression.iter().map(|arg| localize(arg, name)).collect()),
+            }
+        }
+        _ => value.clone(),
+    }
+}
+
+// "{name}" becomes the coalesce, "{name} ({ref})" a concat around it
+fn localize_template(template: &str, name: &Value) -> Value {
+    let parts = split_tokens(template);
+    if !parts
+        .iter()
+        .any(|(token, is_token)| *is_token && is_name_property(token))
+    {
+        return Value::String(template.to_owned());
+    }
+    if let [(_, true)] = parts.as_slice() {
+        return name.clone();
+    }
+
+    let mut concat = vec![json!("concat")];
+    for (part, is_token) in parts {
+        if is_token && is_name_property(part) {
+            concat.push(name.clone());
+        } else if is_token {
+            concat.push(json!(["to-string", ["get", part]]));
+        } else {
+            concat.push(json!(part));
+        }
+    }
+    Value::Array(concat)
+}
+
+// Splits "{name} ({ref})" into [("name", true), (" (", false), ("ref", true),
+// (")", false)]
+fn split_tokens(template: &str) -> Vec<(&str, bool)> {
+    let mut parts = vec![];
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => break,
+        };
+        if start > 0 {
+            parts.push((&rest[..start], false));
+        }
+        parts.push((&rest[start + 1..end], true));
+        rest = &rest[end + 1..];
+    }
+    if !rest.is_empty() {
+        parts.push((rest, false));
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn languages(languages: &[&str]) -> Vec<String> {
+        languages
+            .iter()
+            .map(|language| language.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn localizes_templates() {
+        let de_en = languages(&["de", "en"]);
+        let name = json!([
+            "coalesce",
+            ["get", "name_de"],
+            ["get", "name_en"],
+            ["get", "name"]
+        ]);
+        assert_eq!(
+            localize_text_field(&json!("{name}"), &de_en),
+            Some(name.clone())
+        );
+        assert_eq!(
+            localize_text_field(&json!("{name_en}"), &de_en),
+            Some(name.clone())
+        );
+        assert_eq!(
+            localize_text_field(&json!("{name} ({ref})"), &de_en),
+            Some(json!([
+                "concat",
+                name,
+                " (",
+                ["to-string", ["get", "ref"]],
+                ")"
+            ]))
+        );
+        // Nothing to localize
+        assert_eq!(localize_text_field(&json!("{ref}"), &de_en), None);
+        assert_eq!(localize_text_field(&json!("name"), &de_en), None);
+    }
+
+    #[test]
+    fn localizes_expressions() {
+        let en = languages(&["en"]);
+        let name = json!(["coalesce", ["get", "name_en"], ["get", "name"]]);
+        assert_eq!(
+            localize_text_field(&json!(["get", "name"]), &en),
+            Some(name.clone())
+        );
+        assert_eq!(
+            localize_text_field(&json!(["upcase", ["get", "name_fr"]]), &en),
+            Some(json!(["upcase", name.clone()]))
+        );
+        // Literals are left alone
+        assert_eq!(
+            localize_text_field(
+                &json!(["concat", ["literal", ["get", "name"]], ["get", "name"]]),
+                &en
+            ),
+            Some(json!(["concat", ["literal", ["get", "name"]], name]))
+        );
+        assert_eq!(localize_text_field(&json!(["get", "ref"]), &en), None);
+    }
+
+    #[test]
+    fn relocalizes_localized_text_fields() {
+        let localized = localize_text_field(&json!("{name}"), &languages(&["de"])).unwrap();
+        assert_eq!(
+            localize_text_field(&localized, &languages(&["fr"])),
+            Some(json!(["coalesce", ["get", "name_fr"], ["get", "name"]]))
+        );
+        // No languages left, only the local name
+        assert_eq!(
+            localize_text_field(&localized, &[]),
+            Some(json!(["get", "name"]))
+        );
+    }
+
+    #[test]
+    fn keeps_the_separator_of_the_source() {
+        let de = languages(&["de"]);
+        assert_eq!(
+            localize_text_field(&json!("{name:latin}"), &de),
+            Some(json!(["coalesce", ["get", "name:de"], ["get", "name"]]))
+        );
+        assert_eq!(
+            localize_text_field(&json!(["get", "name:en"]), &de),
+            Some(json!(["coalesce", ["get", "name:de"], ["get", "name"]]))
+        );
+        assert_eq!(
+            localize_text_field(&json!(["get", "name_en"]), &de),
+            Some(json!(["coalesce", ["get", "name_de"], ["get", "name"]]))
+        );
+    }
+}
@@ -0,0 +1,7 @@
+// Labels and icons: layout, collision detection and placement
+pub mod collision_index;
+pub mod line_anchors;
+pub mod localization;
+pub mod placement;
+pub mod symbol_bucket;
+pub mod symbol_layout;
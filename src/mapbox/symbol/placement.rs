@@ -0,0 +1,396 @@
+// Ref: https://github.com/mapbox/mapbox-gl-js/blob/main/src/symbol/placement.js
+//
+// Placement decides which of the laid out symbols show up, given the current
+// camera. It is cheap compared to layout, so it simply reruns from scratch
+// whenever the camera moves or tiles change.
+use super::super::text::quads::SymbolQuad;
+use super::super::text::shaping::ONE_EM;
+use super::super::transform::Transform;
+use super::super::vector_tile_id::UnwrappedTileID;
+use super::collision_index::{CollisionBox, CollisionIndex};
+use super::line_anchors;
+use super::symbol_bucket::SymbolBucket;
+use super::symbol_layout::{self, SymbolInstance, SymbolLayer};
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::f32::consts::PI;
+
+#[derive(Debug, Clone)]
+pub struct PlacedSymbol {
+    pub layer_id: String,
+    pub text: Option<String>,
+    pub icon: Option<String>,
+    pub anchor: (f32, f32), // Screen pixels
+    // text-size over the size glyph SDFs are cut for
+    pub text_scale: f32,
+    // In screen pixels
+    pub quads: Vec<SymbolQuad>,
+    // One box for the whole text, or one per glyph when it follows a line
+    pub text_boxes: Vec<CollisionBox>,
+    pub icon_box: Option<CollisionBox>,
+}
+
+pub struct Placement {
+    // Keyed by layer id
+    buckets: HashMap<String, SymbolBucket>,
+    placed_symbols: Vec<PlacedSymbol>,
+}
+
+impl Placement {
+    pub fn new() -> Placement {
+        Placement {
+            buckets: HashMap::new(),
+            placed_symbols: vec![],
+        }
+    }
+
+    pub fn get_bucket(&self, layer_id: &str) -> Option<&SymbolBucket> {
+        self.buckets.get(layer_id)
+    }
+
+    pub fn get_bucket_mut(&mut self, layer: &SymbolLayer) -> &mut SymbolBucket {
+        let source_changed = self
+            .buckets
+            .get(&layer.id)
+            .map(|bucket| bucket.source_id != layer.source)
+            .unwrap_or(false);
+        if source_changed {
+            self.buckets.remove(&layer.id);
+        }
+        self.buckets
+            .entry(layer.id.clone())
+            .or_insert_with(|| SymbolBucket::new(layer.source.clone()))
+    }
+
+    // Instances get laid out again on the next placement
+    pub fn invalidate_layer(&mut self, layer_id: &str) {
+        self.buckets.remove(layer_id);
+    }
+
+    pub fn invalidate_source(&mut self, source_id: &str) {
+        self.buckets
+            .retain(|_, bucket| bucket.source_id != source_id);
+    }
+
+    // Forgets the instances of tiles that left the view. Buckets hold on to
+    // the models they were laid out from, which would otherwise outlive
+    // their eviction from the tile cache.
+    pub fn retain_tiles(&mut self, tile_ids: &HashMap<String, Vec<UnwrappedTileID>>) {
+        for bucket in self.buckets.values_mut() {
+            let covered = tile_ids
+                .get(&bucket.source_id)
+                .map_or(&[][..], Vec::as_slice);
+            bucket.retain_tiles(|tile_id| {
+                covered
+                    .iter()
+                    .any(|covered_tile| covered_tile.canonical == *tile_id)
+            });
+        }
+    }
+
+    pub fn get_placed_symbols(&self) -> &[PlacedSymbol] {
+        &self.placed_symbols
+    }
+
+    // `layers` in style order, `tile_ids` the tiles covering the viewport by
+    // source
+    pub fn place(
+        &mut self,
+        layers: &[SymbolLayer],
+        tile_ids: &HashMap<String, Vec<UnwrappedTileID>>,
+        transform: &Transform,
+    ) {
+        let (width, height) = transform.get_size();
+        let mut collision_index = CollisionIndex::new(width as f32, height as f32);
+        let mut placed_symbols = vec![];
+
+        // Layers drawn on top get the first pick
+        for layer in layers.iter().rev() {
+            let bucket = match self.buckets.get(&layer.id) {
+                Some(bucket) => bucket,
+                None => continue,
+            };
+            // Instances are laid out once per canonical tile, the tile ID
+            // tells which copy of the world they are placed in
+            let mut instances: Vec<(UnwrappedTileID, &SymbolInstance)> = tile_ids
+                .get(&layer.source)
+                .into_iter()
+                .flatten()
+                .flat_map(|tile_id| {
+                    bucket
+                        .get_instances(&tile_id.canonical)
+                        .iter()
+                        .map(move |instance| (*tile_id, instance))
+                })
+                .collect();
+            // Lower keys are placed first, the sort is stable so ties keep
+            // their feature order
+            instances.sort_by(|(_, a), (_, b)| {
+                a.feature
+                    .sort_key
+                    .partial_cmp(&b.feature.sort_key)
+                    .unwrap_or(Ordering::Equal)
+            });
+
+            // Features crossing tile borders are in every tile they touch
+            let mut feature_tiles: HashMap<u64, UnwrappedTileID> = HashMap::new();
+            // Anchors of the labels placed so far, by label
+            let mut placed_labels: HashMap<String, Vec<(f32, f32)>> = HashMap::new();
+
+            for (tile_id, instance) in instances {
+                let feature = &instance.feature;
+                // Labels along lines have one anchor per tile they cross,
+                // anything else only needs to show once
+                if feature.id != 0 && instance.anchor.line.is_none() {
+                    let first_tile = *feature_tiles.entry(feature.id).or_insert(tile_id);
+                    if first_tile != tile_id {
+                        continue;
+                    }
+                }
+
+                let anchor = transform.tile_point_to_screen(&tile_id, instance.anchor.point);
+                let label = feature
+                    .text
+                    .clone()
+                    .or_else(|| feature.icon.clone())
+                    .unwrap_or_default();
+                let repeated = placed_labels.get(&label).is_some_and(|anchors| {
+                    anchors
+                        .iter()
+                        .any(|other| symbol_layout::distance(*other, anchor) < layer.symbol_spacing)
+                });
+                if repeated {
+                    continue;
+                }
+
+                let text_scale = layer.text_size / ONE_EM;
+                let quads = match instance.anchor.line {
+                    Some(_) => {
+                        match place_glyphs_along_line(&tile_id, instance, layer, transform, anchor)
+                        {
+                            Some(quads) => quads,
+                            None => continue, // Ran off the line
+                        }
+                    }
+                    None => instance
+                        .quads
+                        .iter()
+                        .map(|quad| {
+                            let mut quad = *quad;
+                            quad.scale(text_scale);
+                            quad.translate(anchor);
+                            quad
+                        })
+                        .collect(),
+                };
+
+                let text_boxes: Vec<CollisionBox> = match (&instance.shaping, &instance.anchor.line)
+                {
+                    (Some(_), Some(_)) => quads
+                        .iter()
+                        .map(|quad| {
+                            let (x1, y1, x2, y2) = quad.bounds();
+                            CollisionBox { x1, y1, x2, y2 }.padded(layer.text_padding)
+                        })
+                        .collect(),
+                    (Some(shaping), None) => vec![CollisionBox {
+                        x1: anchor.0 + shaping.left * text_scale,
+                        y1: anchor.1 + shaping.top * text_scale,
+                        x2: anchor.0 + shaping.right * text_scale,
+                        y2: anchor.1 + shaping.bottom * text_scale,
+                    }
+                    .padded(layer.text_padding)],
+                    (None, _) => vec![],
+                };
+                let icon_boxes: Vec<CollisionBox> = instance
+                    .icon
+                    .iter()
+                    .map(|icon| {
+                        let half_width =
+                            icon.width as f32 / icon.pixel_ratio * layer.icon_size / 2.0;
+                        let half_height =
+                            icon.height as f32 / icon.pixel_ratio * layer.icon_size / 2.0;
+                        CollisionBox {
+                            x1: anchor.0 - half_width,
+                            y1: anchor.1 - half_height,
+                            x2: anchor.0 + half_width,
+                            y2: anchor.1 + half_height,
+                        }
+                    })
+                    .collect();
+
+                let fits = |collision_boxes: &[CollisionBox], allow_overlap: bool| {
+                    collision_boxes.iter().all(|collision_box| {
+                        collision_index.is_on_screen(collision_box)
+                            && (allow_overlap || collision_index.is_free(collision_box))
+                    })
+                };
+                if !fits(&text_boxes, layer.text_allow_overlap)
+                    || !fits(&icon_boxes, layer.icon_allow_overlap)
+                {
+                    continue;
+                }
+
+                if !layer.text_ignore_placement {
+                    text_boxes
+                        .iter()
+                        .for_each(|collision_box| collision_index.insert(*collision_box));
+                }
+                if !layer.icon_ignore_placement {
+                    icon_boxes
+                        .iter()
+                        .for_each(|collision_box| collision_index.insert(*collision_box));
+                }
+                placed_labels.entry(label).or_default().push(anchor);
+
+                placed_symbols.push(PlacedSymbol {
+                    layer_id: layer.id.clone(),
+                    text: feature.text.clone(),
+                    icon: feature.icon.clone(),
+                    anchor,
+                    text_scale,
+                    quads,
+                    text_boxes,
+                    icon_box: icon_boxes.first().cloned(),
+                });
+            }
+        }
+
+        self.placed_symbols = placed_symbols;
+    }
+}
+
+impl Default for Placement {
+    fn default() -> Placement {
+        Placement::new()
+    }
+}
+
+// Bends the glyphs of the label along its projected line: every glyph sits on
+// the line at its distance from the anchor, rotated to the segment there.
+// None if the label runs over the end of the line.
+fn place_glyphs_along_line(
+    tile_id: &UnwrappedTileID,
+    instance: &SymbolInstance,
+    layer: &SymbolLayer,
+    transform: &Transform,
+    anchor: (f32, f32),
+) -> Option<Vec<SymbolQuad>> {
+    let line_anchor = instance.anchor.line.as_ref()?;
+    let line: Vec<(f32, f32)> = line_anchor
+        .line
+        .iter()
+        .map(|point| transform.tile_point_to_screen(tile_id, *point))
+        .collect();
+    let segment = line_anchor.segment;
+    let text_scale = layer.text_size / ONE_EM;
+
+    // Keep upright: labels pointing left are laid out the other way around
+    let angle = line_anchors::angle_to(line[segment], line[segment + 1]);
+    let flip = layer.text_keep_upright && !(-PI / 2.0..=PI / 2.0).contains(&angle);
+    let (direction, rotation) = if flip { (-1.0, PI) } else { (1.0, 0.0) };
+
+    let mut quads = vec![];
+    for quad in instance.quads.iter() {
+        let offset = quad.glyph_offset * text_scale * direction;
+        let (point, segment_angle) = walk_along_line(&line, segment, anchor, offset)?;
+
+        let mut quad = *quad;
+        quad.translate((-quad.glyph_offset, 0.0));
+        quad.scale(text_scale);
+        quad.rotate(segment_angle + rotation);
+        quad.translate(point);
+        quads.push(quad);
+    }
+    Some(quads)
+}
+
+// Point `offset` away from `start` (which lies on `segment`) along the line,
+// backwards for negative offsets, and the angle of the segment it's on
+fn walk_along_line(
+    line: &[(f32, f32)],
+    segment: usize,
+    start: (f32, f32),
+    offset: f32,
+) -> Option<((f32, f32), f32)> {
+    let interpolate = |from: (f32, f32), to: (f32, f32), remaining: f32| {
+        let length = symbol_layout::distance(from, to);
+        let t = if length > 0.0 {
+            remaining / length
+        } else {
+            0.0
+        };
+        (from.0 + (to.0 - from.0) * t, from.1 + (to.1 - from.1) * t)
+    };
+
+    let mut current = start;
+    let mut remaining = offset.abs();
+    if offset >= 0.0 {
+        for index in segment + 1..line.len() {
+            let length = symbol_layout::distance(current, line[index]);
+            if remaining <= length {
+                let angle = line_anchors::angle_to(line[index - 1], line[index]);
+                return Some((interpolate(current, line[index], remaining), angle));
+            }
+            remaining -= length;
+            current = line[index];
+        }
+    } else {
+        for index in (0..=segment).rev() {
+            let length = symbol_layout::distance(current, line[index]);
+            if remaining <= length {
+                let angle = line_anchors::angle_to(line[index], line[index + 1]);
+                return Some((interpolate(current, line[index], remaining), angle));
+            }
+            remaining -= length;
+            current = line[index];
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mapbox::tile_cache::{TileCache, TileCacheLimits};
+    use crate::mapbox::vector_tile_id::{UnwrappedTileID, VectorTileID};
+    use crate::mapbox::vector_tile_model::VectorTileModel;
+
+    use std::sync::Arc;
+
+    const SOURCE: &str = "streets";
+
+    #[test]
+    fn evicted_tiles_are_freed() {
+        let mut cache = TileCache::new(TileCacheLimits {
+            max_tiles: 1,
+            max_bytes: usize::MAX,
+        });
+        cache.add_source(SOURCE.to_owned());
+        let mut placement = Placement::new();
+        let mut bucket = SymbolBucket::new(SOURCE.to_owned());
+
+        let (old, new) = (VectorTileID::new(0, 0, 4), VectorTileID::new(1, 0, 4));
+        cache.set_in_use(SOURCE, [old].iter().copied().collect());
+        cache.insert(SOURCE, old, Arc::new(VectorTileModel { layers: vec![] }));
+        bucket.insert(old, cache.get(SOURCE, &old).unwrap(), vec![]);
+        placement.buckets.insert("labels".to_owned(), bucket);
+        let old_model = Arc::downgrade(&cache.get(SOURCE, &old).unwrap());
+
+        // The view moves on to the next tile and the cache makes room for it
+        let mut tile_ids = HashMap::new();
+        tile_ids.insert(SOURCE.to_owned(), vec![UnwrappedTileID::new(1, 0, 4, 1)]);
+        placement.retain_tiles(&tile_ids);
+        cache.set_in_use(SOURCE, [new].iter().copied().collect());
+        let evicted = cache.insert(SOURCE, new, Arc::new(VectorTileModel { layers: vec![] }));
+
+        assert_eq!(evicted, vec![(SOURCE.to_owned(), old)]);
+        assert_eq!(old_model.strong_count(), 0);
+        assert!(placement
+            .get_bucket("labels")
+            .unwrap()
+            .get_instances(&old)
+            .is_empty());
+    }
+}
@@ -0,0 +1,55 @@
+use super::super::vector_tile_id::VectorTileID;
+use super::super::vector_tile_model::VectorTileModel;
+use super::symbol_layout::SymbolInstance;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+struct TileSymbols {
+    // The tile the instances were laid out from, a reloaded tile is a new Arc
+    tile: Arc<VectorTileModel>,
+    instances: Vec<SymbolInstance>,
+}
+
+// Laid out symbol instances of one symbol layer, per tile
+pub struct SymbolBucket {
+    pub source_id: String,
+    tiles: HashMap<VectorTileID, TileSymbols>,
+}
+
+impl SymbolBucket {
+    pub fn new(source_id: String) -> SymbolBucket {
+        SymbolBucket {
+            source_id,
+            tiles: HashMap::new(),
+        }
+    }
+
+    pub fn contains(&self, tile_id: &VectorTileID, tile: &Arc<VectorTileModel>) -> bool {
+        match self.tiles.get(tile_id) {
+            Some(tile_symbols) => Arc::ptr_eq(&tile_symbols.tile, tile),
+            None => false,
+        }
+    }
+
+    pub fn insert(
+        &mut self,
+        tile_id: VectorTileID,
+        tile: Arc<VectorTileModel>,
+        instances: Vec<SymbolInstance>,
+    ) {
+        self.tiles.insert(tile_id, TileSymbols { tile, instances });
+    }
+
+    // Drops the tiles `keep` says no to, along with the models they hold on to
+    pub fn retain_tiles(&mut self, keep: impl Fn(&VectorTileID) -> bool) {
+        self.tiles.retain(|tile_id, _| keep(tile_id));
+    }
+
+    pub fn get_instances(&self, tile_id: &VectorTileID) -> &[SymbolInstance] {
+        match self.tiles.get(tile_id) {
+            Some(tile_symbols) => &tile_symbols.instances,
+            None => &[],
+        }
+    }
+}
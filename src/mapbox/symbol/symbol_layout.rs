@@ -0,0 +1,580 @@
+// Ref: https://github.com/mapbox/mapbox-gl-js/blob/main/src/symbol/symbol_layout.js
+//
+// Turns the features of a tile into symbol instances: the label text (or
+// icon) of every feature, where it is anchored in the tile and its shaped
+// glyphs. Instances only depend on the tile and the layer's layout, so they
+// are kept until either changes. Where they end up on screen, and whether
+// they show at all, is decided by the placement.
+use super::super::config::TILE_SIZE;
+use super::super::sprite_model::SpriteImage;
+use super::super::text::arabic;
+use super::super::text::quads::{self, SymbolQuad};
+use super::super::text::shaping::{Shaping, TextLayout, ONE_EM};
+use super::super::vector_tile_id::VectorTileID;
+use super::super::vector_tile_model::VectorTileModel;
+use super::line_anchors::{self, Anchor};
+
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+const DEFAULT_TEXT_FONT: &[&str] = &["Open Sans Regular", "Arial Unicode MS Regular"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SymbolPlacement {
+    Point,
+    Line,
+    LineCenter,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextTransform {
+    None,
+    Uppercase,
+    Lowercase,
+}
+
+// Layout of a "symbol" style layer. Only constant property values are
+// supported, except for text-field, icon-image and symbol-sort-key which are
+// evaluated per feature.
+#[derive(Debug, Clone)]
+pub struct SymbolLayer {
+    pub id: String,
+    pub source: String,
+    pub source_layer: String,
+    pub min_zoom: f32,
+    pub max_zoom: f32,
+    pub placement: SymbolPlacement,
+    pub text_field: Option<Value>,
+    pub text_font: Vec<String>,
+    pub text_size: f32,
+    pub text_transform: TextTransform,
+    pub text_layout: TextLayout,
+    pub text_padding: f32,
+    pub text_allow_overlap: bool,
+    pub text_ignore_placement: bool,
+    pub text_max_angle: f32, // Degrees
+    pub text_keep_upright: bool,
+    pub icon_image: Option<Value>,
+    pub icon_size: f32,
+    pub icon_allow_overlap: bool,
+    pub icon_ignore_placement: bool,
+    pub symbol_sort_key: Option<Value>,
+    pub symbol_spacing: f32,
+}
+
+impl SymbolLayer {
+    // None for layers which aren't visible symbol layers
+    pub fn from_layer(layer: &Value) -> Option<SymbolLayer> {
+        if layer["type"].as_str() != Some("symbol") {
+            return None;
+        }
+        let layout = &layer["layout"];
+        if layout["visibility"].as_str() == Some("none") {
+            return None;
+        }
+
+        let number =
+            |name: &str, default: f32| layout[name].as_f64().map(|v| v as f32).unwrap_or(default);
+        let boolean = |name: &str| layout[name].as_bool().unwrap_or(false);
+        let text_font = match layout["text-font"].as_array() {
+            Some(fonts) => fonts
+                .iter()
+                .filter_map(|font| font.as_str().map(|font| font.to_owned()))
+                .collect(),
+            None => DEFAULT_TEXT_FONT
+                .iter()
+                .map(|font| font.to_string())
+                .collect(),
+        };
+        let text_transform = match layout["text-transform"].as_str() {
+            Some("uppercase") => TextTransform::Uppercase,
+            Some("lowercase") => TextTransform::Lowercase,
+            _ => TextTransform::None,
+        };
+        let placement = match layout["symbol-placement"].as_str() {
+            Some("line") => SymbolPlacement::Line,
+            Some("line-center") => SymbolPlacement::LineCenter,
+            _ => SymbolPlacement::Point,
+        };
+        let mut text_layout = TextLayout::from_layout(layout);
+        if placement != SymbolPlacement::Point {
+            // Labels along lines stay on one line
+            text_layout.max_width = 0.0;
+        }
+
+        Some(SymbolLayer {
+            id: layer["id"].as_str()?.to_owned(),
+            source: layer["source"].as_str()?.to_owned(),
+            source_layer: layer["source-layer"].as_str()?.to_owned(),
+            min_zoom: layer["minzoom"].as_f64().unwrap_or(0.0) as f32,
+            max_zoom: layer["maxzoom"].as_f64().unwrap_or(24.0) as f32,
+            placement,
+            text_field: layout.get("text-field").cloned(),
+            text_font,
+            text_size: number("text-size", 16.0),
+            text_transform,
+            text_layout,
+            text_padding: number("text-padding", 2.0),
+            text_allow_overlap: boolean("text-allow-overlap"),
+            text_ignore_placement: boolean("text-ignore-placement"),
+            text_max_angle: number("text-max-angle", 45.0),
+            text_keep_upright: layout["text-keep-upright"].as_bool().unwrap_or(true),
+            icon_image: layout.get("icon-image").cloned(),
+            icon_size: number("icon-size", 1.0),
+            icon_allow_overlap: boolean("icon-allow-overlap"),
+            icon_ignore_placement: boolean("icon-ignore-placement"),
+            symbol_sort_key: layout.get("symbol-sort-key").cloned(),
+            symbol_spacing: number("symbol-spacing", 250.0),
+        })
+    }
+
+    pub fn is_visible_at(&self, zoom: f32) -> bool {
+        zoom >= self.min_zoom && zoom < self.max_zoom
+    }
+
+    // Labels and icons of the tile's features, before shaping.
+    // TODO: layer filters aren't evaluated yet
+    pub fn get_features(&self, tile: &VectorTileModel) -> Vec<SymbolFeature> {
+        let mut symbol_features = vec![];
+
+        for layer in tile.layers.iter() {
+            if layer.name != self.source_layer {
+                continue;
+            }
+            for (index, feature) in layer.features.iter().enumerate() {
+                let properties = layer.get_properties(feature);
+                let text = self
+                    .text_field
+                    .as_ref()
+                    .and_then(|field| evaluate_string(field, &properties))
+                    .map(|text| arabic::apply_arabic_shaping(&self.transform_text(&text)))
+                    .filter(|text| !text.trim().is_empty());
+                let icon = self
+                    .icon_image
+                    .as_ref()
+                    .and_then(|image| evaluate_string(image, &properties))
+                    .filter(|icon| !icon.is_empty());
+                if text.is_none() && icon.is_none() {
+                    continue;
+                }
+
+                let sort_key = self
+                    .symbol_sort_key
+                    .as_ref()
+                    .and_then(|key| evaluate_number(key, &properties))
+                    .unwrap_or(0.0);
+
+                symbol_features.push(SymbolFeature {
+                    id: feature.id,
+                    index,
+                    text,
+                    icon,
+                    sort_key,
+                    geometry_type: feature.r#type,
+                    geometry: feature
+                        .geometry
+                        .iter()
+                        .flat_map(|geometry| geometry.geom_set.iter())
+                        .map(|points| Arc::new(points.clone()))
+                        .collect(),
+                });
+            }
+        }
+
+        symbol_features
+    }
+
+    // Where the feature's label goes, which depends on its size for line
+    // placement. Lengths in tile pixels are TILE_SIZE per tile.
+    pub fn get_anchors(
+        &self,
+        feature: &SymbolFeature,
+        shaping: Option<&Shaping>,
+        icon: Option<&SpriteImage>,
+    ) -> Vec<Anchor> {
+        if self.placement == SymbolPlacement::Point {
+            return get_point_anchors(feature)
+                .into_iter()
+                .map(|point| Anchor { point, line: None })
+                .collect();
+        }
+        // Points have no line to follow
+        if feature.geometry_type != 2 && feature.geometry_type != 3 {
+            return vec![];
+        }
+
+        let text_length = shaping
+            .map(|shaping| (shaping.right - shaping.left) * self.text_size / ONE_EM)
+            .unwrap_or(0.0);
+        let icon_length = icon
+            .map(|icon| icon.width as f32 / icon.pixel_ratio * self.icon_size)
+            .unwrap_or(0.0);
+        let tile_size = TILE_SIZE as f32;
+        let label_length = text_length.max(icon_length) / tile_size;
+        let angle_window = if shaping.is_some() {
+            self.text_size * 3.0 / 5.0 / tile_size
+        } else {
+            0.0
+        };
+        let max_angle = self.text_max_angle.to_radians();
+
+        let mut anchors = vec![];
+        for line in feature.geometry.iter() {
+            match self.placement {
+                SymbolPlacement::Line => anchors.extend(line_anchors::get_line_anchors(
+                    line,
+                    self.symbol_spacing / tile_size,
+                    label_length,
+                    angle_window,
+                    max_angle,
+                )),
+                _ => anchors.extend(line_anchors::get_center_anchor(
+                    line,
+                    label_length,
+                    angle_window,
+                    max_angle,
+                )),
+            }
+        }
+        anchors
+    }
+
+    fn transform_text(&self, text: &str) -> String {
+        match self.text_transform {
+            TextTransform::None => text.to_owned(),
+            TextTransform::Uppercase => text.to_uppercase(),
+            TextTransform::Lowercase => text.to_lowercase(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SymbolFeature {
+    pub id: u64,
+    pub index: usize, // Feature order in the tile layer
+    pub text: Option<String>,
+    pub icon: Option<String>,
+    pub sort_key: f32,
+    pub geometry_type: u32,
+    // Points, lines or rings of the feature, in normalized tile coords
+    pub geometry: Vec<Arc<Vec<(f32, f32)>>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SymbolInstance {
+    pub tile_id: VectorTileID,
+    pub feature: Arc<SymbolFeature>,
+    pub anchor: Anchor,
+    pub shaping: Option<Shaping>,
+    pub quads: Vec<SymbolQuad>,
+    pub icon: Option<SpriteImage>,
+}
+
+impl SymbolInstance {
+    // None if the feature ends up with neither text nor icon to draw
+    pub fn new(
+        tile_id: VectorTileID,
+        feature: Arc<SymbolFeature>,
+        anchor: Anchor,
+        shaping: Option<Shaping>,
+        icon: Option<SpriteImage>,
+    ) -> Option<SymbolInstance> {
+        if shaping.is_none() && icon.is_none() {
+            return None;
+        }
+        let quads = shaping
+            .as_ref()
+            .map(quads::get_glyph_quads)
+            .unwrap_or_default();
+        Some(SymbolInstance {
+            tile_id,
+            feature,
+            anchor,
+            shaping,
+            quads,
+            icon,
+        })
+    }
+}
+
+// With point placement every point gets a label, lines and polygons get one
+// per part. Anchors outside of the tile belong to the neighbouring tile.
+fn get_point_anchors(feature: &SymbolFeature) -> Vec<(f32, f32)> {
+    let mut anchors = vec![];
+    match feature.geometry_type {
+        1 => anchors.extend(
+            feature
+                .geometry
+                .iter()
+                .flat_map(|points| points.iter().cloned()),
+        ),
+        2 => anchors.extend(
+            feature
+                .geometry
+                .iter()
+                .filter_map(|line| line_midpoint(line)),
+        ),
+        // Holes wind counter-clockwise and don't get a label
+        3 => anchors.extend(
+            feature
+                .geometry
+                .iter()
+                .filter(|ring| signed_area(ring) >= 0.0)
+                .filter_map(|ring| ring_centroid(ring)),
+        ),
+        _ => {}
+    }
+    anchors
+        .into_iter()
+        .filter(|(x, y)| *x >= 0.0 && *x < 1.0 && *y >= 0.0 && *y < 1.0)
+        .collect()
+}
+
+// Point halfway along the line
+fn line_midpoint(line: &[(f32, f32)]) -> Option<(f32, f32)> {
+    let length: f32 = line.windows(2).map(|w| distance(w[0], w[1])).sum();
+    let mut remaining = length / 2.0;
+    for w in line.windows(2) {
+        let segment = distance(w[0], w[1]);
+        if segment > 0.0 && remaining <= segment {
+            let t = remaining / segment;
+            return Some((
+                w[0].0 + (w[1].0 - w[0].0) * t,
+                w[0].1 + (w[1].1 - w[0].1) * t,
+            ));
+        }
+        remaining -= segment;
+    }
+    line.first().cloned()
+}
+
+// Positive for clockwise rings (in screen coords, y pointing down)
+fn signed_area(ring: &[(f32, f32)]) -> f32 {
+    ring.windows(2)
+        .map(|w| w[0].0 * w[1].1 - w[1].0 * w[0].1)
+        .sum::<f32>()
+        / 2.0
+}
+
+// Area weighted centroid, the vertex average for degenerate rings
+fn ring_centroid(ring: &[(f32, f32)]) -> Option<(f32, f32)> {
+    if ring.is_empty() {
+        return None;
+    }
+    let (mut area, mut cx, mut cy) = (0.0, 0.0, 0.0);
+    for w in ring.windows(2) {
+        let cross = w[0].0 * w[1].1 - w[1].0 * w[0].1;
+        area += cross;
+        cx += (w[0].0 + w[1].0) * cross;
+        cy += (w[0].1 + w[1].1) * cross;
+    }
+    if area.abs() < f32::EPSILON {
+        let n = ring.len() as f32;
+        let (sx, sy) = ring
+            .iter()
+            .fold((0.0, 0.0), |(sx, sy), (x, y)| (sx + x, sy + y));
+        return Some((sx / n, sy / n));
+    }
+    Some((cx / (3.0 * area), cy / (3.0 * area)))
+}
+
+pub fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+    ((b.0 - a.0).powi(2) + (b.1 - a.1).powi(2)).sqrt()
+}
+
+// Feature dependent values
+
+// Supports "{token}" strings and the expressions labels commonly use
+pub fn evaluate_string(value: &Value, properties: &HashMap<String, Value>) -> Option<String> {
+    match value {
+        Value::String(template) => Some(resolve_tokens(template, properties)),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Array(expression) => match expression.first()?.as_str()? {
+            "get" => property_to_string(properties.get(expression.get(1)?.as_str()?)?),
+            "literal" => expression.get(1)?.as_str().map(|s| s.to_owned()),
+            "to-string" => evaluate_string(expression.get(1)?, properties),
+            "coalesce" => expression[1..]
+                .iter()
+                .filter_map(|arg| evaluate_string(arg, properties))
+                .find(|s| !s.is_empty()),
+            "concat" => Some(
+                expression[1..]
+                    .iter()
+                    .filter_map(|arg| evaluate_string(arg, properties))
+                    .collect(),
+            ),
+            // ["format", text, options, text, options, ...]
+            "format" => Some(
+                expression[1..]
+                    .iter()
+                    .filter(|arg| !arg.is_object())
+                    .filter_map(|arg| evaluate_string(arg, properties))
+                    .collect(),
+            ),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+pub fn evaluate_number(value: &Value, properties: &HashMap<String, Value>) -> Option<f32> {
+    match value {
+        Value::Number(number) => number.as_f64().map(|v| v as f32),
+        Value::Array(expression) => match expression.first()?.as_str()? {
+            "get" => properties
+                .get(expression.get(1)?.as_str()?)?
+                .as_f64()
+                .map(|v| v as f32),
+            "to-number" => evaluate_number(expression.get(1)?, properties),
+            "coalesce" => expression[1..]
+                .iter()
+                .find_map(|arg| evaluate_number(arg, properties)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn property_to_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+// "{name} ({ref})" - unknown tokens become empty
+fn resolve_tokens(template: &str, properties: &HashMap<String, Value>) -> String {
+    let mut resolved = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => break,
+        };
+        resolved.push_str(&rest[..start]);
+        if let Some(value) = properties.get(&rest[start + 1..end]) {
+            resolved.push_str(&property_to_string(value).unwrap_or_default());
+        }
+        rest = &rest[end + 1..];
+    }
+    resolved.push_str(rest);
+    resolved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const EPSILON: f32 = 1e-4;
+
+    // Labels 0.1 tiles long, spaced 0.15 tiles apart
+    fn layer(placement: &str) -> SymbolLayer {
+        SymbolLayer::from_layer(&json!({
+            "id": "road-label",
+            "type": "symbol",
+            "source": "streets",
+            "source-layer": "road",
+            "layout": {
+                "symbol-placement": placement,
+                "symbol-spacing": 0.15 * TILE_SIZE as f32,
+                "text-size": ONE_EM,
+                "text-field": "{name}"
+            }
+        }))
+        .unwrap()
+    }
+
+    fn shaping() -> Shaping {
+        let half_length = 0.05 * TILE_SIZE as f32;
+        Shaping {
+            positioned_glyphs: vec![],
+            line_count: 1,
+            top: -ONE_EM / 2.0,
+            bottom: ONE_EM / 2.0,
+            left: -half_length,
+            right: half_length,
+        }
+    }
+
+    fn feature(geometry_type: u32, line: &[(f32, f32)]) -> SymbolFeature {
+        SymbolFeature {
+            id: 1,
+            index: 0,
+            text: Some("Main St".to_owned()),
+            icon: None,
+            sort_key: 0.0,
+            geometry_type,
+            geometry: vec![Arc::new(line.to_vec())],
+        }
+    }
+
+    fn anchor_points(layer: &SymbolLayer, feature: &SymbolFeature) -> Vec<(f32, f32)> {
+        layer
+            .get_anchors(feature, Some(&shaping()), None)
+            .iter()
+            .map(|anchor| anchor.point)
+            .collect()
+    }
+
+    fn assert_points(points: &[(f32, f32)], expected: &[(f32, f32)]) {
+        assert_eq!(points.len(), expected.len(), "{:?}", points);
+        for (point, expected) in points.iter().zip(expected.iter()) {
+            assert!(
+                (point.0 - expected.0).abs() < EPSILON && (point.1 - expected.1).abs() < EPSILON,
+                "{:?} != {:?}",
+                points,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn line_placement_follows_symbol_spacing() {
+        let straight = feature(2, &[(0.1, 0.5), (0.9, 0.5)]);
+        assert_points(
+            &anchor_points(&layer("line"), &straight),
+            &[
+                (0.16, 0.5),
+                (0.31, 0.5),
+                (0.46, 0.5),
+                (0.61, 0.5),
+                (0.76, 0.5),
+            ],
+        );
+        // A point has no line to follow
+        assert!(anchor_points(&layer("line"), &feature(1, &[(0.5, 0.5)])).is_empty());
+    }
+
+    #[test]
+    fn line_placement_avoids_sharp_bends() {
+        let bent = feature(2, &[(0.1, 0.5), (0.5, 0.5), (0.5, 0.9)]);
+        // The anchor at (0.46, 0.5) would put the label around the corner
+        assert_points(
+            &anchor_points(&layer("line"), &bent),
+            &[(0.16, 0.5), (0.31, 0.5), (0.5, 0.61), (0.5, 0.76)],
+        );
+
+        let mut layer = layer("line");
+        layer.text_max_angle = 100.0;
+        assert_eq!(anchor_points(&layer, &bent).len(), 5);
+    }
+
+    #[test]
+    fn line_center_placement_picks_the_middle() {
+        let straight = feature(2, &[(0.1, 0.5), (0.9, 0.5)]);
+        assert_points(
+            &anchor_points(&layer("line-center"), &straight),
+            &[(0.5, 0.5)],
+        );
+        // The middle of the bent line is its corner
+        let bent = feature(2, &[(0.1, 0.5), (0.5, 0.5), (0.5, 0.9)]);
+        assert!(anchor_points(&layer("line-center"), &bent).is_empty());
+        // Too short for the label
+        let short = feature(2, &[(0.5, 0.5), (0.55, 0.5)]);
+        assert!(anchor_points(&layer("line-center"), &short).is_empty());
+    }
+}
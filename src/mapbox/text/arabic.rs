@@ -0,0 +1,235 @@
+// Ref: https://www.unicode.org/versions/latest/ch09.pdf (9.2 Arabic, cursive joining)
+//
+// Arabic letters take a different form depending on whether they join the
+// letters around them. The glyph PBFs only carry one glyph per codepoint, so
+// letters are swapped for their presentation forms (U+FB50..U+FEFF) before
+// shaping, the same as ICU's u_shapeArabic does for mapbox-gl-native.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Joining {
+    // Joins on both sides, forms are isolated, final, initial, medial
+    Dual,
+    // Only joins the letter before it, forms are isolated, final
+    Right,
+}
+
+// (letter, isolated presentation form, joining), the other forms follow the
+// isolated one
+const LETTERS: &[(char, u32, Joining)] = &[
+    ('\u{0622}', 0xfe81, Joining::Right), // Alef with madda above
+    ('\u{0623}', 0xfe83, Joining::Right), // Alef with hamza above
+    ('\u{0624}', 0xfe85, Joining::Right), // Waw with hamza above
+    ('\u{0625}', 0xfe87, Joining::Right), // Alef with hamza below
+    ('\u{0626}', 0xfe89, Joining::Dual),  // Yeh with hamza above
+    ('\u{0627}', 0xfe8d, Joining::Right), // Alef
+    ('\u{0628}', 0xfe8f, Joining::Dual),  // Beh
+    ('\u{0629}', 0xfe93, Joining::Right), // Teh marbuta
+    ('\u{062a}', 0xfe95, Joining::Dual),  // Teh
+    ('\u{062b}', 0xfe99, Joining::Dual),  // Theh
+    ('\u{062c}', 0xfe9d, Joining::Dual),  // Jeem
+    ('\u{062d}', 0xfea1, Joining::Dual),  // Hah
+    ('\u{062e}', 0xfea5, Joining::Dual),  // Khah
+    ('\u{062f}', 0xfea9, Joining::Right), // Dal
+    ('\u{0630}', 0xfeab, Joining::Right), // Thal
+    ('\u{0631}', 0xfead, Joining::Right), // Reh
+    ('\u{0632}', 0xfeaf, Joining::Right), // Zain
+    ('\u{0633}', 0xfeb1, Joining::Dual),  // Seen
+    ('\u{0634}', 0xfeb5, Joining::Dual),  // Sheen
+    ('\u{0635}', 0xfeb9, Joining::Dual),  // Sad
+    ('\u{0636}', 0xfebd, Joining::Dual),  // Dad
+    ('\u{0637}', 0xfec1, Joining::Dual),  // Tah
+    ('\u{0638}', 0xfec5, Joining::Dual),  // Zah
+    ('\u{0639}', 0xfec9, Joining::Dual),  // Ain
+    ('\u{063a}', 0xfecd, Joining::Dual),  // Ghain
+    ('\u{0641}', 0xfed1, Joining::Dual),  // Feh
+    ('\u{0642}', 0xfed5, Joining::Dual),  // Qaf
+    ('\u{0643}', 0xfed9, Joining::Dual),  // Kaf
+    ('\u{0644}', 0xfedd, Joining::Dual),  // Lam
+    ('\u{0645}', 0xfee1, Joining::Dual),  // Meem
+    ('\u{0646}', 0xfee5, Joining::Dual),  // Noon
+    ('\u{0647}', 0xfee9, Joining::Dual),  // Heh
+    ('\u{0648}', 0xfeed, Joining::Right), // Waw
+    ('\u{0649}', 0xfeef, Joining::Right), // Alef maksura
+    ('\u{064a}', 0xfef1, Joining::Dual),  // Yeh
+    // Persian and Urdu
+    ('\u{067e}', 0xfb56, Joining::Dual),  // Peh
+    ('\u{0686}', 0xfb7a, Joining::Dual),  // Tcheh
+    ('\u{0698}', 0xfb8a, Joining::Right), // Jeh
+    ('\u{06a9}', 0xfb8e, Joining::Dual),  // Keheh
+    ('\u{06af}', 0xfb92, Joining::Dual),  // Gaf
+    ('\u{06cc}', 0xfbfc, Joining::Dual),  // Farsi yeh
+];
+
+const LAM: char = '\u{0644}';
+const TATWEEL: char = '\u{0640}';
+
+// Lam followed by one of these alefs becomes a single ligature, (alef,
+// isolated ligature), the final form follows
+const LAM_ALEF: &[(char, u32)] = &[
+    ('\u{0622}', 0xfef5),
+    ('\u{0623}', 0xfef7),
+    ('\u{0625}', 0xfef9),
+    ('\u{0627}', 0xfefb),
+];
+
+fn letter(c: char) -> Option<(u32, Joining)> {
+    LETTERS
+        .iter()
+        .find(|(letter, _, _)| *letter == c)
+        .map(|(_, isolated, joining)| (*isolated, *joining))
+}
+
+// Marks (harakat) sit on top of letters and don't break the joining
+fn is_transparent(c: char) -> bool {
+    matches!(c as u32, 0x0610..=0x061a | 0x064b..=0x065f | 0x0670 | 0x06d6..=0x06ed)
+}
+
+// Whether `c` connects to the letter after it
+fn joins_next(c: char) -> bool {
+    c == TATWEEL || matches!(letter(c), Some((_, Joining::Dual)))
+}
+
+// Whether `c` connects to the letter before it
+fn joins_previous(c: char) -> bool {
+    c == TATWEEL || letter(c).is_some()
+}
+
+fn is_arabic(c: char) -> bool {
+    matches!(c as u32, 0x0600..=0x06ff)
+}
+
+// Replaces Arabic letters by their contextual presentation forms, in logical
+// order. Text without Arabic is returned as is.
+pub fn apply_arabic_shaping(text: &str) -> String {
+    if !text.chars().any(is_arabic) {
+        return text.to_owned();
+    }
+
+    let chars: Vec<char> = text.chars().collect();
+    let previous = |inx: usize| chars[..inx].iter().rev().find(|c| !is_transparent(**c));
+    // Index of the next letter, past any marks
+    let next = |inx: usize| (inx + 1..chars.len()).find(|&n| !is_transparent(chars[n]));
+
+    let mut shaped = String::with_capacity(text.len());
+    let mut inx = 0;
+    while inx < chars.len() {
+        let c = chars[inx];
+        let joined_before = previous(inx).is_some_and(|&p| joins_next(p));
+
+        if c == LAM {
+            let ligature = next(inx).and_then(|alef_inx| {
+                LAM_ALEF
+                    .iter()
+                    .find(|(alef, _)| *alef == chars[alef_inx])
+                    .map(|(_, isolated)| (alef_inx, isolated))
+            });
+            if let Some((alef_inx, isolated)) = ligature {
+                let form = if joined_before { 1 } else { 0 };
+                shaped.extend(std::char::from_u32(isolated + form));
+                // The marks on the lam stay, after the ligature
+                shaped.extend(chars[inx + 1..alef_inx].iter());
+                inx = alef_inx + 1;
+                continue;
+            }
+        }
+
+        match letter(c) {
+            Some((isolated, joining)) => {
+                let joined_after =
+                    joining == Joining::Dual && next(inx).is_some_and(|n| joins_previous(chars[n]));
+                let form = match (joined_before, joined_after) {
+                    (false, false) => 0,
+                    (true, false) => 1,
+                    (false, true) => 2,
+                    (true, true) => 3,
+                };
+                shaped.extend(std::char::from_u32(isolated + form));
+            }
+            None => shaped.push(c),
+        }
+        inx += 1;
+    }
+    shaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BEH: char = '\u{0628}';
+    const DAL: char = '\u{062f}';
+    const ALEF: char = '\u{0627}';
+    const FATHA: char = '\u{064e}';
+
+    fn shape(chars: &[char]) -> Vec<u32> {
+        let text: String = chars.iter().collect();
+        apply_arabic_shaping(&text)
+            .chars()
+            .map(|c| c as u32)
+            .collect()
+    }
+
+    #[test]
+    fn leaves_other_scripts_alone() {
+        assert_eq!(apply_arabic_shaping("Main St."), "Main St.");
+        assert_eq!(apply_arabic_shaping("שלום"), "שלום");
+    }
+
+    #[test]
+    fn picks_contextual_forms() {
+        // Isolated, then initial, medial and final
+        assert_eq!(shape(&[BEH]), vec![0xfe8f]);
+        assert_eq!(shape(&[BEH, BEH]), vec![0xfe91, 0xfe90]);
+        assert_eq!(shape(&[BEH, BEH, BEH]), vec![0xfe91, 0xfe92, 0xfe90]);
+        // Words are shaped on their own
+        assert_eq!(
+            shape(&[BEH, BEH, ' ', BEH]),
+            vec![0xfe91, 0xfe90, ' ' as u32, 0xfe8f]
+        );
+        // Tatweel stretches the join and keeps both sides connected
+        assert_eq!(
+            shape(&[BEH, TATWEEL, BEH]),
+            vec![0xfe91, TATWEEL as u32, 0xfe90]
+        );
+    }
+
+    #[test]
+    fn right_joining_letters_break_the_word() {
+        // Dal joins the beh before it, but not the one after it
+        assert_eq!(shape(&[BEH, DAL]), vec![0xfe91, 0xfeaa]);
+        assert_eq!(shape(&[DAL, BEH]), vec![0xfea9, 0xfe8f]);
+        assert_eq!(
+            shape(&[BEH, DAL, BEH, BEH]),
+            vec![0xfe91, 0xfeaa, 0xfe91, 0xfe90]
+        );
+    }
+
+    #[test]
+    fn marks_are_transparent() {
+        assert_eq!(
+            shape(&[BEH, FATHA, BEH]),
+            vec![0xfe91, FATHA as u32, 0xfe90]
+        );
+        assert_eq!(
+            shape(&[BEH, FATHA, DAL, FATHA]),
+            vec![0xfe91, FATHA as u32, 0xfeaa, FATHA as u32]
+        );
+    }
+
+    #[test]
+    fn ligates_lam_alef() {
+        assert_eq!(shape(&[LAM, ALEF]), vec![0xfefb]);
+        assert_eq!(shape(&[LAM, '\u{0623}']), vec![0xfef7]);
+        // Final after a joining letter, and nothing joins after it
+        assert_eq!(shape(&[BEH, LAM, ALEF]), vec![0xfe91, 0xfefc]);
+        assert_eq!(shape(&[LAM, ALEF, BEH]), vec![0xfefb, 0xfe8f]);
+        // Marks in between don't keep the letters apart
+        assert_eq!(shape(&[LAM, FATHA, ALEF]), vec![0xfefb, FATHA as u32]);
+        assert_eq!(
+            shape(&[BEH, LAM, FATHA, ALEF, BEH]),
+            vec![0xfe91, 0xfefc, FATHA as u32, 0xfe8f]
+        );
+        // Lam before anything else is shaped as a letter
+        assert_eq!(shape(&[LAM, BEH]), vec![0xfedf, 0xfe90]);
+    }
+}
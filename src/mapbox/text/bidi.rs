@@ -0,0 +1,121 @@
+// Ref: https://github.com/mapbox/mapbox-gl-native/blob/master/platform/default/src/mbgl/text/bidi.cpp
+//
+// Glyphs are laid out left to right, so right-to-left runs (Arabic, Hebrew)
+// have to be put in visual order first. Lines are broken on the logical
+// order, every line is then reordered on its own with the levels resolved
+// for the whole paragraph.
+use unicode_bidi::ParagraphBidiInfo;
+
+// `line_ends` are char indices into `paragraph`, as returned by the line
+// breaking. Returns the chars of every line in visual order.
+pub fn process_text(paragraph: &str, line_ends: &[usize]) -> Vec<Vec<char>> {
+    let chars: Vec<char> = paragraph.chars().collect();
+    let bidi_info = ParagraphBidiInfo::new(paragraph, None);
+    if !bidi_info.has_rtl() {
+        let mut start = 0;
+        return line_ends
+            .iter()
+            .map(|&end| {
+                let line = chars[start..end].to_vec();
+                start = end;
+                line
+            })
+            .collect();
+    }
+
+    // unicode_bidi works on byte offsets
+    let byte_offsets: Vec<usize> = paragraph
+        .char_indices()
+        .map(|(offset, _)| offset)
+        .chain(std::iter::once(paragraph.len()))
+        .collect();
+
+    let mut lines = vec![];
+    let mut start = 0;
+    for &end in line_ends {
+        let line = byte_offsets[start]..byte_offsets[end];
+        let (levels, runs) = bidi_info.visual_runs(line);
+        let mut visual = vec![];
+        for run in runs {
+            let run_chars = paragraph[run.clone()].chars();
+            if levels[run.start].is_rtl() {
+                visual.extend(run_chars.rev().map(mirror));
+            } else {
+                visual.extend(run_chars);
+            }
+        }
+        lines.push(visual);
+        start = end;
+    }
+    lines
+}
+
+// Brackets in right-to-left runs face the other way (rule L4). Only the
+// common pairs, see BidiMirroring.txt for the full list.
+fn mirror(c: char) -> char {
+    match c {
+        '(' => ')',
+        ')' => '(',
+        '[' => ']',
+        ']' => '[',
+        '{' => '}',
+        '}' => '{',
+        '<' => '>',
+        '>' => '<',
+        '«' => '»',
+        '»' => '«',
+        '\u{2039}' => '\u{203a}',
+        '\u{203a}' => '\u{2039}',
+        _ => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(paragraph: &str, line_ends: &[usize]) -> Vec<String> {
+        process_text(paragraph, line_ends)
+            .iter()
+            .map(|line| line.iter().collect())
+            .collect()
+    }
+
+    #[test]
+    fn keeps_left_to_right_text() {
+        assert_eq!(lines("abc def", &[4, 7]), vec!["abc ", "def"]);
+        assert_eq!(lines("(1)", &[3]), vec!["(1)"]);
+    }
+
+    #[test]
+    fn reverses_right_to_left_text() {
+        assert_eq!(lines("אבג", &[3]), vec!["גבא"]);
+        assert_eq!(lines("אבג דהו", &[7]), vec!["והד גבא"]);
+        // Numbers keep reading left to right
+        assert_eq!(lines("אב 12", &[5]), vec!["12 בא"]);
+    }
+
+    #[test]
+    fn reorders_runs_of_mixed_text() {
+        // The first strong letter sets the paragraph direction
+        assert_eq!(lines("abc אבג def", &[11]), vec!["abc גבא def"]);
+        assert_eq!(lines("אבג abc דהו", &[11]), vec!["והד abc גבא"]);
+    }
+
+    #[test]
+    fn mirrors_brackets_in_right_to_left_runs() {
+        assert_eq!(lines("א(ב)", &[4]), vec!["(ב)א"]);
+        assert_eq!(lines("«אב»", &[4]), vec!["«בא»"]);
+    }
+
+    #[test]
+    fn reorders_every_line_on_its_own() {
+        // Reordering the whole paragraph would put "והד" before "גבא" and
+        // move Hebrew words from one line to the other
+        let paragraph = "abc אבג דהו xyz";
+        assert_eq!(lines(paragraph, &[8, 15]), vec!["abc גבא ", "והד xyz"]);
+
+        let paragraph = "אבג דהו abc";
+        assert_eq!(lines(paragraph, &[4, 11]), vec![" גבא", "abc והד"]);
+    }
+}
@@ -0,0 +1,5 @@
+// Turning label strings into positioned, textured glyph quads
+pub mod arabic;
+pub mod bidi;
+pub mod quads;
+pub mod shaping;
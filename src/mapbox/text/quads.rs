@@ -0,0 +1,81 @@
+// Ref: https://github.com/mapbox/mapbox-gl-js/blob/main/src/symbol/quads.js
+use super::super::glyph_model::GLYPH_BORDER;
+use super::shaping::Shaping;
+
+// One textured rectangle of a label. Corners are relative to the anchor in
+// ONE_EM pixels, the texture rectangle is in glyph atlas pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SymbolQuad {
+    pub tl: (f32, f32),
+    pub tr: (f32, f32),
+    pub bl: (f32, f32),
+    pub br: (f32, f32),
+    pub tex: (u32, u32, u32, u32), // (x, y, width, height)
+    pub codepoint: char,
+    // Horizontal center of the glyph's advance, labels along lines are bent
+    // around it
+    pub glyph_offset: f32,
+}
+
+impl SymbolQuad {
+    // Rotates the quad around the anchor, for text-rotate and line labels
+    pub fn rotate(&mut self, angle: f32) {
+        let (sin, cos) = angle.sin_cos();
+        let rotate = |(x, y): (f32, f32)| (x * cos - y * sin, x * sin + y * cos);
+        self.tl = rotate(self.tl);
+        self.tr = rotate(self.tr);
+        self.bl = rotate(self.bl);
+        self.br = rotate(self.br);
+    }
+
+    pub fn scale(&mut self, scale: f32) {
+        for corner in [&mut self.tl, &mut self.tr, &mut self.bl, &mut self.br].iter_mut() {
+            corner.0 *= scale;
+            corner.1 *= scale;
+        }
+    }
+
+    // Axis aligned bounds as (x1, y1, x2, y2)
+    pub fn bounds(&self) -> (f32, f32, f32, f32) {
+        let corners = [self.tl, self.tr, self.bl, self.br];
+        corners.iter().fold(
+            (f32::MAX, f32::MAX, f32::MIN, f32::MIN),
+            |(x1, y1, x2, y2), (x, y)| (x1.min(*x), y1.min(*y), x2.max(*x), y2.max(*y)),
+        )
+    }
+
+    pub fn translate(&mut self, (dx, dy): (f32, f32)) {
+        for corner in [&mut self.tl, &mut self.tr, &mut self.bl, &mut self.br].iter_mut() {
+            corner.0 += dx;
+            corner.1 += dy;
+        }
+    }
+}
+
+// Glyphs without a bitmap (e.g. spaces) produce no quad
+pub fn get_glyph_quads(shaping: &Shaping) -> Vec<SymbolQuad> {
+    let border = GLYPH_BORDER as f32;
+
+    shaping
+        .positioned_glyphs
+        .iter()
+        .filter(|positioned_glyph| positioned_glyph.glyph.width > 0)
+        .map(|positioned_glyph| {
+            let glyph = positioned_glyph.glyph;
+            let x1 = positioned_glyph.x + glyph.metrics.left as f32 - border;
+            let y1 = positioned_glyph.y - glyph.metrics.top as f32 - border;
+            let x2 = x1 + glyph.width as f32;
+            let y2 = y1 + glyph.height as f32;
+
+            SymbolQuad {
+                tl: (x1, y1),
+                tr: (x2, y1),
+                bl: (x1, y2),
+                br: (x2, y2),
+                tex: (glyph.x, glyph.y, glyph.width, glyph.height),
+                codepoint: positioned_glyph.codepoint,
+                glyph_offset: positioned_glyph.x + glyph.metrics.advance as f32 / 2.0,
+            }
+        })
+        .collect()
+}
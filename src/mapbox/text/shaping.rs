@@ -0,0 +1,694 @@
+// Ref: https://github.com/mapbox/mapbox-gl-js/blob/main/src/symbol/shaping.js
+//
+// Shaping lays the glyphs of a label out relative to its anchor, in pixels of
+// a ONE_EM sized font (glyph SDFs are cut at that size). Scaling to the
+// actual text-size happens when the quads are drawn.
+use super::super::glyph_atlas::GlyphPosition;
+use super::bidi;
+
+use serde_json::Value;
+
+pub const ONE_EM: f32 = 24.0;
+// Baseline of the first line, relative to the top of the line box
+const SHAPING_DEFAULT_OFFSET: f32 = -17.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextJustify {
+    Auto,
+    Left,
+    Center,
+    Right,
+}
+
+impl TextJustify {
+    pub fn parse(justify: &str) -> Option<TextJustify> {
+        match justify {
+            "auto" => Some(TextJustify::Auto),
+            "left" => Some(TextJustify::Left),
+            "center" => Some(TextJustify::Center),
+            "right" => Some(TextJustify::Right),
+            _ => None,
+        }
+    }
+
+    // 0 is flush left, 1 flush right. Auto follows the anchor.
+    fn factor(self, anchor: TextAnchor) -> f32 {
+        match self {
+            TextJustify::Auto => anchor.align().0,
+            TextJustify::Left => 0.0,
+            TextJustify::Center => 0.5,
+            TextJustify::Right => 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextAnchor {
+    Center,
+    Left,
+    Right,
+    Top,
+    Bottom,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl TextAnchor {
+    pub fn parse(anchor: &str) -> Option<TextAnchor> {
+        match anchor {
+            "center" => Some(TextAnchor::Center),
+            "left" => Some(TextAnchor::Left),
+            "right" => Some(TextAnchor::Right),
+            "top" => Some(TextAnchor::Top),
+            "bottom" => Some(TextAnchor::Bottom),
+            "top-left" => Some(TextAnchor::TopLeft),
+            "top-right" => Some(TextAnchor::TopRight),
+            "bottom-left" => Some(TextAnchor::BottomLeft),
+            "bottom-right" => Some(TextAnchor::BottomRight),
+            _ => None,
+        }
+    }
+
+    // (horizontal, vertical) part of the text box which sits on the anchor,
+    // 0 being left / top and 1 right / bottom
+    pub fn align(self) -> (f32, f32) {
+        let horizontal = match self {
+            TextAnchor::Left | TextAnchor::TopLeft | TextAnchor::BottomLeft => 0.0,
+            TextAnchor::Right | TextAnchor::TopRight | TextAnchor::BottomRight => 1.0,
+            _ => 0.5,
+        };
+        let vertical = match self {
+            TextAnchor::Top | TextAnchor::TopLeft | TextAnchor::TopRight => 0.0,
+            TextAnchor::Bottom | TextAnchor::BottomLeft | TextAnchor::BottomRight => 1.0,
+            _ => 0.5,
+        };
+        (horizontal, vertical)
+    }
+}
+
+// Layout properties which affect shaping, lengths are in ems
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextLayout {
+    pub max_width: f32, // 0 disables line breaking
+    pub line_height: f32,
+    pub letter_spacing: f32,
+    pub justify: TextJustify,
+    pub anchor: TextAnchor,
+    pub offset: (f32, f32),
+}
+
+impl Default for TextLayout {
+    fn default() -> TextLayout {
+        TextLayout {
+            max_width: 10.0,
+            line_height: 1.2,
+            letter_spacing: 0.0,
+            justify: TextJustify::Center,
+            anchor: TextAnchor::Center,
+            offset: (0.0, 0.0),
+        }
+    }
+}
+
+impl TextLayout {
+    // Reads a layer's "layout" object. Only constant values are supported,
+    // anything else (e.g. zoom expressions) falls back to the default.
+    pub fn from_layout(layout: &Value) -> TextLayout {
+        let default = TextLayout::default();
+        let number =
+            |name: &str, default: f32| layout[name].as_f64().map(|v| v as f32).unwrap_or(default);
+        let offset = layout["text-offset"]
+            .as_array()
+            .filter(|offset| offset.len() == 2)
+            .and_then(|offset| Some((offset[0].as_f64()? as f32, offset[1].as_f64()? as f32)))
+            .unwrap_or(default.offset);
+
+        TextLayout {
+            max_width: number("text-max-width", default.max_width),
+            line_height: number("text-line-height", default.line_height),
+            letter_spacing: number("text-letter-spacing", default.letter_spacing),
+            justify: layout["text-justify"]
+                .as_str()
+                .and_then(TextJustify::parse)
+                .unwrap_or(default.justify),
+            anchor: layout["text-anchor"]
+                .as_str()
+                .and_then(TextAnchor::parse)
+                .unwrap_or(default.anchor),
+            offset,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PositionedGlyph {
+    pub codepoint: char,
+    // Left end of the glyph's advance on the baseline
+    pub x: f32,
+    pub y: f32,
+    pub glyph: GlyphPosition,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shaping {
+    pub positioned_glyphs: Vec<PositionedGlyph>,
+    pub line_count: usize,
+    // Bounding box of the text relative to the anchor
+    pub top: f32,
+    pub bottom: f32,
+    pub left: f32,
+    pub right: f32,
+}
+
+// Lays `text` out. `get_glyph` provides the glyph metrics, characters without
+// a glyph are skipped. None if nothing is left to draw.
+pub fn shape_text(
+    text: &str,
+    layout: &TextLayout,
+    get_glyph: &dyn Fn(char) -> Option<GlyphPosition>,
+) -> Option<Shaping> {
+    let line_height = layout.line_height * ONE_EM;
+    let spacing = layout.letter_spacing * ONE_EM;
+    let max_width = layout.max_width * ONE_EM;
+
+    let mut lines: Vec<Vec<char>> = vec![];
+    for paragraph in text.split('\n') {
+        // Breaks are found in logical order, the lines are then reordered
+        let chars: Vec<char> = paragraph.chars().collect();
+        let line_ends = determine_line_breaks(&chars, spacing, max_width, get_glyph);
+        lines.extend(bidi::process_text(paragraph, &line_ends));
+    }
+
+    let mut positioned_glyphs = vec![];
+    let mut max_line_width: f32 = 0.0;
+    let mut line_extents = vec![];
+    let mut y = SHAPING_DEFAULT_OFFSET;
+
+    for line in lines.iter() {
+        let line_start = positioned_glyphs.len();
+        let mut x = 0.0;
+        for &codepoint in trim(line) {
+            let glyph = match get_glyph(codepoint) {
+                Some(glyph) => glyph,
+                None => continue,
+            };
+            positioned_glyphs.push(PositionedGlyph {
+                codepoint,
+                x,
+                y,
+                glyph,
+            });
+            x += glyph.metrics.advance as f32 + spacing;
+        }
+
+        // The last glyph's spacing doesn't count
+        let line_width = match positioned_glyphs[line_start..].last() {
+            Some(last) => last.x + last.glyph.metrics.advance as f32,
+            None => 0.0,
+        };
+        max_line_width = max_line_width.max(line_width);
+        line_extents.push((line_start, positioned_glyphs.len(), line_width));
+        y += line_height;
+    }
+
+    if positioned_glyphs.is_empty() {
+        return None;
+    }
+
+    let justify = layout.justify.factor(layout.anchor);
+    let (horizontal_align, vertical_align) = layout.anchor.align();
+    let line_count = lines.len();
+    let block_height = line_count as f32 * line_height;
+    let (offset_x, offset_y) = (layout.offset.0 * ONE_EM, layout.offset.1 * ONE_EM);
+
+    let shift_y = (-vertical_align * line_count as f32 + 0.5) * line_height + offset_y;
+    for (start, end, line_width) in line_extents {
+        let shift_x =
+            (max_line_width - line_width) * justify - max_line_width * horizontal_align + offset_x;
+        for positioned_glyph in positioned_glyphs[start..end].iter_mut() {
+            positioned_glyph.x += shift_x;
+            positioned_glyph.y += shift_y;
+        }
+    }
+
+    let top = offset_y - block_height * vertical_align;
+    let left = offset_x - max_line_width * horizontal_align;
+    Some(Shaping {
+        positioned_glyphs,
+        line_count,
+        top,
+        bottom: top + block_height,
+        left,
+        right: left + max_line_width,
+    })
+}
+
+fn trim(line: &[char]) -> &[char] {
+    let start = line
+        .iter()
+        .position(|c| !c.is_whitespace())
+        .unwrap_or(line.len());
+    let end = line
+        .iter()
+        .rposition(|c| !c.is_whitespace())
+        .map(|inx| inx + 1)
+        .unwrap_or(start);
+    &line[start..end]
+}
+
+// Line breaking
+
+struct Break {
+    inx: usize, // The line ends before chars[inx]
+    x: f32,
+    badness: f32,
+    prior: Option<usize>, // Index into the list of evaluated breaks
+}
+
+// Returns the end index of every line. Breaks are balanced so that all lines
+// come out about as wide as each other instead of filling up greedily.
+fn determine_line_breaks(
+    chars: &[char],
+    spacing: f32,
+    max_width: f32,
+    get_glyph: &dyn Fn(char) -> Option<GlyphPosition>,
+) -> Vec<usize> {
+    if max_width <= 0.0 || chars.is_empty() {
+        return vec![chars.len()];
+    }
+
+    let advance = |c: char| {
+        get_glyph(c)
+            .map(|glyph| glyph.metrics.advance as f32 + spacing)
+            .unwrap_or(0.0)
+    };
+    let total_width: f32 = chars.iter().map(|&c| advance(c)).sum();
+    if total_width <= max_width {
+        return vec![chars.len()];
+    }
+    let line_count = (total_width / max_width).ceil().max(1.0);
+    let target_width = total_width / line_count;
+
+    let mut breaks: Vec<Break> = vec![];
+    let mut x = 0.0;
+    for (inx, &c) in chars.iter().enumerate() {
+        x += advance(c);
+        if inx + 1 < chars.len() {
+            let next = chars[inx + 1];
+            let ideographic = allows_ideographic_break(c) || allows_ideographic_break(next);
+            if is_breakable(c) || ideographic {
+                let penalty = break_penalty(c, next);
+                let evaluated = evaluate_break(inx + 1, x, target_width, &breaks, penalty, false);
+                breaks.push(evaluated);
+            }
+        }
+    }
+    let last = evaluate_break(chars.len(), x, target_width, &breaks, 0.0, true);
+
+    let mut line_ends = vec![last.inx];
+    let mut prior = last.prior;
+    while let Some(inx) = prior {
+        line_ends.push(breaks[inx].inx);
+        prior = breaks[inx].prior;
+    }
+    line_ends.reverse();
+    line_ends
+}
+
+fn evaluate_break(
+    inx: usize,
+    x: f32,
+    target_width: f32,
+    breaks: &[Break],
+    penalty: f32,
+    is_last_break: bool,
+) -> Break {
+    let mut prior = None;
+    let mut best_badness = calculate_badness(x, target_width, penalty, is_last_break);
+
+    for (break_inx, potential) in breaks.iter().enumerate() {
+        let badness = calculate_badness(x - potential.x, target_width, penalty, is_last_break)
+            + potential.badness;
+        if badness <= best_badness {
+            prior = Some(break_inx);
+            best_badness = badness;
+        }
+    }
+
+    Break {
+        inx,
+        x,
+        badness: best_badness,
+        prior,
+    }
+}
+
+fn calculate_badness(line_width: f32, target_width: f32, penalty: f32, is_last_break: bool) -> f32 {
+    let raggedness = (line_width - target_width).powi(2);
+    if is_last_break {
+        // A short last line is better than a long one
+        if line_width < target_width {
+            raggedness / 2.0
+        } else {
+            raggedness * 2.0
+        }
+    } else if penalty < 0.0 {
+        raggedness - penalty * penalty
+    } else {
+        raggedness + penalty * penalty
+    }
+}
+
+fn break_penalty(c: char, next: char) -> f32 {
+    let mut penalty = 0.0;
+    // Keep parentheses with what they enclose
+    if c == '(' || c == '\u{ff08}' {
+        penalty += 50.0;
+    }
+    if next == ')' || next == '\u{ff09}' {
+        penalty += 50.0;
+    }
+    penalty
+}
+
+// A line may end after these
+fn is_breakable(c: char) -> bool {
+    matches!(
+        c,
+        ' ' | '\t'
+            | '&'
+            | '('
+            | ')'
+            | '+'
+            | '-'
+            | '/'
+            | '\u{ad}'
+            | '\u{b7}'
+            | '\u{200b}'
+            | '\u{2010}'
+            | '\u{2013}'
+            | '\u{2027}'
+    )
+}
+
+// CJK scripts don't separate words, lines may break between any two characters
+pub fn allows_ideographic_break(c: char) -> bool {
+    match c as u32 {
+        0x2e80..=0x2fdf // CJK Radicals, Kangxi Radicals
+        | 0x2ff0..=0x303f // Ideographic Description, CJK Symbols and Punctuation
+        | 0x3040..=0x30ff // Hiragana, Katakana
+        | 0x3100..=0x312f // Bopomofo
+        | 0x3190..=0x31ef // Kanbun, Bopomofo Extended, CJK Strokes
+        | 0x31f0..=0x31ff // Katakana Phonetic Extensions
+        | 0x3200..=0x4dbf // Enclosed CJK, CJK Compatibility, CJK Extension A
+        | 0x4e00..=0x9fff // CJK Unified Ideographs
+        | 0xa000..=0xa4cf // Yi
+        | 0xf900..=0xfaff // CJK Compatibility Ideographs
+        | 0xfe30..=0xfe4f // CJK Compatibility Forms
+        | 0xff00..=0xffef // Halfwidth and Fullwidth Forms
+        => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::glyph_model::GlyphMetrics;
+    use super::*;
+    use serde_json::json;
+
+    const ADVANCE: f32 = 12.0;
+    const LINE_HEIGHT: f32 = 1.2 * ONE_EM;
+    const EPSILON: f32 = 1e-4;
+
+    // Every character half an em wide, except control characters which have
+    // no glyph
+    fn glyph(c: char) -> Option<GlyphPosition> {
+        if c.is_control() {
+            return None;
+        }
+        Some(GlyphPosition {
+            x: 0,
+            y: 0,
+            width: 10,
+            height: 10,
+            metrics: GlyphMetrics {
+                advance: ADVANCE as u32,
+                ..Default::default()
+            },
+        })
+    }
+
+    fn layout(justify: TextJustify, anchor: TextAnchor) -> TextLayout {
+        TextLayout {
+            justify,
+            anchor,
+            ..Default::default()
+        }
+    }
+
+    fn assert_near(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < EPSILON,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    // (x, y) of the first glyph of every line
+    fn line_starts(shaping: &Shaping) -> Vec<(f32, f32)> {
+        let mut starts: Vec<(f32, f32)> = vec![];
+        for positioned_glyph in shaping.positioned_glyphs.iter() {
+            if starts.last().is_none_or(|(_, y)| *y != positioned_glyph.y) {
+                starts.push((positioned_glyph.x, positioned_glyph.y));
+            }
+        }
+        starts
+    }
+
+    fn line_breaks(text: &str, max_width: f32) -> Vec<usize> {
+        let chars: Vec<char> = text.chars().collect();
+        determine_line_breaks(&chars, 0.0, max_width, &glyph)
+    }
+
+    #[test]
+    fn parses_justify_and_anchor() {
+        assert_eq!(TextJustify::parse("right"), Some(TextJustify::Right));
+        assert_eq!(TextJustify::parse("middle"), None);
+        assert_eq!(
+            TextAnchor::parse("bottom-left"),
+            Some(TextAnchor::BottomLeft)
+        );
+        assert_eq!(TextAnchor::parse("bottom left"), None);
+
+        let layout = TextLayout::from_layout(&json!({
+            "text-justify": "left",
+            "text-anchor": "top",
+            "text-max-width": 4,
+            "text-offset": [1, -0.5],
+            "text-line-height": ["interpolate", ["linear"], ["zoom"], 0, 1, 10, 2]
+        }));
+        assert_eq!(layout.justify, TextJustify::Left);
+        assert_eq!(layout.anchor, TextAnchor::Top);
+        assert_eq!(layout.max_width, 4.0);
+        assert_eq!(layout.offset, (1.0, -0.5));
+        // Not a constant
+        assert_eq!(layout.line_height, TextLayout::default().line_height);
+    }
+
+    #[test]
+    fn shapes_single_line_around_anchor() {
+        let shaping = shape_text("abc", &TextLayout::default(), &glyph).unwrap();
+        assert_eq!(shaping.line_count, 1);
+        let xs: Vec<f32> = shaping.positioned_glyphs.iter().map(|g| g.x).collect();
+        assert_eq!(xs, vec![-18.0, -6.0, 6.0]);
+        for positioned_glyph in shaping.positioned_glyphs.iter() {
+            assert_near(positioned_glyph.y, SHAPING_DEFAULT_OFFSET);
+        }
+        assert_near(shaping.left, -18.0);
+        assert_near(shaping.right, 18.0);
+        assert_near(shaping.top, -LINE_HEIGHT / 2.0);
+        assert_near(shaping.bottom, LINE_HEIGHT / 2.0);
+    }
+
+    #[test]
+    fn letter_spacing_widens_line() {
+        let layout = TextLayout {
+            letter_spacing: 0.5,
+            anchor: TextAnchor::Left,
+            ..Default::default()
+        };
+        let shaping = shape_text("abc", &layout, &glyph).unwrap();
+        let xs: Vec<f32> = shaping.positioned_glyphs.iter().map(|g| g.x).collect();
+        assert_eq!(xs, vec![0.0, 24.0, 48.0]);
+        // No spacing after the last glyph
+        assert_near(shaping.right, 60.0);
+    }
+
+    #[test]
+    fn breaks_lines_evenly() {
+        // Filling up greedily would give "aaaaaa bb " and "cc dd"
+        assert_eq!(line_breaks("aaaaaa bb cc dd", 5.0 * ONE_EM), vec![7, 15]);
+        // Short enough, or breaking disabled
+        assert_eq!(line_breaks("aaaaaa bb cc dd", 10.0 * ONE_EM), vec![15]);
+        assert_eq!(line_breaks("aaaaaa bb cc dd", 0.0), vec![15]);
+        // Nowhere to break
+        assert_eq!(line_breaks("aaaaaaaaaaaaaaa", 2.0 * ONE_EM), vec![15]);
+
+        let layout = TextLayout {
+            max_width: 5.0,
+            ..Default::default()
+        };
+        let shaping = shape_text("aaaaaa bb cc dd", &layout, &glyph).unwrap();
+        assert_eq!(shaping.line_count, 2);
+        // The space the first line ends with is trimmed
+        assert_eq!(shaping.positioned_glyphs.len(), 14);
+        let starts = line_starts(&shaping);
+        assert_eq!(starts.len(), 2);
+        assert_near(starts[1].1 - starts[0].1, LINE_HEIGHT);
+    }
+
+    #[test]
+    fn keeps_parentheses_with_their_words() {
+        // "aaaa (" would be the most even first line
+        assert_eq!(line_breaks("aaaa (bb) cccc", 4.0 * ONE_EM), vec![9, 14]);
+    }
+
+    #[test]
+    fn breaks_between_ideographs() {
+        assert!(allows_ideographic_break('中'));
+        assert!(allows_ideographic_break('カ'));
+        assert!(!allows_ideographic_break('a'));
+
+        assert_eq!(line_breaks("中文字体测试", 2.0 * ONE_EM), vec![3, 6]);
+        // Latin next to an ideograph may break there too
+        assert_eq!(line_breaks("abcd中文", 2.0 * ONE_EM), vec![4, 6]);
+    }
+
+    #[test]
+    fn paragraphs_are_lines_of_their_own() {
+        let shaping = shape_text("aaaa\nbb", &TextLayout::default(), &glyph).unwrap();
+        assert_eq!(shaping.line_count, 2);
+        assert_near(shaping.top, -LINE_HEIGHT);
+        assert_near(shaping.bottom, LINE_HEIGHT);
+    }
+
+    #[test]
+    fn justifies_lines_within_block() {
+        let shape = |justify, anchor| {
+            let shaping = shape_text("aaaa\nbb", &layout(justify, anchor), &glyph).unwrap();
+            line_starts(&shaping)
+                .iter()
+                .map(|(x, _)| *x)
+                .collect::<Vec<f32>>()
+        };
+        assert_eq!(
+            shape(TextJustify::Left, TextAnchor::TopLeft),
+            vec![0.0, 0.0]
+        );
+        assert_eq!(
+            shape(TextJustify::Center, TextAnchor::TopLeft),
+            vec![0.0, 12.0]
+        );
+        assert_eq!(
+            shape(TextJustify::Right, TextAnchor::TopLeft),
+            vec![0.0, 24.0]
+        );
+        // Auto follows the anchor
+        assert_eq!(
+            shape(TextJustify::Auto, TextAnchor::Right),
+            vec![-48.0, -24.0]
+        );
+        assert_eq!(
+            shape(TextJustify::Auto, TextAnchor::Bottom),
+            vec![-24.0, -12.0]
+        );
+    }
+
+    #[test]
+    fn anchors_and_offsets_block() {
+        let shaping = shape_text(
+            "aaaa\nbb",
+            &layout(TextJustify::Left, TextAnchor::TopLeft),
+            &glyph,
+        )
+        .unwrap();
+        assert_near(shaping.top, 0.0);
+        assert_near(shaping.bottom, 2.0 * LINE_HEIGHT);
+        assert_near(shaping.left, 0.0);
+        assert_near(shaping.right, 48.0);
+        let first_baseline = shaping.positioned_glyphs[0].y;
+        assert_near(first_baseline, SHAPING_DEFAULT_OFFSET + LINE_HEIGHT / 2.0);
+
+        let shaping = shape_text(
+            "aaaa\nbb",
+            &layout(TextJustify::Left, TextAnchor::BottomRight),
+            &glyph,
+        )
+        .unwrap();
+        assert_near(shaping.top, -2.0 * LINE_HEIGHT);
+        assert_near(shaping.bottom, 0.0);
+        assert_near(shaping.left, -48.0);
+        assert_near(shaping.right, 0.0);
+        assert_near(
+            shaping.positioned_glyphs[0].y,
+            first_baseline - 2.0 * LINE_HEIGHT,
+        );
+
+        // Offsets are in ems and move everything
+        let layout = TextLayout {
+            offset: (1.0, -2.0),
+            ..layout(TextJustify::Left, TextAnchor::TopLeft)
+        };
+        let shaping = shape_text("aaaa\nbb", &layout, &glyph).unwrap();
+        assert_near(shaping.left, ONE_EM);
+        assert_near(shaping.top, -2.0 * ONE_EM);
+        assert_near(shaping.positioned_glyphs[0].x, ONE_EM);
+        assert_near(
+            shaping.positioned_glyphs[0].y,
+            first_baseline - 2.0 * ONE_EM,
+        );
+    }
+
+    #[test]
+    fn reorders_mixed_direction_lines_after_breaking() {
+        let text = "abc אבג דהו xyz";
+        assert_eq!(line_breaks(text, 4.0 * ONE_EM), vec![8, 15]);
+
+        let layout = TextLayout {
+            max_width: 4.0,
+            ..Default::default()
+        };
+        let shaping = shape_text(text, &layout, &glyph).unwrap();
+        assert_eq!(shaping.line_count, 2);
+        let line_ys: Vec<f32> = line_starts(&shaping).iter().map(|(_, y)| *y).collect();
+        let line = |y: f32| -> String {
+            shaping
+                .positioned_glyphs
+                .iter()
+                .filter(|g| g.y == y)
+                .map(|g| g.codepoint)
+                .collect()
+        };
+        // Every line is in visual order on its own, the Hebrew words stay on
+        // the line they were broken onto
+        assert_eq!(line(line_ys[0]), "abc גבא");
+        assert_eq!(line(line_ys[1]), "והד xyz");
+    }
+
+    #[test]
+    fn nothing_to_draw_shapes_to_none() {
+        let layout = TextLayout::default();
+        assert_eq!(shape_text("", &layout, &glyph), None);
+        assert_eq!(shape_text("   \n ", &layout, &glyph), None);
+        // No glyphs for any of it
+        assert_eq!(shape_text("\u{1}\u{2}", &layout, &glyph), None);
+        // Missing glyphs are skipped
+        let shaping = shape_text("a\u{1}b", &layout, &glyph).unwrap();
+        assert_eq!(shaping.positioned_glyphs.len(), 2);
+        assert_near(shaping.right - shaping.left, 24.0);
+    }
+}
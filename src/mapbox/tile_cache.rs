@@ -0,0 +1,466 @@
+// Ref: https://github.com/mapbox/mapbox-gl-native/blob/master/src/mbgl/tile/tile_cache.cpp
+//
+// Parsed tiles of all sources, least recently used ones evicted once the
+// cache holds more tiles or bytes than allowed. Tiles in use, i.e. drawn for
+// the current view or standing in for tiles of it, are never evicted, so the
+// limits may be exceeded while the view itself needs more.
+use super::config;
+use super::vector_tile_id::VectorTileID;
+use super::vector_tile_model::VectorTileModel;
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TileCacheLimits {
+    pub max_tiles: usize,
+    pub max_bytes: usize,
+}
+
+impl Default for TileCacheLimits {
+    fn default() -> TileCacheLimits {
+        TileCacheLimits {
+            max_tiles: config::TILE_CACHE_MAX_TILES,
+            max_bytes: config::TILE_CACHE_MAX_BYTES,
+        }
+    }
+}
+
+// Hits and misses count tiles coming into view the cache could and couldn't
+// serve
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TileCacheMetrics {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    pub tiles: usize,
+    // Parsed models plus the GPU buffers built from them
+    pub bytes: usize,
+}
+
+struct CachedTile {
+    model: Arc<VectorTileModel>,
+    model_bytes: usize,
+    buffer_bytes: usize,
+    last_used: u64,
+}
+
+impl CachedTile {
+    fn bytes(&self) -> usize {
+        self.model_bytes + self.buffer_bytes
+    }
+}
+
+pub struct TileCache {
+    tiles: HashMap<String, HashMap<VectorTileID, CachedTile>>,
+    in_use: HashMap<String, HashSet<VectorTileID>>,
+    // Tiles of the last request per source, and whether they were cached
+    covered: HashMap<String, HashMap<VectorTileID, bool>>,
+    limits: TileCacheLimits,
+    metrics: TileCacheMetrics,
+    // Bumped on every use, orders tiles from least to most recently used
+    clock: u64,
+}
+
+impl TileCache {
+    pub fn new(limits: TileCacheLimits) -> TileCache {
+        TileCache {
+            tiles: HashMap::new(),
+            in_use: HashMap::new(),
+            covered: HashMap::new(),
+            limits,
+            metrics: TileCacheMetrics::default(),
+            clock: 0,
+        }
+    }
+
+    pub fn add_source(&mut self, source_id: String) {
+        self.tiles.entry(source_id).or_default();
+    }
+
+    pub fn has_source(&self, source_id: &str) -> bool {
+        self.tiles.contains_key(source_id)
+    }
+
+    pub fn remove_source(&mut self, source_id: &str) {
+        self.in_use.remove(source_id);
+        self.covered.remove(source_id);
+        for tile in self
+            .tiles
+            .remove(source_id)
+            .into_iter()
+            .flat_map(|tiles| tiles.into_values())
+        {
+            self.metrics.tiles -= 1;
+            self.metrics.bytes -= tile.bytes();
+        }
+    }
+
+    pub fn contains(&self, source_id: &str, tile_id: &VectorTileID) -> bool {
+        self.tiles
+            .get(source_id)
+            .is_some_and(|tiles| tiles.contains_key(tile_id))
+    }
+
+    pub fn get(&self, source_id: &str, tile_id: &VectorTileID) -> Option<Arc<VectorTileModel>> {
+        Some(self.tiles.get(source_id)?.get(tile_id)?.model.clone())
+    }
+
+    // Tiles covering the view for the source, replacing the ones covered
+    // before. Returns those not cached. Only tiles coming into the cover
+    // count as hits or misses, so a view redrawn every frame doesn't inflate
+    // them, and so do covered tiles that were evicted since.
+    pub fn request(&mut self, source_id: &str, tile_ids: &[VectorTileID]) -> Vec<VectorTileID> {
+        if !self.has_source(source_id) {
+            return vec![]; // Source removed
+        }
+        self.clock += 1;
+        let previous = self.covered.remove(source_id).unwrap_or_default();
+        let mut covered = HashMap::new();
+        let mut missing = vec![];
+        for tile_id in tile_ids.iter() {
+            if covered.contains_key(tile_id) {
+                continue; // Another copy of the world
+            }
+            let cached = match self
+                .tiles
+                .get_mut(source_id)
+                .and_then(|tiles| tiles.get_mut(tile_id))
+            {
+                Some(tile) => {
+                    tile.last_used = self.clock;
+                    true
+                }
+                None => false,
+            };
+            match (previous.get(tile_id), cached) {
+                (None, true) => self.metrics.hits += 1,
+                (None, false) | (Some(true), false) => self.metrics.misses += 1,
+                _ => {}
+            }
+            if !cached {
+                missing.push(*tile_id);
+            }
+            covered.insert(*tile_id, cached);
+        }
+        self.covered.insert(source_id.to_owned(), covered);
+        missing
+    }
+
+    // Returns the tiles evicted to make room
+    pub fn insert(
+        &mut self,
+        source_id: &str,
+        tile_id: VectorTileID,
+        model: Arc<VectorTileModel>,
+    ) -> Vec<(String, VectorTileID)> {
+        let tiles = match self.tiles.get_mut(source_id) {
+            Some(tiles) => tiles,
+            None => return vec![], // Source removed
+        };
+        self.clock += 1;
+        let tile = CachedTile {
+            model_bytes: model.estimated_size(),
+            model,
+            buffer_bytes: 0,
+            last_used: self.clock,
+        };
+        self.metrics.tiles += 1;
+        self.metrics.bytes += tile.bytes();
+        if let Some(replaced) = tiles.insert(tile_id, tile) {
+            self.metrics.tiles -= 1;
+            self.metrics.bytes -= replaced.bytes();
+        }
+        self.evict()
+    }
+
+    // Size of the GPU buffers built for the tile, once they are. Reported
+    // while drawing, so it doesn't evict, the next load does.
+    pub fn set_buffer_size(&mut self, source_id: &str, tile_id: &VectorTileID, bytes: usize) {
+        if let Some(tile) = self
+            .tiles
+            .get_mut(source_id)
+            .and_then(|tiles| tiles.get_mut(tile_id))
+        {
+            self.metrics.bytes = self.metrics.bytes - tile.buffer_bytes + bytes;
+            tile.buffer_bytes = bytes;
+        }
+    }
+
+    // Tiles the current view draws from for the source, replacing the ones
+    // set before. Out of view tiles become evictable.
+    pub fn set_in_use(
+        &mut self,
+        source_id: &str,
+        tile_ids: HashSet<VectorTileID>,
+    ) -> Vec<(String, VectorTileID)> {
+        self.clock += 1;
+        if let Some(tiles) = self.tiles.get_mut(source_id) {
+            for tile_id in tile_ids.iter() {
+                if let Some(tile) = tiles.get_mut(tile_id) {
+                    tile.last_used = self.clock;
+                }
+            }
+        }
+        self.in_use.insert(source_id.to_owned(), tile_ids);
+        self.evict()
+    }
+
+    pub fn get_limits(&self) -> TileCacheLimits {
+        self.limits
+    }
+
+    pub fn set_limits(&mut self, limits: TileCacheLimits) -> Vec<(String, VectorTileID)> {
+        self.limits = limits;
+        self.evict()
+    }
+
+    pub fn get_metrics(&self) -> TileCacheMetrics {
+        self.metrics
+    }
+
+    fn is_over_limits(&self) -> bool {
+        self.metrics.tiles > self.limits.max_tiles || self.metrics.bytes > self.limits.max_bytes
+    }
+
+    fn evict(&mut self) -> Vec<(String, VectorTileID)> {
+        let mut evicted = vec![];
+        if !self.is_over_limits() {
+            return evicted;
+        }
+
+        let mut candidates: Vec<(u64, String, VectorTileID)> = vec![];
+        for (source_id, tiles) in self.tiles.iter() {
+            let in_use = self.in_use.get(source_id);
+            for (tile_id, tile) in tiles.iter() {
+                if !in_use.is_some_and(|in_use| in_use.contains(tile_id)) {
+                    candidates.push((tile.last_used, source_id.clone(), *tile_id));
+                }
+            }
+        }
+        candidates.sort_by_key(|(last_used, _, _)| *last_used);
+
+        for (_, source_id, tile_id) in candidates {
+            if !self.is_over_limits() {
+                break;
+            }
+            let tile = self
+                .tiles
+                .get_mut(&source_id)
+                .and_then(|tiles| tiles.remove(&tile_id))
+                .unwrap();
+            self.metrics.tiles -= 1;
+            self.metrics.bytes -= tile.bytes();
+            self.metrics.evictions += 1;
+            evicted.push((source_id, tile_id));
+        }
+        evicted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "streets";
+
+    fn tile(x: u32) -> VectorTileID {
+        VectorTileID::new(x, 0, 4)
+    }
+
+    fn model() -> Arc<VectorTileModel> {
+        Arc::new(VectorTileModel { layers: vec![] })
+    }
+
+    fn model_bytes() -> usize {
+        model().estimated_size()
+    }
+
+    fn cache(max_tiles: usize, max_bytes: usize) -> TileCache {
+        let mut cache = TileCache::new(TileCacheLimits {
+            max_tiles,
+            max_bytes,
+        });
+        cache.add_source(SOURCE.to_owned());
+        cache
+    }
+
+    fn evicted(tile_ids: &[VectorTileID]) -> Vec<(String, VectorTileID)> {
+        tile_ids
+            .iter()
+            .map(|tile_id| (SOURCE.to_owned(), *tile_id))
+            .collect()
+    }
+
+    #[test]
+    fn counts_tiles_coming_into_cover() {
+        let mut cache = cache(10, usize::MAX);
+        assert_eq!(
+            cache.request(SOURCE, &[tile(0), tile(1)]),
+            vec![tile(0), tile(1)]
+        );
+        // The same view, frame after frame
+        cache.request(SOURCE, &[tile(0), tile(1)]);
+        cache.request(SOURCE, &[tile(0), tile(1)]);
+        assert_eq!(
+            (cache.get_metrics().hits, cache.get_metrics().misses),
+            (0, 2)
+        );
+
+        // Loading a missed tile counts nothing more
+        cache.insert(SOURCE, tile(0), model());
+        assert_eq!(cache.request(SOURCE, &[tile(0), tile(1)]), vec![tile(1)]);
+        assert_eq!(
+            (cache.get_metrics().hits, cache.get_metrics().misses),
+            (0, 2)
+        );
+
+        // Out of view and back in again
+        cache.request(SOURCE, &[tile(1)]);
+        cache.request(SOURCE, &[tile(0), tile(1)]);
+        assert_eq!(
+            (cache.get_metrics().hits, cache.get_metrics().misses),
+            (1, 2)
+        );
+    }
+
+    #[test]
+    fn counts_world_copies_once() {
+        let mut cache = cache(10, usize::MAX);
+        cache.insert(SOURCE, tile(0), model());
+        assert!(cache.request(SOURCE, &[tile(0), tile(0)]).is_empty());
+        assert_eq!(cache.get_metrics().hits, 1);
+        assert_eq!(cache.request(SOURCE, &[tile(1), tile(1)]), vec![tile(1)]);
+        assert_eq!(cache.get_metrics().misses, 1);
+    }
+
+    #[test]
+    fn counts_covered_tile_evicted_since() {
+        let mut cache = cache(1, usize::MAX);
+        cache.insert(SOURCE, tile(0), model());
+        cache.request(SOURCE, &[tile(0)]);
+        cache.insert(SOURCE, tile(1), model());
+        assert!(!cache.contains(SOURCE, &tile(0)));
+
+        assert_eq!(cache.request(SOURCE, &[tile(0)]), vec![tile(0)]);
+        assert_eq!(
+            (cache.get_metrics().hits, cache.get_metrics().misses),
+            (1, 1)
+        );
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let mut cache = cache(2, usize::MAX);
+        assert!(cache.insert(SOURCE, tile(0), model()).is_empty());
+        assert!(cache.insert(SOURCE, tile(1), model()).is_empty());
+        // Used again, so tile 1 is the oldest now
+        cache.request(SOURCE, &[tile(0)]);
+
+        assert_eq!(cache.insert(SOURCE, tile(2), model()), evicted(&[tile(1)]));
+        assert_eq!(cache.insert(SOURCE, tile(3), model()), evicted(&[tile(0)]));
+        assert!(cache.contains(SOURCE, &tile(2)));
+        assert!(cache.contains(SOURCE, &tile(3)));
+
+        let metrics = cache.get_metrics();
+        assert_eq!((metrics.tiles, metrics.evictions), (2, 2));
+        assert_eq!(metrics.bytes, 2 * model_bytes());
+    }
+
+    #[test]
+    fn evicts_down_to_max_bytes() {
+        let mut cache = cache(10, usize::MAX);
+        for x in 0..3 {
+            cache.insert(SOURCE, tile(x), model());
+        }
+        cache.set_buffer_size(SOURCE, &tile(2), 1000);
+
+        // Room for the big tile only, the older ones go first
+        let evicted_tiles = cache.set_limits(TileCacheLimits {
+            max_tiles: 10,
+            max_bytes: model_bytes() + 1000,
+        });
+        assert_eq!(evicted_tiles, evicted(&[tile(0), tile(1)]));
+        assert_eq!(cache.get_metrics().bytes, model_bytes() + 1000);
+
+        // Too big on its own
+        let evicted_tiles = cache.set_limits(TileCacheLimits {
+            max_tiles: 10,
+            max_bytes: 500,
+        });
+        assert_eq!(evicted_tiles, evicted(&[tile(2)]));
+        let metrics = cache.get_metrics();
+        assert_eq!((metrics.tiles, metrics.bytes, metrics.evictions), (0, 0, 3));
+    }
+
+    #[test]
+    fn keeps_tiles_in_use() {
+        let mut cache = cache(1, usize::MAX);
+        cache.insert(SOURCE, tile(0), model());
+        cache.set_in_use(SOURCE, [tile(0), tile(1)].iter().cloned().collect());
+
+        // The view needs both, so the limit gives way
+        assert!(cache.insert(SOURCE, tile(1), model()).is_empty());
+        assert_eq!(cache.get_metrics().tiles, 2);
+
+        // Tiles not in use go, however recently used
+        assert_eq!(cache.insert(SOURCE, tile(2), model()), evicted(&[tile(2)]));
+
+        assert_eq!(
+            cache.set_in_use(SOURCE, [tile(1)].iter().cloned().collect()),
+            evicted(&[tile(0)])
+        );
+        assert!(cache.contains(SOURCE, &tile(1)));
+        assert_eq!(cache.get_metrics().tiles, 1);
+    }
+
+    #[test]
+    fn buffer_sizes_add_up() {
+        let mut cache = cache(10, usize::MAX);
+        cache.insert(SOURCE, tile(0), model());
+        cache.insert(SOURCE, tile(1), model());
+
+        cache.set_buffer_size(SOURCE, &tile(0), 300);
+        cache.set_buffer_size(SOURCE, &tile(1), 200);
+        assert_eq!(cache.get_metrics().bytes, 2 * model_bytes() + 500);
+
+        // Rebuilt buffers replace the old size
+        cache.set_buffer_size(SOURCE, &tile(0), 100);
+        assert_eq!(cache.get_metrics().bytes, 2 * model_bytes() + 300);
+
+        // Tiles the cache doesn't hold are ignored
+        cache.set_buffer_size(SOURCE, &tile(5), 1000);
+        cache.set_buffer_size("other", &tile(0), 1000);
+        assert_eq!(cache.get_metrics().bytes, 2 * model_bytes() + 300);
+
+        // A reloaded tile starts without buffers
+        cache.insert(SOURCE, tile(0), model());
+        let metrics = cache.get_metrics();
+        assert_eq!((metrics.tiles, metrics.bytes), (2, 2 * model_bytes() + 200));
+    }
+
+    #[test]
+    fn remove_source_drops_its_tiles() {
+        let mut cache = cache(10, usize::MAX);
+        cache.add_source("other".to_owned());
+        cache.insert(SOURCE, tile(0), model());
+        cache.insert(SOURCE, tile(1), model());
+        cache.set_buffer_size(SOURCE, &tile(1), 400);
+        cache.insert("other", tile(0), model());
+        cache.request(SOURCE, &[tile(0), tile(1)]);
+
+        cache.remove_source(SOURCE);
+        assert!(!cache.has_source(SOURCE));
+        assert!(!cache.contains(SOURCE, &tile(0)));
+        assert!(cache.contains("other", &tile(0)));
+        let metrics = cache.get_metrics();
+        assert_eq!((metrics.tiles, metrics.bytes), (1, model_bytes()));
+        // Dropped, not evicted, and earlier counts stay
+        assert_eq!((metrics.hits, metrics.evictions), (2, 0));
+
+        // Late responses and requests for it are ignored
+        assert!(cache.insert(SOURCE, tile(0), model()).is_empty());
+        assert!(cache.request(SOURCE, &[tile(0)]).is_empty());
+        assert_eq!(cache.get_metrics(), metrics);
+    }
+}
@@ -0,0 +1,346 @@
+// Ref: https://github.com/mapbox/mapbox-gl-js/blob/main/src/geo/transform.js
+//
+// Camera state and the matrices derived from it. "World" coords are pixels of
+// the whole projected world at the current (fractional) zoom, tiles are
+// requested at the integer zoom below it and scaled up to fill the gap.
+use super::config::TILE_SIZE;
+use super::geo::{LatLng, LatLngBounds, ScreenCoordinate, TileCoordinate};
+use super::utils::matrix::{self, Mat4};
+use super::utils::projection::ProjectionType;
+use super::utils::transformation::Tranformation;
+use super::vector_tile_id::UnwrappedTileID;
+
+// Vertical field of view, the same as mapbox-gl uses
+const FIELD_OF_VIEW: f64 = 0.643_501_108_793_284_4;
+pub const MAX_PITCH: f64 = 60.0;
+pub const DEFAULT_MIN_ZOOM: f64 = 0.0;
+pub const DEFAULT_MAX_ZOOM: f64 = 22.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    center: LatLng,
+    zoom: f64,
+    bearing: f64, // Degrees clockwise from north, in (-180, 180]
+    pitch: f64,   // Degrees away from looking straight down
+    size: (u32, u32),
+    min_zoom: f64,
+    max_zoom: f64,
+    // The viewport has to stay inside
+    max_bounds: Option<LatLngBounds>,
+    projection: ProjectionType,
+}
+
+impl Transform {
+    pub fn new(size: (u32, u32)) -> Transform {
+        Transform {
+            center: LatLng::new(0.0, 0.0),
+            zoom: 0.0,
+            bearing: 0.0,
+            pitch: 0.0,
+            size,
+            min_zoom: DEFAULT_MIN_ZOOM,
+            max_zoom: DEFAULT_MAX_ZOOM,
+            max_bounds: None,
+            projection: ProjectionType::default(),
+        }
+    }
+
+    pub fn get_center(&self) -> LatLng {
+        self.center
+    }
+
+    // Latitude is clamped to what the projection can show, longitude wrapped
+    pub fn set_center(&mut self, center: LatLng) {
+        let max_latitude = self.projection.projection().max_latitude();
+        self.center =
+            LatLng::new(center.lat.clamp(-max_latitude, max_latitude), center.long).wrapped();
+        self.constrain();
+    }
+
+    pub fn get_zoom(&self) -> f64 {
+        self.zoom
+    }
+
+    pub fn set_zoom(&mut self, zoom: f64) {
+        self.zoom = zoom.clamp(self.min_zoom, self.max_zoom);
+        self.constrain();
+    }
+
+    pub fn get_min_zoom(&self) -> f64 {
+        self.min_zoom
+    }
+
+    pub fn get_max_zoom(&self) -> f64 {
+        self.max_zoom
+    }
+
+    // Expects min_zoom <= max_zoom, the zoom is pulled into the new range
+    pub fn set_zoom_range(&mut self, min_zoom: f64, max_zoom: f64) {
+        self.min_zoom = min_zoom.max(0.0);
+        self.max_zoom = max_zoom.max(self.min_zoom);
+        self.set_zoom(self.zoom);
+    }
+
+    pub fn get_projection(&self) -> ProjectionType {
+        self.projection
+    }
+
+    pub fn set_projection(&mut self, projection: ProjectionType) {
+        self.projection = projection;
+        self.set_center(self.center);
+    }
+
+    pub fn get_max_bounds(&self) -> Option<LatLngBounds> {
+        self.max_bounds
+    }
+
+    pub fn set_max_bounds(&mut self, max_bounds: Option<LatLngBounds>) {
+        self.max_bounds = max_bounds;
+        self.constrain();
+    }
+
+    pub fn get_bearing(&self) -> f64 {
+        self.bearing
+    }
+
+    pub fn set_bearing(&mut self, bearing: f64) {
+        let wrapped = (bearing + 180.0).rem_euclid(360.0) - 180.0;
+        self.bearing = if wrapped == -180.0 { 180.0 } else { wrapped };
+    }
+
+    pub fn get_pitch(&self) -> f64 {
+        self.pitch
+    }
+
+    pub fn set_pitch(&mut self, pitch: f64) {
+        self.pitch = pitch.clamp(0.0, MAX_PITCH);
+    }
+
+    pub fn get_size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    pub fn set_size(&mut self, size: (u32, u32)) {
+        self.size = size;
+        self.constrain();
+    }
+
+    // Zooms in until the max bounds fill the viewport, then moves the center
+    // until the viewport is inside them. Like mapbox-gl, this looks at the
+    // viewport as if the camera had no bearing nor pitch.
+    fn constrain(&mut self) {
+        let max_bounds = match self.max_bounds {
+            Some(max_bounds) => max_bounds,
+            None => return,
+        };
+        let (width, height) = (self.size.0 as f64, self.size.1 as f64);
+
+        let bounds_in_world = |transform: &Transform| {
+            let (min_x, min_y) = transform.project(&max_bounds.north_west());
+            let (max_x, max_y) = transform.project(&max_bounds.south_east());
+            (min_x, min_y, max_x, max_y)
+        };
+        let (min_x, min_y, max_x, max_y) = bounds_in_world(self);
+        let scale = (width / (max_x - min_x)).max(height / (max_y - min_y));
+        if scale > 1.0 {
+            self.zoom = (self.zoom + scale.log2()).min(self.max_zoom);
+        }
+
+        let (min_x, min_y, max_x, max_y) = bounds_in_world(self);
+        let clamp_axis = |value: f64, min: f64, max: f64, half: f64| {
+            if max - min <= half * 2.0 {
+                (min + max) / 2.0
+            } else {
+                value.clamp(min + half, max - half)
+            }
+        };
+        let (x, y) = self.center_world_point();
+        self.center = self.unproject((
+            clamp_axis(x, min_x, max_x, width / 2.0),
+            clamp_axis(y, min_y, max_y, height / 2.0),
+        ));
+    }
+
+    // Integer zoom level of the tiles covering the view
+    pub fn get_tile_zoom(&self) -> u32 {
+        self.zoom.floor() as u32
+    }
+
+    // Height of the world in world pixels, it is world_width times as wide
+    pub fn world_size(&self) -> f64 {
+        TILE_SIZE as f64 * 2f64.powf(self.zoom)
+    }
+
+    // Tiles across the world at zoom 0 in the map's projection
+    pub fn world_width(&self) -> u32 {
+        self.projection.projection().world_width()
+    }
+
+    // Size of a tile of zoom `z` in world pixels. Tiles of the tile zoom come
+    // out between TILE_SIZE and twice that.
+    pub fn tile_size_at(&self, z: u32) -> f64 {
+        self.world_size() / 2f64.powi(z as i32)
+    }
+
+    // World pixels of a place at the current zoom
+    pub fn project(&self, latlng: &LatLng) -> (f64, f64) {
+        Tranformation::project(self.projection.projection(), latlng, self.world_size())
+    }
+
+    pub fn unproject(&self, point: (f64, f64)) -> LatLng {
+        Tranformation::unproject(self.projection.projection(), point, self.world_size())
+    }
+
+    pub fn center_world_point(&self) -> (f64, f64) {
+        self.project(&self.center)
+    }
+
+    // Center at the tile zoom
+    pub fn center_tile_point(&self) -> TileCoordinate {
+        self.world_to_tile_coordinate(self.center_world_point(), self.get_tile_zoom())
+    }
+
+    fn world_to_tile_coordinate(&self, (x, y): (f64, f64), z: u32) -> TileCoordinate {
+        let tile_size = self.tile_size_at(z);
+        TileCoordinate::new(x / tile_size, y / tile_size, z)
+    }
+
+    fn camera_to_center_distance(&self) -> f64 {
+        0.5 / (FIELD_OF_VIEW / 2.0).tan() * self.size.1 as f64
+    }
+
+    // Perspective of the camera. Y is flipped so that world y, like screen y,
+    // grows downwards.
+    pub fn projection_matrix(&self) -> Mat4 {
+        let (width, height) = (self.size.0 as f64, self.size.1 as f64);
+        let pitch = self.pitch.to_radians();
+        let camera_to_center = self.camera_to_center_distance();
+
+        // Far enough for the top of the viewport to reach the ground
+        let ground_angle = std::f64::consts::FRAC_PI_2 + pitch;
+        let top_half_surface_distance = (FIELD_OF_VIEW / 2.0).sin() * camera_to_center
+            / (std::f64::consts::PI - ground_angle - FIELD_OF_VIEW / 2.0).sin();
+        let furthest_distance = pitch.sin() * top_half_surface_distance + camera_to_center;
+        let far = furthest_distance * 1.01;
+        let near = height / 50.0;
+
+        let projection = matrix::perspective(FIELD_OF_VIEW, width / height, near, far);
+        matrix::scale(&projection, (1.0, -1.0, 1.0))
+    }
+
+    // World pixels to camera space
+    pub fn view_matrix(&self) -> Mat4 {
+        let (x, y) = self.center_world_point();
+        let view = matrix::translate(
+            &matrix::identity(),
+            (0.0, 0.0, -self.camera_to_center_distance()),
+        );
+        let view = matrix::rotate_x(&view, self.pitch.to_radians());
+        let view = matrix::rotate_z(&view, -self.bearing.to_radians());
+        matrix::translate(&view, (-x, -y, 0.0))
+    }
+
+    // World pixels to clip space
+    pub fn proj_matrix(&self) -> Mat4 {
+        matrix::multiply(&self.projection_matrix(), &self.view_matrix())
+    }
+
+    // Normalized tile coords (0..1 across the tile) to world pixels, in the
+    // copy of the world the tile is in
+    pub fn tile_model_matrix(&self, tile_id: &UnwrappedTileID) -> Mat4 {
+        let tile_size = self.tile_size_at(tile_id.canonical.z);
+        let model = matrix::translate(
+            &matrix::identity(),
+            (
+                tile_id.unwrapped_x(self.world_width()) as f64 * tile_size,
+                tile_id.canonical.y as f64 * tile_size,
+                0.0,
+            ),
+        );
+        matrix::scale(&model, (tile_size, tile_size, 1.0))
+    }
+
+    // Normalized tile coords to clip space, what tile shaders draw with
+    pub fn tile_matrix(&self, tile_id: &UnwrappedTileID) -> Mat4 {
+        matrix::multiply(&self.proj_matrix(), &self.tile_model_matrix(tile_id))
+    }
+
+    // Screen pixels of a point given in normalized tile coords, in the f32
+    // the symbol quads are laid out with
+    pub fn tile_point_to_screen(&self, tile_id: &UnwrappedTileID, point: (f32, f32)) -> (f32, f32) {
+        let screen = self.tile_coordinate_to_screen(&TileCoordinate::new(
+            tile_id.unwrapped_x(self.world_width()) as f64 + point.0 as f64,
+            tile_id.canonical.y as f64 + point.1 as f64,
+            tile_id.canonical.z,
+        ));
+        (screen.x as f32, screen.y as f32)
+    }
+
+    pub fn tile_coordinate_to_screen(&self, coord: &TileCoordinate) -> ScreenCoordinate {
+        let tile_size = self.tile_size_at(coord.z);
+        self.world_to_screen((coord.x * tile_size, coord.y * tile_size))
+    }
+
+    pub fn world_to_screen(&self, (x, y): (f64, f64)) -> ScreenCoordinate {
+        let clip = matrix::transform_vec4(&self.proj_matrix(), &[x, y, 0.0, 1.0]);
+        let (ndc_x, ndc_y) = (clip[0] / clip[3], clip[1] / clip[3]);
+        ScreenCoordinate::new(
+            (ndc_x + 1.0) / 2.0 * self.size.0 as f64,
+            (1.0 - ndc_y) / 2.0 * self.size.1 as f64,
+        )
+    }
+
+    // World pixels on the ground under a screen pixel, by casting a ray from
+    // the near to the far plane. None if the ray misses the ground.
+    pub fn screen_to_world(&self, point: ScreenCoordinate) -> Option<(f64, f64)> {
+        let inverse = matrix::invert(&self.proj_matrix())?;
+        let ndc_x = point.x / self.size.0 as f64 * 2.0 - 1.0;
+        let ndc_y = 1.0 - point.y / self.size.1 as f64 * 2.0;
+
+        let unproject = |ndc_z: f64| {
+            let point = matrix::transform_vec4(&inverse, &[ndc_x, ndc_y, ndc_z, 1.0]);
+            (
+                point[0] / point[3],
+                point[1] / point[3],
+                point[2] / point[3],
+            )
+        };
+        let (near, far) = (unproject(-1.0), unproject(1.0));
+        if (far.2 - near.2).abs() < f64::EPSILON {
+            return None;
+        }
+        let t = -near.2 / (far.2 - near.2);
+        if !(0.0..=1.0).contains(&t) {
+            return None;
+        }
+        Some((near.0 + (far.0 - near.0) * t, near.1 + (far.1 - near.1) * t))
+    }
+
+    // Tiles of zoom `z` under a screen pixel
+    pub fn screen_to_tile_coordinate(
+        &self,
+        point: ScreenCoordinate,
+        z: u32,
+    ) -> Option<TileCoordinate> {
+        Some(self.world_to_tile_coordinate(self.screen_to_world(point)?, z))
+    }
+
+    // Screen pixel of a place on the ground
+    pub fn latlng_to_screen(&self, latlng: &LatLng) -> ScreenCoordinate {
+        self.world_to_screen(self.project(latlng))
+    }
+
+    // Place on the ground under a screen pixel
+    pub fn screen_to_latlng(&self, point: ScreenCoordinate) -> Option<LatLng> {
+        Some(self.unproject(self.screen_to_world(point)?))
+    }
+
+    // Center which puts `latlng` under the screen pixel `point`, keeping
+    // zoom, bearing and pitch
+    pub fn center_for_anchor(&self, latlng: &LatLng, point: ScreenCoordinate) -> Option<LatLng> {
+        let anchor = self.project(latlng);
+        let (x, y) = self.screen_to_world(point)?;
+        let (center_x, center_y) = self.center_world_point();
+        Some(self.unproject((center_x + anchor.0 - x, center_y + anchor.1 - y)))
+    }
+}
@@ -0,0 +1,141 @@
+// Ref: https://github.com/toji/gl-matrix/blob/master/src/mat4.js
+//
+// 4x4 matrices in column-major order, the layout GL expects. Math happens in
+// f64, matrices are only narrowed to f32 when handed to the shaders.
+
+pub type Mat4 = [f64; 16];
+pub type Vec4 = [f64; 4];
+
+pub fn identity() -> Mat4 {
+    let mut out = [0.0; 16];
+    out[0] = 1.0;
+    out[5] = 1.0;
+    out[10] = 1.0;
+    out[15] = 1.0;
+    out
+}
+
+// `fovy` in radians
+pub fn perspective(fovy: f64, aspect: f64, near: f64, far: f64) -> Mat4 {
+    let f = 1.0 / (fovy / 2.0).tan();
+    let nf = 1.0 / (near - far);
+    let mut out = [0.0; 16];
+    out[0] = f / aspect;
+    out[5] = f;
+    out[10] = (far + near) * nf;
+    out[11] = -1.0;
+    out[14] = 2.0 * far * near * nf;
+    out
+}
+
+// a * b, i.e. b is applied first
+pub fn multiply(a: &Mat4, b: &Mat4) -> Mat4 {
+    let mut out = [0.0; 16];
+    for col in 0..4 {
+        for row in 0..4 {
+            out[col * 4 + row] = (0..4).map(|k| a[k * 4 + row] * b[col * 4 + k]).sum();
+        }
+    }
+    out
+}
+
+pub fn translate(m: &Mat4, (x, y, z): (f64, f64, f64)) -> Mat4 {
+    let mut out = *m;
+    for row in 0..4 {
+        out[12 + row] = m[row] * x + m[4 + row] * y + m[8 + row] * z + m[12 + row];
+    }
+    out
+}
+
+pub fn scale(m: &Mat4, (x, y, z): (f64, f64, f64)) -> Mat4 {
+    let mut out = *m;
+    for row in 0..4 {
+        out[row] *= x;
+        out[4 + row] *= y;
+        out[8 + row] *= z;
+    }
+    out
+}
+
+// Rotations are in radians
+pub fn rotate_x(m: &Mat4, angle: f64) -> Mat4 {
+    let (s, c) = angle.sin_cos();
+    let mut out = *m;
+    for row in 0..4 {
+        let (y, z) = (m[4 + row], m[8 + row]);
+        out[4 + row] = y * c + z * s;
+        out[8 + row] = z * c - y * s;
+    }
+    out
+}
+
+pub fn rotate_z(m: &Mat4, angle: f64) -> Mat4 {
+    let (s, c) = angle.sin_cos();
+    let mut out = *m;
+    for row in 0..4 {
+        let (x, y) = (m[row], m[4 + row]);
+        out[row] = x * c + y * s;
+        out[4 + row] = y * c - x * s;
+    }
+    out
+}
+
+// None if the matrix is singular
+pub fn invert(m: &Mat4) -> Option<Mat4> {
+    let b00 = m[0] * m[5] - m[1] * m[4];
+    let b01 = m[0] * m[6] - m[2] * m[4];
+    let b02 = m[0] * m[7] - m[3] * m[4];
+    let b03 = m[1] * m[6] - m[2] * m[5];
+    let b04 = m[1] * m[7] - m[3] * m[5];
+    let b05 = m[2] * m[7] - m[3] * m[6];
+    let b06 = m[8] * m[13] - m[9] * m[12];
+    let b07 = m[8] * m[14] - m[10] * m[12];
+    let b08 = m[8] * m[15] - m[11] * m[12];
+    let b09 = m[9] * m[14] - m[10] * m[13];
+    let b10 = m[9] * m[15] - m[11] * m[13];
+    let b11 = m[10] * m[15] - m[11] * m[14];
+
+    let det = b00 * b11 - b01 * b10 + b02 * b09 + b03 * b08 - b04 * b07 + b05 * b06;
+    if det == 0.0 {
+        return None;
+    }
+    let det = 1.0 / det;
+
+    Some([
+        (m[5] * b11 - m[6] * b10 + m[7] * b09) * det,
+        (m[2] * b10 - m[1] * b11 - m[3] * b09) * det,
+        (m[13] * b05 - m[14] * b04 + m[15] * b03) * det,
+        (m[10] * b04 - m[9] * b05 - m[11] * b03) * det,
+        (m[6] * b08 - m[4] * b11 - m[7] * b07) * det,
+        (m[0] * b11 - m[2] * b08 + m[3] * b07) * det,
+        (m[14] * b02 - m[12] * b05 - m[15] * b01) * det,
+        (m[8] * b05 - m[10] * b02 + m[11] * b01) * det,
+        (m[4] * b10 - m[5] * b08 + m[7] * b06) * det,
+        (m[1] * b08 - m[0] * b10 - m[3] * b06) * det,
+        (m[12] * b04 - m[13] * b02 + m[15] * b00) * det,
+        (m[9] * b02 - m[8] * b04 - m[11] * b00) * det,
+        (m[5] * b07 - m[4] * b09 - m[6] * b06) * det,
+        (m[0] * b09 - m[1] * b07 + m[2] * b06) * det,
+        (m[13] * b01 - m[12] * b03 - m[14] * b00) * det,
+        (m[8] * b03 - m[9] * b01 + m[10] * b00) * det,
+    ])
+}
+
+pub fn transform_vec4(m: &Mat4, v: &Vec4) -> Vec4 {
+    let mut out = [0.0; 4];
+    for (row, value) in out.iter_mut().enumerate() {
+        *value = m[row] * v[0] + m[4 + row] * v[1] + m[8 + row] * v[2] + m[12 + row] * v[3];
+    }
+    out
+}
+
+// Columns as f32 arrays, for gfx constant buffers
+pub fn to_columns_f32(m: &Mat4) -> [[f32; 4]; 4] {
+    let mut out = [[0.0; 4]; 4];
+    for (col, column) in out.iter_mut().enumerate() {
+        for (row, value) in column.iter_mut().enumerate() {
+            *value = m[col * 4 + row] as f32;
+        }
+    }
+    out
+}
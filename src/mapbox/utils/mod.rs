@@ -1,2 +1,5 @@
+pub mod matrix;
 pub mod pbf;
+pub mod projection;
+pub mod tile_cover;
 pub mod transformation;
@@ -183,6 +183,14 @@ impl Pbf {
         unsafe { String::from_utf8_unchecked(chunk) }
     }
 
+    pub fn bytes(&mut self) -> Vec<u8> {
+        let bytes = self.varint32() as usize;
+        let chunk = self.data[self.inx..self.inx + bytes].to_vec();
+
+        self.skip_bytes(bytes as u32);
+        chunk
+    }
+
     pub fn message(&mut self) -> Self {
         let bytes = self.varint32() as usize;
         let chunk = (&self.data[self.inx..self.inx + bytes]).to_vec();
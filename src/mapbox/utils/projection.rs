@@ -0,0 +1,231 @@
+// Ref: https://github.com/mapbox/mapbox-gl-js/tree/main/src/geo/projection
+//
+// Projections map places onto the world the tiles are cut from, (0, 0) being
+// its top left and (world_width, 1) its bottom right corner. Tiles and camera
+// only ever see these world coords, so a map draws in whatever projection its
+// sources are tiled in.
+use super::super::geo::{LatLng, LatLngBounds};
+use super::super::vector_tile_id::VectorTileID;
+
+use std::f64::consts::PI;
+
+// Latitude at which the Mercator world turns square, the poles are unreachable
+pub const MAX_MERCATOR_LATITUDE: f64 = 85.051_128_779_806_59;
+
+pub trait Projection {
+    fn project(&self, latlng: &LatLng) -> (f64, f64);
+
+    fn unproject(&self, point: (f64, f64)) -> LatLng;
+
+    // Latitudes beyond this can't be shown
+    fn max_latitude(&self) -> f64;
+
+    // How much the projection stretches east-west distances at `lat`,
+    // compared to the equator
+    fn scale_at_latitude(&self, lat: f64) -> f64;
+
+    // Tiles across the world at zoom 0, it is always one tile high
+    fn world_width(&self) -> u32 {
+        1
+    }
+
+    fn tile_bounds(&self, tile_id: &VectorTileID) -> LatLngBounds {
+        let tiles = 2f64.powi(tile_id.z as i32);
+        let north_west = self.unproject((tile_id.x as f64 / tiles, tile_id.y as f64 / tiles));
+        let south_east = self.unproject((
+            (tile_id.x + 1) as f64 / tiles,
+            (tile_id.y + 1) as f64 / tiles,
+        ));
+        LatLngBounds::new(
+            LatLng::new(south_east.lat, north_west.long),
+            LatLng::new(north_west.lat, south_east.long),
+        )
+    }
+}
+
+// EPSG:3857, what Mapbox and most tile servers use
+pub struct WebMercator;
+
+impl Projection for WebMercator {
+    fn project(&self, latlng: &LatLng) -> (f64, f64) {
+        let lat = latlng
+            .lat
+            .clamp(-MAX_MERCATOR_LATITUDE, MAX_MERCATOR_LATITUDE);
+        let y = (PI / 4.0 + lat.to_radians() / 2.0).tan().ln();
+        ((180.0 + latlng.long) / 360.0, 0.5 - y / (2.0 * PI))
+    }
+
+    fn unproject(&self, (x, y): (f64, f64)) -> LatLng {
+        let lat = (2.0 * ((0.5 - y) * 2.0 * PI).exp().atan() - PI / 2.0).to_degrees();
+        LatLng::new(lat, x * 360.0 - 180.0)
+    }
+
+    fn max_latitude(&self) -> f64 {
+        MAX_MERCATOR_LATITUDE
+    }
+
+    fn scale_at_latitude(&self, lat: f64) -> f64 {
+        1.0 / lat.to_radians().cos()
+    }
+}
+
+// EPSG:4326 as WMTS and TMS servers tile it, degrees used as they are. Zoom 0
+// has two square tiles side by side, west and east of the prime meridian, so
+// a tile spans 180 / 2^z degrees either way.
+pub struct Equirectangular;
+
+impl Projection for Equirectangular {
+    fn project(&self, latlng: &LatLng) -> (f64, f64) {
+        let lat = latlng.lat.clamp(-90.0, 90.0);
+        ((180.0 + latlng.long) / 180.0, (90.0 - lat) / 180.0)
+    }
+
+    fn unproject(&self, (x, y): (f64, f64)) -> LatLng {
+        LatLng::new(90.0 - y * 180.0, x * 180.0 - 180.0)
+    }
+
+    fn max_latitude(&self) -> f64 {
+        90.0
+    }
+
+    fn scale_at_latitude(&self, lat: f64) -> f64 {
+        1.0 / lat.to_radians().cos()
+    }
+
+    fn world_width(&self) -> u32 {
+        2
+    }
+}
+
+// Projection a map draws in. Kept as a plain enum so that Transform stays Copy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProjectionType {
+    #[default]
+    WebMercator,
+    // EPSG:4326, see Equirectangular
+    Equirectangular,
+}
+
+impl ProjectionType {
+    pub fn projection(&self) -> &'static dyn Projection {
+        match self {
+            ProjectionType::WebMercator => &WebMercator,
+            ProjectionType::Equirectangular => &Equirectangular,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f64 = 1e-9;
+
+    fn assert_point(actual: (f64, f64), expected: (f64, f64)) {
+        assert!(
+            (actual.0 - expected.0).abs() < EPSILON && (actual.1 - expected.1).abs() < EPSILON,
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    fn assert_latlng(actual: LatLng, expected: LatLng) {
+        assert!(
+            (actual.lat - expected.lat).abs() < EPSILON
+                && (actual.long - expected.long).abs() < EPSILON,
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    // Places from pole to pole, or as far as the projection goes
+    fn places(projection: &dyn Projection) -> Vec<LatLng> {
+        let max = projection.max_latitude();
+        let mut places = vec![];
+        for lat in [-max, -60.0, -23.5, 0.0, 0.001, 45.0, 71.3, max].iter() {
+            for long in [-180.0, -122.42, -0.5, 0.0, 13.4, 179.999].iter() {
+                places.push(LatLng::new(*lat, *long));
+            }
+        }
+        places
+    }
+
+    fn assert_round_trips(projection: &dyn Projection) {
+        let width = projection.world_width() as f64;
+        for latlng in places(projection) {
+            let point = projection.project(&latlng);
+            assert!((-EPSILON..=width + EPSILON).contains(&point.0));
+            assert!((-EPSILON..=1.0 + EPSILON).contains(&point.1));
+            assert_latlng(projection.unproject(point), latlng);
+        }
+        for x in [0.0, 0.1, 0.5, 0.75, 1.0].iter() {
+            for y in [0.0, 0.2, 0.5, 0.9, 1.0].iter() {
+                let latlng = projection.unproject((*x * width, *y));
+                assert_point(projection.project(&latlng), (*x * width, *y));
+            }
+        }
+    }
+
+    #[test]
+    fn web_mercator_round_trips() {
+        assert_round_trips(&WebMercator);
+    }
+
+    #[test]
+    fn equirectangular_round_trips() {
+        assert_round_trips(&Equirectangular);
+    }
+
+    #[test]
+    fn web_mercator_world() {
+        let projection = WebMercator;
+        assert_point(projection.project(&LatLng::new(0.0, 0.0)), (0.5, 0.5));
+        assert_point(
+            projection.project(&LatLng::new(MAX_MERCATOR_LATITUDE, -180.0)),
+            (0.0, 0.0),
+        );
+        assert_point(
+            projection.project(&LatLng::new(-MAX_MERCATOR_LATITUDE, 180.0)),
+            (1.0, 1.0),
+        );
+        // The poles are clamped to the edge of the world
+        assert_point(projection.project(&LatLng::new(90.0, 0.0)), (0.5, 0.0));
+        assert!((projection.scale_at_latitude(60.0) - 2.0).abs() < EPSILON);
+
+        let bounds = projection.tile_bounds(&VectorTileID::new(0, 0, 1));
+        assert_latlng(bounds.south_west, LatLng::new(0.0, -180.0));
+        assert_latlng(bounds.north_east, LatLng::new(MAX_MERCATOR_LATITUDE, 0.0));
+    }
+
+    #[test]
+    fn equirectangular_world() {
+        let projection = Equirectangular;
+        assert_point(projection.project(&LatLng::new(0.0, 0.0)), (1.0, 0.5));
+        assert_point(projection.project(&LatLng::new(90.0, -180.0)), (0.0, 0.0));
+        assert_point(projection.project(&LatLng::new(-90.0, 180.0)), (2.0, 1.0));
+        assert_point(projection.project(&LatLng::new(45.0, 90.0)), (1.5, 0.25));
+        assert_point(projection.project(&LatLng::new(100.0, 0.0)), (1.0, 0.0));
+        assert_eq!(projection.world_width(), 2);
+        assert_eq!(WebMercator.world_width(), 1);
+    }
+
+    #[test]
+    fn equirectangular_tiles() {
+        let projection = Equirectangular;
+
+        // Two tiles at zoom 0, west and east of the prime meridian
+        let bounds = projection.tile_bounds(&VectorTileID::new(0, 0, 0));
+        assert_latlng(bounds.south_west, LatLng::new(-90.0, -180.0));
+        assert_latlng(bounds.north_east, LatLng::new(90.0, 0.0));
+        let bounds = projection.tile_bounds(&VectorTileID::new(1, 0, 0));
+        assert_latlng(bounds.south_west, LatLng::new(-90.0, 0.0));
+        assert_latlng(bounds.north_east, LatLng::new(90.0, 180.0));
+
+        // Square in degrees at every zoom
+        let bounds = projection.tile_bounds(&VectorTileID::new(5, 2, 2));
+        assert_latlng(bounds.south_west, LatLng::new(-45.0, 45.0));
+        assert_latlng(bounds.north_east, LatLng::new(0.0, 90.0));
+    }
+}
@@ -0,0 +1,256 @@
+// Ref: https://github.com/mapbox/mapbox-gl-native/blob/master/src/mbgl/util/tile_cover.cpp
+//
+// Tiles under the footprint the viewport casts on the ground. With bearing
+// and pitch that footprint is some convex quad, which is scanned one row of
+// tiles at a time. x is left unwrapped so that copies of the world left and
+// right of the antimeridian are covered too.
+
+use super::super::geo::TileCoordinate;
+use super::super::vector_tile_id::UnwrappedTileID;
+
+// `quad` are the corners of the footprint in order, `center` is where the
+// camera looks at, both at the zoom of the tiles wanted. `world_width` is the
+// projection's tiles across at zoom 0, a copy of the world is that many times
+// 2^z tiles wide. Tiles come out closest to the center first.
+pub fn tile_cover(
+    quad: &[TileCoordinate; 4],
+    center: &TileCoordinate,
+    world_width: u32,
+) -> Vec<UnwrappedTileID> {
+    let z = center.z;
+    let tiles = 2f64.powi(z as i32);
+
+    // There is nothing above the top or below the bottom of the world
+    let top = quad.iter().map(|c| c.y).fold(f64::MAX, f64::min).max(0.0);
+    let bottom = quad.iter().map(|c| c.y).fold(f64::MIN, f64::max).min(tiles);
+
+    let mut covered_tiles = vec![];
+    if top >= bottom {
+        return covered_tiles;
+    }
+    for y in (top.floor() as u32)..(bottom.ceil() as u32) {
+        if let Some((left, right)) = span(quad, y as f64, y as f64 + 1.0) {
+            for x in (left.floor() as i64)..(right.ceil() as i64) {
+                covered_tiles.push(UnwrappedTileID::new(x, y, z, world_width));
+            }
+        }
+    }
+
+    let distance = |tile_id: &UnwrappedTileID| {
+        (tile_id.unwrapped_x(world_width) as f64 + 0.5 - center.x)
+            .hypot(tile_id.canonical.y as f64 + 0.5 - center.y)
+    };
+    covered_tiles.sort_by(|a, b| distance(a).total_cmp(&distance(b)));
+    covered_tiles
+}
+
+// Left and right end of the part of the quad between rows `top` and
+// `bottom`. For a convex quad that's the extremes of the corners inside the
+// rows and the points where the edges cross them.
+fn span(quad: &[TileCoordinate; 4], top: f64, bottom: f64) -> Option<(f64, f64)> {
+    let mut xs = vec![];
+    for (i, a) in quad.iter().enumerate() {
+        let b = &quad[(i + 1) % quad.len()];
+        if (top..=bottom).contains(&a.y) {
+            xs.push(a.x);
+        }
+        for row in [top, bottom].iter() {
+            if (a.y - row) * (b.y - row) < 0.0 {
+                xs.push(a.x + (row - a.y) / (b.y - a.y) * (b.x - a.x));
+            }
+        }
+    }
+    let left = xs.iter().cloned().fold(f64::MAX, f64::min);
+    let right = xs.iter().cloned().fold(f64::MIN, f64::max);
+    if left < right {
+        Some((left, right))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::vector_tile_id::VectorTileID;
+    use super::*;
+
+    fn quad(left: f64, top: f64, right: f64, bottom: f64, z: u32) -> [TileCoordinate; 4] {
+        [
+            TileCoordinate::new(left, top, z),
+            TileCoordinate::new(right, top, z),
+            TileCoordinate::new(right, bottom, z),
+            TileCoordinate::new(left, bottom, z),
+        ]
+    }
+
+    fn corners(corners: &[(f64, f64)], z: u32) -> [TileCoordinate; 4] {
+        let corner = |inx: usize| TileCoordinate::new(corners[inx].0, corners[inx].1, z);
+        [corner(0), corner(1), corner(2), corner(3)]
+    }
+
+    // Unwrapped x of the covered tiles, row by row
+    fn rows(tile_ids: &[UnwrappedTileID]) -> Vec<(u32, Vec<i64>)> {
+        let mut rows: Vec<(u32, Vec<i64>)> = vec![];
+        let mut tile_ids = tile_ids.to_vec();
+        tile_ids.sort_by_key(|tile_id| (tile_id.canonical.y, tile_id.unwrapped_x(1)));
+        for tile_id in tile_ids {
+            let x = tile_id.unwrapped_x(1);
+            match rows.last_mut() {
+                Some((y, xs)) if *y == tile_id.canonical.y => xs.push(x),
+                _ => rows.push((tile_id.canonical.y, vec![x])),
+            }
+        }
+        rows
+    }
+
+    #[test]
+    fn covers_the_tiles_under_a_box() {
+        let tile_ids = tile_cover(
+            &quad(1.5, 1.5, 3.5, 2.5, 3),
+            &TileCoordinate::new(2.5, 2.0, 3),
+            1,
+        );
+        assert_eq!(
+            rows(&tile_ids),
+            vec![(1, vec![1, 2, 3]), (2, vec![1, 2, 3])]
+        );
+        // Edges on tile borders don't reach into the next tile
+        let tile_ids = tile_cover(
+            &quad(1.0, 1.0, 3.0, 2.0, 3),
+            &TileCoordinate::new(2.0, 1.5, 3),
+            1,
+        );
+        assert_eq!(rows(&tile_ids), vec![(1, vec![1, 2])]);
+    }
+
+    #[test]
+    fn covers_a_rotated_footprint() {
+        // A square turned by 45 degrees leaves out the corners of its
+        // bounding box
+        let diamond = corners(&[(4.0, 2.0), (6.0, 4.0), (4.0, 6.0), (2.0, 4.0)], 3);
+        let tile_ids = tile_cover(&diamond, &TileCoordinate::new(4.0, 4.0, 3), 1);
+        assert_eq!(
+            rows(&tile_ids),
+            vec![
+                (2, vec![3, 4]),
+                (3, vec![2, 3, 4, 5]),
+                (4, vec![2, 3, 4, 5]),
+                (5, vec![3, 4]),
+            ]
+        );
+    }
+
+    #[test]
+    fn covers_a_pitched_footprint() {
+        // Far away at the top the footprint is wider than close by
+        let trapezoid = corners(&[(1.0, 1.0), (7.0, 1.0), (5.0, 4.0), (3.0, 4.0)], 3);
+        let tile_ids = tile_cover(&trapezoid, &TileCoordinate::new(4.0, 3.0, 3), 1);
+        assert_eq!(
+            rows(&tile_ids),
+            vec![
+                (1, vec![1, 2, 3, 4, 5, 6]),
+                (2, vec![1, 2, 3, 4, 5, 6]),
+                (3, vec![2, 3, 4, 5]),
+            ]
+        );
+    }
+
+    #[test]
+    fn covers_copies_across_the_antimeridian() {
+        let tile_ids = tile_cover(
+            &quad(-1.5, 0.2, 0.5, 0.8, 1),
+            &TileCoordinate::new(-0.5, 0.5, 1),
+            1,
+        );
+        assert_eq!(rows(&tile_ids), vec![(0, vec![-2, -1, 0])]);
+        // The copy on the left, then the world itself
+        assert_eq!(
+            tile_ids,
+            vec![
+                UnwrappedTileID {
+                    wrap: -1,
+                    canonical: VectorTileID::new(1, 0, 1)
+                },
+                UnwrappedTileID {
+                    wrap: -1,
+                    canonical: VectorTileID::new(0, 0, 1)
+                },
+                UnwrappedTileID {
+                    wrap: 0,
+                    canonical: VectorTileID::new(0, 0, 1)
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn clips_rows_to_the_world() {
+        // Pitched towards a pole, the footprint reaches past the world
+        let tile_ids = tile_cover(
+            &quad(0.5, -3.0, 1.5, 5.0, 1),
+            &TileCoordinate::new(1.0, 1.0, 1),
+            1,
+        );
+        assert_eq!(rows(&tile_ids), vec![(0, vec![0, 1]), (1, vec![0, 1])]);
+
+        let above = tile_cover(
+            &quad(0.0, -3.0, 1.0, -1.0, 1),
+            &TileCoordinate::new(0.5, -2.0, 1),
+            1,
+        );
+        assert!(above.is_empty());
+        let below = tile_cover(
+            &quad(0.0, 2.0, 1.0, 3.0, 1),
+            &TileCoordinate::new(0.5, 2.5, 1),
+            1,
+        );
+        assert!(below.is_empty());
+    }
+
+    #[test]
+    fn orders_tiles_from_the_center() {
+        let center = TileCoordinate::new(5.2, 2.7, 3);
+        let tile_ids = tile_cover(&quad(0.5, 0.5, 7.5, 7.5, 3), &center, 1);
+        assert_eq!(tile_ids.len(), 64);
+        assert_eq!(tile_ids[0].canonical, VectorTileID::new(5, 2, 3));
+        let distances: Vec<f64> = tile_ids
+            .iter()
+            .map(|tile_id| {
+                (tile_id.unwrapped_x(1) as f64 + 0.5 - center.x)
+                    .hypot(tile_id.canonical.y as f64 + 0.5 - center.y)
+            })
+            .collect();
+        assert!(distances.windows(2).all(|pair| pair[0] <= pair[1]));
+
+        // A center gone bad doesn't bring the cover down
+        let center = TileCoordinate::new(f64::NAN, 2.7, 3);
+        let tile_ids = tile_cover(&quad(0.5, 0.5, 7.5, 7.5, 3), &center, 1);
+        assert_eq!(tile_ids.len(), 64);
+    }
+
+    #[test]
+    fn covers_a_world_two_tiles_wide() {
+        let center = TileCoordinate::new(1.0, 0.5, 0);
+        let tile_ids = tile_cover(&quad(0.2, 0.1, 1.8, 0.9, 0), &center, 2);
+        let mut canonical: Vec<_> = tile_ids.iter().map(|tile_id| tile_id.canonical).collect();
+        canonical.sort_by_key(|tile_id| tile_id.x);
+        assert_eq!(
+            canonical,
+            vec![VectorTileID::new(0, 0, 0), VectorTileID::new(1, 0, 0)]
+        );
+        assert!(tile_ids.iter().all(|tile_id| tile_id.wrap == 0));
+
+        // Past the east edge comes the western tile of the next copy
+        let center = TileCoordinate::new(3.5, 1.5, 1);
+        let tile_ids = tile_cover(&quad(3.2, 1.2, 4.8, 1.8, 1), &center, 2);
+        assert_eq!(
+            tile_ids,
+            vec![
+                UnwrappedTileID::new(3, 1, 1, 2),
+                UnwrappedTileID::new(4, 1, 1, 2)
+            ]
+        );
+        assert_eq!(tile_ids[1].wrap, 1);
+        assert_eq!(tile_ids[1].canonical, VectorTileID::new(0, 1, 1));
+    }
+}
@@ -1,28 +1,27 @@
-// TODO: use trait for projectors
+// Conversions between places and the pixels of the world at some zoom, in
+// the projection of the map
 
-use super::super::config;
-use std::f64::consts;
+use super::super::geo::{LatLng, TileCoordinate};
+use super::projection::Projection;
 
 pub struct Tranformation {}
 
 impl Tranformation {
-    pub fn latlong_to_tile_coord(lat: f32, long: f32, zoom: u32) -> (f32, f32) {
-        let k = (2 as u32).pow(zoom) as f32 / (config::TILE_SIZE as f32);
-        (
-            Tranformation::long_x(long) * k,
-            Tranformation::lat_y(lat) * k,
-        )
+    pub fn latlong_to_tile_coord(
+        projection: &dyn Projection,
+        latlng: &LatLng,
+        zoom: u32,
+    ) -> TileCoordinate {
+        TileCoordinate::from_latlng(projection, latlng, zoom)
     }
 
-    fn long_x(lng: f32) -> f32 {
-        (180.0 + lng) * (config::TILE_SIZE as f32) / 360.0
+    // Pixels of a world `world_size` pixels across, i.e. TILE_SIZE * 2^zoom
+    pub fn project(projection: &dyn Projection, latlng: &LatLng, world_size: f64) -> (f64, f64) {
+        let (x, y) = projection.project(latlng);
+        (x * world_size, y * world_size)
     }
 
-    fn lat_y(lat: f32) -> f32 {
-        let y_ = 180.0 / consts::PI
-            * (consts::PI / 4.0 + (lat as f64) * consts::PI / 360.0)
-                .tan()
-                .log(consts::E);
-        ((180.0 - y_) * (config::TILE_SIZE as f64) / 360.0) as f32
+    pub fn unproject(projection: &dyn Projection, (x, y): (f64, f64), world_size: f64) -> LatLng {
+        projection.unproject((x / world_size, y / world_size))
     }
 }
@@ -1,3 +1,13 @@
+// Ref: https://github.com/mapbox/mapbox-gl-native/blob/master/include/mbgl/tile/tile_id.hpp
+//
+// VectorTileID is the canonical ID, the z/x/y tiles are requested with.
+// UnwrappedTileID adds the world copy a tile is drawn in, OverscaledTileID the
+// zoom a tile is drawn at when it is stretched beyond the zooms of its source.
+use super::geo::{LatLngBounds, ProjectedMeters, TileCoordinate, EARTH_RADIUS};
+use super::utils::projection::Projection;
+
+use std::f64::consts::PI;
+
 #[derive(PartialEq, Eq, Hash, Debug, Copy)]
 pub struct VectorTileID {
     pub x: u32,
@@ -10,3 +20,435 @@ impl Clone for VectorTileID {
         *self
     }
 }
+
+impl VectorTileID {
+    pub fn new(x: u32, y: u32, z: u32) -> VectorTileID {
+        VectorTileID { x, y, z }
+    }
+
+    // Tile at zoom `z` this one is part of, itself for its own zoom. Only
+    // scales down, `z` above the tile's zoom is taken as its zoom.
+    pub fn scaled_to(&self, z: u32) -> VectorTileID {
+        let shift = self.z.saturating_sub(z);
+        VectorTileID::new(self.x >> shift, self.y >> shift, self.z - shift)
+    }
+
+    pub fn parent(&self) -> Option<VectorTileID> {
+        if self.z == 0 {
+            return None;
+        }
+        Some(self.scaled_to(self.z - 1))
+    }
+
+    // In the order top left, top right, bottom left, bottom right
+    pub fn children(&self) -> [VectorTileID; 4] {
+        let (x, y, z) = (self.x * 2, self.y * 2, self.z + 1);
+        [
+            VectorTileID::new(x, y, z),
+            VectorTileID::new(x + 1, y, z),
+            VectorTileID::new(x, y + 1, z),
+            VectorTileID::new(x + 1, y + 1, z),
+        ]
+    }
+
+    // The other children of the parent, none at zoom 0
+    pub fn siblings(&self) -> Vec<VectorTileID> {
+        match self.parent() {
+            Some(parent) => parent
+                .children()
+                .iter()
+                .filter(|sibling| *sibling != self)
+                .copied()
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    // Whether this tile lies inside `parent`, at any zoom above it
+    pub fn is_child_of(&self, parent: &VectorTileID) -> bool {
+        self.z > parent.z && self.scaled_to(parent.z) == *parent
+    }
+
+    // Ref: https://docs.microsoft.com/en-us/bingmaps/articles/bing-maps-tile-system
+    pub fn quadkey(&self) -> String {
+        (1..=self.z)
+            .rev()
+            .map(|level| {
+                let mask = 1 << (level - 1);
+                let digit = (self.x & mask != 0) as u8 + 2 * (self.y & mask != 0) as u8;
+                (b'0' + digit) as char
+            })
+            .collect()
+    }
+
+    // None if the quadkey has anything but the digits 0 to 3
+    pub fn from_quadkey(quadkey: &str) -> Option<VectorTileID> {
+        let mut tile_id = VectorTileID::new(0, 0, 0);
+        for digit in quadkey.chars() {
+            let digit = digit.to_digit(4)?;
+            tile_id = tile_id.children()[digit as usize];
+        }
+        Some(tile_id)
+    }
+
+    pub fn bounds(&self, projection: &dyn Projection) -> LatLngBounds {
+        projection.tile_bounds(self)
+    }
+
+    // (south west, north east) in EPSG:3857 meters
+    pub fn projected_bounds(&self) -> (ProjectedMeters, ProjectedMeters) {
+        let circumference = 2.0 * PI * EARTH_RADIUS;
+        let tile_size = circumference / 2f64.powi(self.z as i32);
+        let west = self.x as f64 * tile_size - circumference / 2.0;
+        let north = circumference / 2.0 - self.y as f64 * tile_size;
+        (
+            ProjectedMeters::new(north - tile_size, west),
+            ProjectedMeters::new(north, west + tile_size),
+        )
+    }
+
+    // Distance from the middle of the tile to `coord`, in tiles of this
+    // tile's zoom. Tiles are best loaded closest to the viewport center first.
+    pub fn distance_to(&self, coord: &TileCoordinate) -> f64 {
+        let scale = 2f64.powi(self.z as i32 - coord.z as i32);
+        let (x, y) = (coord.x * scale, coord.y * scale);
+        (self.x as f64 + 0.5 - x).hypot(self.y as f64 + 0.5 - y)
+    }
+}
+
+// A canonical tile in one of the copies of the world side by side, `wrap`
+// being the copy, 0 for the one between -180 and 180 degrees
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub struct UnwrappedTileID {
+    pub wrap: i32,
+    pub canonical: VectorTileID,
+}
+
+impl UnwrappedTileID {
+    // `x` may lie outside of the world, e.g. -1 is the last tile of the copy
+    // on the left. `world_width` is the projection's tiles across at zoom 0.
+    pub fn new(x: i64, y: u32, z: u32, world_width: u32) -> UnwrappedTileID {
+        let tiles = (world_width as i64) << z;
+        UnwrappedTileID {
+            wrap: x.div_euclid(tiles) as i32,
+            canonical: VectorTileID::new(x.rem_euclid(tiles) as u32, y, z),
+        }
+    }
+
+    // x counted from the left edge of world copy 0
+    pub fn unwrapped_x(&self, world_width: u32) -> i64 {
+        self.wrap as i64 * ((world_width as i64) << self.canonical.z) + self.canonical.x as i64
+    }
+
+    pub fn parent(&self) -> Option<UnwrappedTileID> {
+        Some(UnwrappedTileID {
+            wrap: self.wrap,
+            canonical: self.canonical.parent()?,
+        })
+    }
+
+    pub fn children(&self) -> [UnwrappedTileID; 4] {
+        let wrap = self.wrap;
+        let children = self.canonical.children();
+        [
+            UnwrappedTileID {
+                wrap,
+                canonical: children[0],
+            },
+            UnwrappedTileID {
+                wrap,
+                canonical: children[1],
+            },
+            UnwrappedTileID {
+                wrap,
+                canonical: children[2],
+            },
+            UnwrappedTileID {
+                wrap,
+                canonical: children[3],
+            },
+        ]
+    }
+
+    pub fn is_child_of(&self, parent: &UnwrappedTileID) -> bool {
+        self.wrap == parent.wrap && self.canonical.is_child_of(&parent.canonical)
+    }
+
+    // Drawn at `overscaled_z`, not below the canonical zoom
+    pub fn overscale_to(&self, overscaled_z: u32) -> OverscaledTileID {
+        OverscaledTileID::new(overscaled_z, self.wrap, self.canonical)
+    }
+}
+
+// A canonical tile drawn at `overscaled_z`, e.g. tile 14/x/y stretched over
+// the area of a 16/x'/y' tile when the source has no tiles beyond 14
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub struct OverscaledTileID {
+    pub overscaled_z: u32,
+    pub wrap: i32,
+    pub canonical: VectorTileID,
+}
+
+impl OverscaledTileID {
+    // `overscaled_z` below the canonical zoom is raised to it
+    pub fn new(overscaled_z: u32, wrap: i32, canonical: VectorTileID) -> OverscaledTileID {
+        OverscaledTileID {
+            overscaled_z: overscaled_z.max(canonical.z),
+            wrap,
+            canonical,
+        }
+    }
+
+    // How many times the tile is stretched across, 1 if it isn't
+    pub fn overscale_factor(&self) -> u32 {
+        1 << (self.overscaled_z - self.canonical.z)
+    }
+
+    pub fn is_overscaled(&self) -> bool {
+        self.overscaled_z > self.canonical.z
+    }
+
+    // The same area at a lower zoom, drawn from a lower canonical tile once
+    // `z` is below the canonical zoom
+    pub fn scaled_to(&self, z: u32) -> OverscaledTileID {
+        OverscaledTileID::new(z, self.wrap, self.canonical.scaled_to(z))
+    }
+
+    pub fn parent(&self) -> Option<OverscaledTileID> {
+        if self.overscaled_z == 0 {
+            return None;
+        }
+        Some(self.scaled_to(self.overscaled_z - 1))
+    }
+
+    // Children of an overscaled tile are the same canonical tile, stretched
+    // further, up to `max_z`, the highest zoom of the source
+    pub fn children(&self, max_z: u32) -> Vec<OverscaledTileID> {
+        if self.canonical.z >= max_z {
+            return vec![OverscaledTileID::new(
+                self.overscaled_z + 1,
+                self.wrap,
+                self.canonical,
+            )];
+        }
+        self.canonical
+            .children()
+            .iter()
+            .map(|child| OverscaledTileID::new(self.overscaled_z + 1, self.wrap, *child))
+            .collect()
+    }
+
+    pub fn is_child_of(&self, parent: &OverscaledTileID) -> bool {
+        self.wrap == parent.wrap
+            && self.overscaled_z > parent.overscaled_z
+            && self.canonical.scaled_to(parent.canonical.z) == parent.canonical
+    }
+
+    pub fn to_unwrapped(&self) -> UnwrappedTileID {
+        UnwrappedTileID {
+            wrap: self.wrap,
+            canonical: self.canonical,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quadkey_round_trips() {
+        // Ref: the example of the Bing Maps tile system article
+        assert_eq!(VectorTileID::new(3, 5, 3).quadkey(), "213");
+        assert_eq!(VectorTileID::new(0, 0, 0).quadkey(), "");
+
+        for z in 0..8 {
+            let tiles = 1 << z;
+            for (x, y) in [
+                (0, 0),
+                (tiles - 1, 0),
+                (tiles / 2, tiles - 1),
+                (tiles / 3, tiles / 5),
+            ]
+            .iter()
+            {
+                let tile_id = VectorTileID::new(*x, *y, z);
+                let quadkey = tile_id.quadkey();
+                assert_eq!(quadkey.len(), z as usize);
+                assert_eq!(VectorTileID::from_quadkey(&quadkey), Some(tile_id));
+            }
+        }
+        assert_eq!(VectorTileID::from_quadkey("0124"), None);
+        assert_eq!(VectorTileID::from_quadkey("a"), None);
+    }
+
+    #[test]
+    fn parent_and_children() {
+        let tile_id = VectorTileID::new(5, 6, 3);
+        assert_eq!(tile_id.parent(), Some(VectorTileID::new(2, 3, 2)));
+        assert_eq!(VectorTileID::new(0, 0, 0).parent(), None);
+        for child in tile_id.children().iter() {
+            assert_eq!(child.parent(), Some(tile_id));
+            assert!(child.is_child_of(&tile_id));
+            assert!(child.is_child_of(&VectorTileID::new(0, 0, 0)));
+        }
+        assert!(!tile_id.is_child_of(&tile_id));
+        assert!(!VectorTileID::new(1, 6, 3).is_child_of(&VectorTileID::new(1, 1, 1)));
+
+        let siblings = tile_id.siblings();
+        assert_eq!(siblings.len(), 3);
+        assert!(!siblings.contains(&tile_id));
+        assert!(VectorTileID::new(0, 0, 0).siblings().is_empty());
+
+        assert_eq!(tile_id.scaled_to(1), VectorTileID::new(1, 1, 1));
+        assert_eq!(tile_id.scaled_to(5), tile_id);
+    }
+
+    #[test]
+    fn unwrapped_ids_wrap_around_the_world() {
+        let tile_id = UnwrappedTileID::new(-1, 2, 2, 1);
+        assert_eq!(tile_id.wrap, -1);
+        assert_eq!(tile_id.canonical, VectorTileID::new(3, 2, 2));
+        assert_eq!(tile_id.unwrapped_x(1), -1);
+
+        let tile_id = UnwrappedTileID::new(9, 0, 2, 1);
+        assert_eq!((tile_id.wrap, tile_id.canonical.x), (2, 1));
+        assert_eq!(tile_id.unwrapped_x(1), 9);
+
+        for x in -9..9 {
+            assert_eq!(UnwrappedTileID::new(x, 1, 3, 1).unwrapped_x(1), x);
+        }
+        // Zoom 0 has a single tile, every x is a copy of it
+        let tile_id = UnwrappedTileID::new(-3, 0, 0, 1);
+        assert_eq!((tile_id.wrap, tile_id.canonical.x), (-3, 0));
+
+        // A world two tiles wide, as EPSG:4326 is, wraps every 2^(z + 1) tiles
+        let tile_id = UnwrappedTileID::new(-1, 0, 0, 2);
+        assert_eq!((tile_id.wrap, tile_id.canonical.x), (-1, 1));
+        let tile_id = UnwrappedTileID::new(9, 1, 2, 2);
+        assert_eq!((tile_id.wrap, tile_id.canonical.x), (1, 1));
+        for x in -17..17 {
+            assert_eq!(UnwrappedTileID::new(x, 1, 2, 2).unwrapped_x(2), x);
+        }
+    }
+
+    #[test]
+    fn unwrapped_ids_stay_in_their_copy() {
+        let tile_id = UnwrappedTileID::new(-3, 1, 2, 1);
+        let parent = tile_id.parent().unwrap();
+        assert_eq!(parent.wrap, -1);
+        assert_eq!(parent.canonical, VectorTileID::new(0, 0, 1));
+        for child in tile_id.children().iter() {
+            assert_eq!(child.wrap, -1);
+            assert!(child.is_child_of(&tile_id));
+        }
+        // The same tile in another copy is no relation
+        let other_copy = UnwrappedTileID {
+            wrap: 0,
+            canonical: parent.canonical,
+        };
+        assert!(!tile_id.is_child_of(&other_copy));
+    }
+
+    #[test]
+    fn overscaled_parents_and_children() {
+        let canonical = VectorTileID::new(3, 2, 2);
+        let tile_id = OverscaledTileID::new(4, 1, canonical);
+        assert!(tile_id.is_overscaled());
+        assert_eq!(tile_id.overscale_factor(), 4);
+        // Raised to the canonical zoom
+        assert_eq!(OverscaledTileID::new(1, 0, canonical).overscaled_z, 2);
+
+        // Overscaled parents keep the canonical tile until they reach its zoom
+        let parent = tile_id.parent().unwrap();
+        assert_eq!(parent, OverscaledTileID::new(3, 1, canonical));
+        let grandparent = parent.parent().unwrap();
+        assert_eq!(grandparent, OverscaledTileID::new(2, 1, canonical));
+        assert!(!grandparent.is_overscaled());
+        assert_eq!(
+            grandparent.parent(),
+            Some(OverscaledTileID::new(1, 1, VectorTileID::new(1, 1, 1)))
+        );
+        assert!(tile_id.is_child_of(&parent));
+        assert!(tile_id.is_child_of(&grandparent.parent().unwrap()));
+        assert!(!parent.is_child_of(&tile_id));
+        assert!(!tile_id.is_child_of(&OverscaledTileID::new(3, 0, canonical)));
+
+        // Beyond the source's max zoom there is a single, stretched child
+        assert_eq!(
+            tile_id.children(2),
+            vec![OverscaledTileID::new(5, 1, canonical)]
+        );
+        let children = grandparent.children(14);
+        assert_eq!(children.len(), 4);
+        for child in children.iter() {
+            assert_eq!(child.overscaled_z, 3);
+            assert!(!child.is_overscaled());
+            assert!(child.is_child_of(&grandparent));
+            assert_eq!(child.parent(), Some(grandparent));
+        }
+
+        assert_eq!(tile_id.to_unwrapped(), UnwrappedTileID::new(7, 2, 2, 1));
+        assert_eq!(UnwrappedTileID::new(7, 2, 2, 1).overscale_to(4), tile_id);
+        assert_eq!(
+            OverscaledTileID::new(0, 0, VectorTileID::new(0, 0, 0)).parent(),
+            None
+        );
+    }
+
+    #[test]
+    fn distance_orders_tiles_from_center() {
+        let center = TileCoordinate::new(2.5, 1.5, 2);
+        assert_eq!(VectorTileID::new(2, 1, 2).distance_to(&center), 0.0);
+        assert_eq!(VectorTileID::new(0, 1, 2).distance_to(&center), 2.0);
+
+        let mut tile_ids = vec![
+            VectorTileID::new(0, 3, 2),
+            VectorTileID::new(3, 3, 2),
+            VectorTileID::new(2, 1, 2),
+            VectorTileID::new(3, 1, 2),
+            VectorTileID::new(2, 3, 2),
+        ];
+        tile_ids.sort_by(|a, b| {
+            a.distance_to(&center)
+                .partial_cmp(&b.distance_to(&center))
+                .unwrap()
+        });
+        assert_eq!(
+            tile_ids,
+            vec![
+                VectorTileID::new(2, 1, 2),
+                VectorTileID::new(3, 1, 2),
+                VectorTileID::new(2, 3, 2),
+                VectorTileID::new(3, 3, 2),
+                VectorTileID::new(0, 3, 2),
+            ]
+        );
+
+        // Measured in tiles of the tile's own zoom
+        let center = TileCoordinate::new(0.5, 0.5, 0);
+        assert_eq!(
+            VectorTileID::new(1, 1, 1).distance_to(&center),
+            0.5f64.hypot(0.5)
+        );
+        assert_eq!(
+            VectorTileID::new(2, 2, 2).distance_to(&center),
+            0.5f64.hypot(0.5)
+        );
+    }
+
+    #[test]
+    fn projected_bounds_split_the_world() {
+        let (south_west, north_east) = VectorTileID::new(0, 0, 0).projected_bounds();
+        let half = PI * EARTH_RADIUS;
+        assert!((south_west.easting + half).abs() < 1e-6);
+        assert!((south_west.northing + half).abs() < 1e-6);
+        assert!((north_east.easting - half).abs() < 1e-6);
+        assert!((north_east.northing - half).abs() < 1e-6);
+
+        let (south_west, north_east) = VectorTileID::new(1, 0, 1).projected_bounds();
+        assert!(south_west.easting.abs() < 1e-6 && south_west.northing.abs() < 1e-6);
+        assert!((north_east.easting - half).abs() < 1e-6);
+        assert!((north_east.northing - half).abs() < 1e-6);
+    }
+}
@@ -1,23 +1,24 @@
 use super::common::map_error::MapError;
 use super::common::task_responder::TaskResponder;
 use super::common::types::{Threadable, ThreadableNew};
-use super::config;
-use super::config::TILE_SIZE;
+use super::geo::ScreenCoordinate;
 use super::io::resource::Resource;
+use super::render_tile::{render_tiles, RenderTile};
+use super::tile_cache::{TileCache, TileCacheLimits, TileCacheMetrics};
+use super::transform::Transform;
 use super::utils::pbf::Pbf;
-use super::utils::transformation::Tranformation;
-use super::vector_tile_id::VectorTileID;
+use super::utils::tile_cover::tile_cover;
+use super::vector_tile_id::{OverscaledTileID, UnwrappedTileID, VectorTileID};
 use super::vector_tile_model::VectorTileModel;
 use super::vector_tile_observer::VectorTileObserver;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::string::ToString;
 use std::sync::Arc;
 
 pub struct VectorTileManager {
     vector_tile_manager_impl: Threadable<VectorTileManagerImpl>,
     resource: Resource,
-    zoom: f32,
 }
 
 impl VectorTileManager {
@@ -27,80 +28,152 @@ impl VectorTileManager {
         VectorTileManager {
             vector_tile_manager_impl,
             resource,
-            zoom: config::MAP_DEFAULT_ZOOM_LEVEL,
         }
     }
 
-    pub fn set_zoom(&mut self, zoom: f32) {
-        self.zoom = zoom;
+    pub fn register_source(&mut self, source_id: String) {
+        self.vector_tile_manager_impl
+            .lock()
+            .unwrap()
+            .register_source(source_id);
     }
 
-    pub fn load_covered_tiles(
-        &self,
-        vector_name: String,
-        center_lat_long: &(f32, f32),
-        zoom: f32,
-        screen_size: (u32, u32),
-        url_template: String,
-    ) {
-        let covered_tiles = self.get_covered_tiles(center_lat_long, zoom, &screen_size);
-        for vector_id in covered_tiles.iter() {
-            if !self
-                .vector_tile_manager_impl
-                .lock()
-                .unwrap()
-                .is_tile_loaded(vector_name.clone(), vector_id)
-            {
-                let url = self.get_tile_request_url(&vector_id, url_template.as_ref());
-                println!("-- covered {:?} @ {}", vector_id, url);
+    pub fn unregister_source(&mut self, source_id: &str) {
+        self.vector_tile_manager_impl
+            .lock()
+            .unwrap()
+            .unregister_source(source_id);
+    }
 
-                let responder = self.vector_tile_manager_impl.clone();
-                self.resource.get(&url, responder);
-            }
+    pub fn invalidate_source(&self, source_id: &str) {
+        self.vector_tile_manager_impl
+            .lock()
+            .unwrap()
+            .invalidate_source(source_id);
+    }
+
+    pub fn load_tiles(&self, source_id: String, tile_ids: &[VectorTileID], url_template: String) {
+        // Claims the tiles, so that camera moves while they are in flight
+        // don't request them again
+        let (generation, requested) = {
+            let mut vector_tile_manager_impl = self.vector_tile_manager_impl.lock().unwrap();
+            let generation = match vector_tile_manager_impl.generations.get(&source_id) {
+                Some(generation) => *generation,
+                None => return,
+            };
+            let requested = vector_tile_manager_impl.mark_tiles_requested(&source_id, tile_ids);
+            (generation, requested)
+        };
+        for vector_id in requested.iter() {
+            let url = self.get_tile_request_url(vector_id, url_template.as_ref());
+            println!("-- covered {:?} @ {}", vector_id, url);
+
+            let responder = ThreadableNew(VectorTileRequest {
+                source_id: source_id.clone(),
+                generation,
+                vector_tile_id: *vector_id,
+                vector_tile_manager_impl: self.vector_tile_manager_impl.clone(),
+            });
+            self.resource.get(&url, responder);
         }
     }
 
-    pub fn get_center_point_tile_xy(&self, center_lat_long: (f32, f32), zoom: f32) -> (f32, f32) {
-        Tranformation::latlong_to_tile_coord(
-            center_lat_long.0,
-            center_lat_long.1,
-            zoom.floor() as u32,
-        )
+    pub fn get_loaded_tile(
+        &self,
+        source_id: &str,
+        vector_tile_id: &VectorTileID,
+    ) -> Option<Arc<VectorTileModel>> {
+        self.vector_tile_manager_impl
+            .lock()
+            .unwrap()
+            .tile_cache
+            .get(source_id, vector_tile_id)
     }
 
-    // Stateless function - no threading concerns
-    // TODO: to support rotation later
-    pub fn get_covered_tiles(
+    // Tiles of the source the view draws from, kept out of eviction
+    pub fn set_tiles_in_use(&self, source_id: &str, tile_ids: HashSet<VectorTileID>) {
+        let mut vector_tile_manager_impl = self.vector_tile_manager_impl.lock().unwrap();
+        let evicted = vector_tile_manager_impl
+            .tile_cache
+            .set_in_use(source_id, tile_ids);
+        vector_tile_manager_impl.on_vector_tiles_evicted(evicted);
+    }
+
+    // Bytes of the GPU buffers the painter built for the tile
+    pub fn set_tile_buffer_size(
         &self,
-        center_lat_long: &(f32, f32),
-        _zoom: f32,
-        screen_size: &(u32, u32),
-    ) -> Vec<VectorTileID> {
-        let (tile_x, tile_y) = self.get_center_point_tile_xy(center_lat_long.to_owned(), self.zoom);
+        source_id: &str,
+        vector_tile_id: &VectorTileID,
+        bytes: usize,
+    ) {
+        self.vector_tile_manager_impl
+            .lock()
+            .unwrap()
+            .tile_cache
+            .set_buffer_size(source_id, vector_tile_id, bytes);
+    }
 
-        let delta_tile_x = screen_size.0 as f32 / 2.0 / (TILE_SIZE as f32);
-        let delta_tile_y = screen_size.1 as f32 / 2.0 / (TILE_SIZE as f32);
-        let (min_tile_x_u32, min_tile_y_u32) = (
-            (tile_x - delta_tile_x).floor() as u32,
-            (tile_y - delta_tile_y).floor() as u32,
-        );
-        let (max_tile_x_u32, max_tile_y_u32) = (
-            (tile_x + delta_tile_x).floor() as u32,
-            (tile_y + delta_tile_y).floor() as u32,
-        );
+    pub fn get_tile_cache_limits(&self) -> TileCacheLimits {
+        self.vector_tile_manager_impl
+            .lock()
+            .unwrap()
+            .tile_cache
+            .get_limits()
+    }
+
+    pub fn set_tile_cache_limits(&self, limits: TileCacheLimits) {
+        let mut vector_tile_manager_impl = self.vector_tile_manager_impl.lock().unwrap();
+        let evicted = vector_tile_manager_impl.tile_cache.set_limits(limits);
+        vector_tile_manager_impl.on_vector_tiles_evicted(evicted);
+    }
+
+    pub fn get_tile_cache_metrics(&self) -> TileCacheMetrics {
+        self.vector_tile_manager_impl
+            .lock()
+            .unwrap()
+            .tile_cache
+            .get_metrics()
+    }
 
-        let mut covered_tiles = vec![];
+    // Loaded tiles standing in for `ideal_tiles` of the source
+    pub fn get_render_tiles(
+        &self,
+        source_id: &str,
+        ideal_tiles: &[OverscaledTileID],
+    ) -> Vec<RenderTile> {
+        let vector_tile_manager_impl = self.vector_tile_manager_impl.lock().unwrap();
+        render_tiles(ideal_tiles, |tile_id| {
+            vector_tile_manager_impl.is_tile_loaded(source_id, tile_id)
+        })
+    }
+
+    // Stateless function - no threading concerns
+    // Tiles of zoom `z` under the viewport's footprint on the ground,
+    // closest to the center first
+    pub fn get_covered_tiles(&self, transform: &Transform, z: u32) -> Vec<UnwrappedTileID> {
+        let (width, height) = transform.get_size();
+        let (width, height) = (width as f64, height as f64);
+        let corners = [
+            ScreenCoordinate::new(0.0, 0.0),
+            ScreenCoordinate::new(width, 0.0),
+            ScreenCoordinate::new(width, height),
+            ScreenCoordinate::new(0.0, height),
+        ];
 
-        for y in min_tile_y_u32..=max_tile_y_u32 {
-            for x in min_tile_x_u32..=max_tile_x_u32 {
-                covered_tiles.push(VectorTileID {
-                    x,
-                    y,
-                    z: self.zoom.floor() as u32,
-                });
+        let mut quad = vec![];
+        for corner in corners.iter() {
+            // Pitch is capped well below the horizon, so corners always hit
+            // the ground
+            match transform.screen_to_tile_coordinate(*corner, z) {
+                Some(coord) => quad.push(coord),
+                None => return vec![],
             }
         }
-        covered_tiles
+        tile_cover(
+            &[quad[0], quad[1], quad[2], quad[3]],
+            &transform.center_tile_point().zoom_to(z),
+            transform.world_width(),
+        )
     }
 
     fn get_tile_request_url(&self, tile_id: &VectorTileID, url_template: &str) -> String {
@@ -123,36 +196,144 @@ impl VectorTileManager {
 
 //
 
+// One in-flight tile request. Carries the source and tile it was issued for
+// so that responses never have to be routed by parsing the request URL.
+struct VectorTileRequest {
+    source_id: String,
+    // Registration of the source the request was issued for, a source
+    // replaced under the same id since drops the response
+    generation: u64,
+    vector_tile_id: VectorTileID,
+    vector_tile_manager_impl: Threadable<VectorTileManagerImpl>,
+}
+
+impl TaskResponder for VectorTileRequest {
+    fn on_task_success(&mut self, url: String, data: Option<Vec<u8>>) {
+        println!("Yikes: VectorTile Load Succeeded from {}", &url);
+
+        match data {
+            Some(bytes) => {
+                self.vector_tile_manager_impl
+                    .lock()
+                    .unwrap()
+                    .on_vector_tile_data(
+                        self.source_id.clone(),
+                        self.generation,
+                        self.vector_tile_id,
+                        bytes,
+                    );
+            }
+            None => {
+                println!("Error: empty VectorTile loaded");
+                self.vector_tile_manager_impl
+                    .lock()
+                    .unwrap()
+                    .on_vector_tile_failed(&self.source_id, self.generation, &self.vector_tile_id);
+            }
+        }
+    }
+
+    fn on_task_failure(&self, map_error: MapError) {
+        println!(
+            "Error: VectorTile {:?} of {} Load Failed {}",
+            self.vector_tile_id, self.source_id, map_error
+        );
+        // Lets the next camera move retry it
+        self.vector_tile_manager_impl
+            .lock()
+            .unwrap()
+            .on_vector_tile_failed(&self.source_id, self.generation, &self.vector_tile_id);
+    }
+}
+
+//
+
 struct VectorTileManagerImpl {
-    loaded_tiles: HashMap<String, HashMap<VectorTileID, Arc<VectorTileModel>>>,
+    tile_cache: TileCache,
+    // Requested, but not loaded yet
+    pending_tiles: HashMap<String, HashSet<VectorTileID>>,
+    // Registered sources, each registration numbered anew
+    generations: HashMap<String, u64>,
+    next_generation: u64,
     painter_observer: Option<Threadable<dyn VectorTileObserver>>,
 }
 
 impl VectorTileManagerImpl {
     fn new() -> VectorTileManagerImpl {
-        let mut loaded_tiles = HashMap::new();
-        loaded_tiles.insert("COMPOSITE".to_string(), HashMap::new());
-        loaded_tiles.insert("BUILDINGS".to_string(), HashMap::new());
-        loaded_tiles.insert("INCIDENTS".to_string(), HashMap::new());
-        loaded_tiles.insert("POI".to_string(), HashMap::new());
         VectorTileManagerImpl {
-            loaded_tiles,
+            tile_cache: TileCache::new(TileCacheLimits::default()),
+            pending_tiles: HashMap::new(),
+            generations: HashMap::new(),
+            next_generation: 0,
             painter_observer: None,
         }
     }
 
-    fn is_tile_loaded(&self, vector_name: String, vector_tile_id: &VectorTileID) -> bool {
-        self.loaded_tiles[&vector_name].contains_key(vector_tile_id)
+    fn register_source(&mut self, source_id: String) {
+        self.next_generation += 1;
+        self.generations
+            .insert(source_id.clone(), self.next_generation);
+        self.tile_cache.add_source(source_id);
     }
 
-    fn get_tile_id_from_url(&self, url: &str) -> VectorTileID {
-        let tokens: Vec<&str> = url.rsplit("/").collect();
-        let (x, y, z) = (
-            tokens[2].parse::<u32>().unwrap(),
-            tokens[1].parse::<u32>().unwrap(),
-            tokens[3].parse::<u32>().unwrap(),
-        );
-        VectorTileID { x, y, z }
+    fn unregister_source(&mut self, source_id: &str) {
+        self.tile_cache.remove_source(source_id);
+        self.pending_tiles.remove(source_id);
+        self.generations.remove(source_id);
+
+        if let Some(painter_observer) = &self.painter_observer {
+            painter_observer
+                .lock()
+                .unwrap()
+                .on_vector_source_removed(source_id.to_owned());
+        }
+    }
+
+    fn invalidate_source(&self, source_id: &str) {
+        if let Some(painter_observer) = &self.painter_observer {
+            painter_observer
+                .lock()
+                .unwrap()
+                .on_vector_source_invalidated(source_id.to_owned());
+        }
+    }
+
+    fn is_tile_loaded(&self, source_id: &str, vector_tile_id: &VectorTileID) -> bool {
+        self.tile_cache.contains(source_id, vector_tile_id)
+    }
+
+    // Tiles of the cover to request, those neither loaded nor on their way
+    fn mark_tiles_requested(
+        &mut self,
+        source_id: &str,
+        tile_ids: &[VectorTileID],
+    ) -> Vec<VectorTileID> {
+        let missing = self.tile_cache.request(source_id, tile_ids);
+        let pending = self.pending_tiles.entry(source_id.to_owned()).or_default();
+        missing
+            .into_iter()
+            .filter(|tile_id| pending.insert(*tile_id))
+            .collect()
+    }
+
+    // Whether a response to a request issued for registration `generation`
+    // of the source is still wanted
+    fn is_current(&self, source_id: &str, generation: u64) -> bool {
+        self.generations.get(source_id) == Some(&generation)
+    }
+
+    fn on_vector_tile_failed(
+        &mut self,
+        source_id: &str,
+        generation: u64,
+        vector_tile_id: &VectorTileID,
+    ) {
+        if !self.is_current(source_id, generation) {
+            return;
+        }
+        if let Some(pending) = self.pending_tiles.get_mut(source_id) {
+            pending.remove(vector_tile_id);
+        }
     }
 
     pub fn add_vector_tile_observer(
@@ -162,50 +343,106 @@ impl VectorTileManagerImpl {
         self.painter_observer = Some(vector_tile_obs);
     }
 
-    fn get_vector_tile_name_from_url(&self, url: String) -> String {
-        // TODO: simple assumption: url is ..../COMPOSITE?v=4
-        let segs: Vec<&str> = url.rsplit("/").collect();
-        let with_ver = segs[0].to_string();
-        let tokens: Vec<&str> = with_ver.split_terminator('?').collect();
-        tokens[0].to_string()
-    }
-}
+    fn on_vector_tile_data(
+        &mut self,
+        source_id: String,
+        generation: u64,
+        vector_tile_id: VectorTileID,
+        bytes: Vec<u8>,
+    ) {
+        if !self.is_current(&source_id, generation) {
+            // The source may have been removed or replaced while the request
+            // was in flight.
+            println!("Warning: VectorTile for stale source {} dropped", source_id);
+            return;
+        }
 
-impl TaskResponder for VectorTileManagerImpl {
-    fn on_task_success(&mut self, url: String, data: Option<Vec<u8>>) {
-        println!("Yikes: VectorTile Load Succeeded from {}", &url);
+        if let Some(pending) = self.pending_tiles.get_mut(&source_id) {
+            pending.remove(&vector_tile_id);
+        }
 
-        match data {
-            Some(bytes) => {
-                let mut tile_pbf = Pbf::new(bytes);
-                let mut orig_parsed_tile = VectorTileModel::parse(&mut tile_pbf);
-                orig_parsed_tile.normalize_coords();
-                let parsed_tile = Arc::new(orig_parsed_tile);
-
-                let vector_tile_id = self.get_tile_id_from_url(url.as_ref());
-                println!(" -- Parsed VectorTile: {:?}", &vector_tile_id);
-
-                let vector_name = self.get_vector_tile_name_from_url(url.clone());
-                let named_loaded_tiles = self.loaded_tiles.get_mut(&vector_name).unwrap();
-                named_loaded_tiles.insert(vector_tile_id.clone(), parsed_tile.clone());
-
-                let tile_name = self.get_vector_tile_name_from_url(url);
-                if self.painter_observer.is_some() {
-                    self.painter_observer
-                        .as_ref()
-                        .unwrap()
-                        .lock()
-                        .unwrap()
-                        .on_vector_tile_loaded(tile_name, vector_tile_id, parsed_tile.clone());
-                }
-            }
-            None => {
-                println!("Error: empty VectorTile loaded");
+        let mut tile_pbf = Pbf::new(bytes);
+        let mut orig_parsed_tile = VectorTileModel::parse(&mut tile_pbf);
+        orig_parsed_tile.normalize_coords();
+        let parsed_tile = Arc::new(orig_parsed_tile);
+        println!(" -- Parsed VectorTile: {:?}", &vector_tile_id);
+
+        let evicted = self
+            .tile_cache
+            .insert(&source_id, vector_tile_id, parsed_tile.clone());
+
+        if let Some(painter_observer) = &self.painter_observer {
+            painter_observer.lock().unwrap().on_vector_tile_loaded(
+                source_id,
+                vector_tile_id,
+                parsed_tile,
+            );
+        }
+        self.on_vector_tiles_evicted(evicted);
+    }
+
+    // Lets the painter free the GPU buffers of tiles the cache dropped
+    fn on_vector_tiles_evicted(&self, evicted: Vec<(String, VectorTileID)>) {
+        if evicted.is_empty() {
+            return;
+        }
+        println!(" -- Evicted VectorTiles: {:?}", &evicted);
+        if let Some(painter_observer) = self.painter_observer.as_ref() {
+            let mut painter_observer = painter_observer.lock().unwrap();
+            for (source_id, vector_tile_id) in evicted {
+                painter_observer.on_vector_tile_evicted(source_id, vector_tile_id);
             }
         }
     }
+}
 
-    fn on_task_failure(&self, map_error: MapError) {
-        println!("Error: VectorTile Load Failed {}", map_error);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "streets";
+
+    #[test]
+    fn drops_responses_of_replaced_sources() {
+        let tile_id = VectorTileID::new(1, 2, 3);
+        let mut manager = VectorTileManagerImpl::new();
+        manager.register_source(SOURCE.to_owned());
+        let old_generation = manager.generations[SOURCE];
+        assert_eq!(
+            manager.mark_tiles_requested(SOURCE, &[tile_id]),
+            vec![tile_id]
+        );
+
+        // The style swaps the source for one with another URL under the same id
+        manager.unregister_source(SOURCE);
+        manager.register_source(SOURCE.to_owned());
+        let generation = manager.generations[SOURCE];
+        assert_ne!(generation, old_generation);
+        assert_eq!(
+            manager.mark_tiles_requested(SOURCE, &[tile_id]),
+            vec![tile_id]
+        );
+
+        manager.on_vector_tile_data(SOURCE.to_owned(), old_generation, tile_id, vec![]);
+        assert!(!manager.is_tile_loaded(SOURCE, &tile_id));
+        // Nor does a stale failure free the tile for another request
+        manager.on_vector_tile_failed(SOURCE, old_generation, &tile_id);
+        assert!(manager.mark_tiles_requested(SOURCE, &[tile_id]).is_empty());
+
+        manager.on_vector_tile_data(SOURCE.to_owned(), generation, tile_id, vec![]);
+        assert!(manager.is_tile_loaded(SOURCE, &tile_id));
+    }
+
+    #[test]
+    fn drops_responses_of_removed_sources() {
+        let tile_id = VectorTileID::new(0, 0, 0);
+        let mut manager = VectorTileManagerImpl::new();
+        manager.register_source(SOURCE.to_owned());
+        let generation = manager.generations[SOURCE];
+        manager.mark_tiles_requested(SOURCE, &[tile_id]);
+        manager.unregister_source(SOURCE);
+
+        manager.on_vector_tile_data(SOURCE.to_owned(), generation, tile_id, vec![]);
+        assert!(!manager.is_tile_loaded(SOURCE, &tile_id));
     }
 }
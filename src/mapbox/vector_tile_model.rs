@@ -27,6 +27,7 @@
 
 use super::utils::pbf::Pbf;
 
+use serde_json::Value;
 use std::collections::HashMap;
 
 #[derive(Debug)]
@@ -55,6 +56,31 @@ impl VectorTileModel {
             layer.normalize_coords();
         }
     }
+
+    // Rough bytes held in memory, what tile caches budget with. Geometry
+    // makes up most of it.
+    pub fn estimated_size(&self) -> usize {
+        use std::mem::size_of;
+
+        let mut size = size_of::<VectorTileModel>();
+        for layer in self.layers.iter() {
+            size += size_of::<VectorTileLayer>() + layer.name.len();
+            size += layer.keys.iter().map(|key| key.len()).sum::<usize>();
+            size += layer.values.len() * size_of::<VectorTileValue>();
+            for feature in layer.features.iter() {
+                size += size_of::<VectorTileFeature>();
+                size += feature.tags.len() * 2 * size_of::<u32>();
+                for geometry in feature.geometry.iter() {
+                    size += geometry
+                        .geom_set
+                        .iter()
+                        .map(|points| points.len() * size_of::<(f32, f32)>())
+                        .sum::<usize>();
+                }
+            }
+        }
+        size
+    }
 }
 
 #[derive(Debug)]
@@ -114,6 +140,19 @@ impl VectorTileLayer {
         layer
     }
 
+    // Attributes of a feature of this layer, resolved from the tag indices
+    pub fn get_properties(&self, feature: &VectorTileFeature) -> HashMap<String, Value> {
+        feature
+            .tags
+            .iter()
+            .filter_map(|(key, value)| {
+                let key = self.keys.get(*key as usize)?;
+                let value = self.values.get(*value as usize)?;
+                Some((key.clone(), value.to_value()))
+            })
+            .collect()
+    }
+
     pub fn normalize_coords(&mut self) {
         let extent = self.extent;
         for feature in self.features.iter_mut() {
@@ -181,6 +220,18 @@ impl VectorTileValue {
         } // while
         val
     }
+
+    pub fn to_value(&self) -> Value {
+        match self {
+            VectorTileValue::None => Value::Null,
+            VectorTileValue::StringVal(v) => Value::from(v.as_str()),
+            VectorTileValue::Float32Val(v) => Value::from(*v as f64),
+            VectorTileValue::Float64Val(v) => Value::from(*v),
+            VectorTileValue::Int64Val(v) | VectorTileValue::SInt64Val(v) => Value::from(*v),
+            VectorTileValue::UInt64Val(v) => Value::from(*v),
+            VectorTileValue::BoolVal(v) => Value::from(*v),
+        }
+    }
 }
 
 //
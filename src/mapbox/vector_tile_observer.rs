@@ -10,4 +10,12 @@ pub trait VectorTileObserver: Send {
         vector_tile_id: VectorTileID,
         parsed_vector_tile: Arc<VectorTileModel>,
     );
+
+    // Style of the source changed - parsed tiles stay but anything derived from them is stale
+    fn on_vector_source_invalidated(&mut self, name: String);
+
+    fn on_vector_source_removed(&mut self, name: String);
+
+    // Dropped from the tile cache, anything built from it can go too
+    fn on_vector_tile_evicted(&mut self, name: String, vector_tile_id: VectorTileID);
 }
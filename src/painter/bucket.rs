@@ -18,7 +18,8 @@ gfx_defines! {
     }
 
     constant Transform {
-        screen_ratio: f32 = "u_ScreenRatio",
+        matrix: [[f32; 4]; 4] = "u_Matrix",
+        clip: [f32; 4] = "u_Clip",
     }
 
     pipeline pipe {
@@ -47,21 +48,10 @@ pub struct Bucket {
 
 impl Bucket {
     pub fn new() -> Self {
-        let vector_tiles_map = ThreadableNew(HashMap::new());
-        let renderable_vector_tiles_map = ThreadableNew(HashMap::new());
-        for vector_tile_name in ["COMPOSITE", "BUILDINGS"].iter() {
-            vector_tiles_map
-                .lock()
-                .unwrap()
-                .insert(vector_tile_name.to_string(), HashMap::new());
-            renderable_vector_tiles_map
-                .lock()
-                .unwrap()
-                .insert(vector_tile_name.to_string(), HashMap::new());
-        }
+        // Sources are added lazily as their first tile arrives.
         Bucket {
-            vector_tiles_map,
-            renderable_vector_tiles_map,
+            vector_tiles_map: ThreadableNew(HashMap::new()),
+            renderable_vector_tiles_map: ThreadableNew(HashMap::new()),
         }
     }
 
@@ -104,6 +94,19 @@ impl Bucket {
         }
     }
 
+    // Drops GPU data of a source so that it gets rebuilt from the parsed tiles
+    pub fn invalidate_source(&mut self, vector_name: &str) {
+        self.renderable_vector_tiles_map
+            .lock()
+            .unwrap()
+            .remove(vector_name);
+    }
+
+    pub fn remove_source(&mut self, vector_name: &str) {
+        self.vector_tiles_map.lock().unwrap().remove(vector_name);
+        self.invalidate_source(vector_name);
+    }
+
     pub fn add_vector_tile_data(
         &mut self,
         vector_name: &str,
@@ -111,12 +114,10 @@ impl Bucket {
         parsed_vector_tile: Arc<VectorTileModel>,
     ) {
         let mut vector_tiles_map = self.vector_tiles_map.lock().unwrap();
-        let vector_tile_data = vector_tiles_map.get_mut(vector_name);
-        if vector_tile_data.is_some() {
-            vector_tile_data
-                .unwrap()
-                .insert(vector_tile_id, parsed_vector_tile.clone());
-        }
+        vector_tiles_map
+            .entry(vector_name.to_string())
+            .or_insert_with(HashMap::new)
+            .insert(vector_tile_id, parsed_vector_tile.clone());
     }
 
     pub fn add_renderable_item(
@@ -127,8 +128,8 @@ impl Bucket {
     ) {
         let mut renderable_vector_tiles_map = self.renderable_vector_tiles_map.lock().unwrap();
         let renderable_vector_tile_data = renderable_vector_tiles_map
-            .get_mut(&vector_tile_name)
-            .unwrap();
+            .entry(vector_tile_name)
+            .or_insert_with(HashMap::new);
         if !renderable_vector_tile_data.contains_key(&vector_tile_id) {
             renderable_vector_tile_data.insert(vector_tile_id, ThreadableNew(vec![]));
         }
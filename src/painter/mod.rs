@@ -1,4 +1,4 @@
 pub mod painter;
+pub mod window_input;
 
 mod bucket;
-mod tile_grid;
@@ -10,17 +10,19 @@ use glutin::Api::OpenGl;
 use glutin::{EventsLoop, GlRequest, PossiblyCurrent};
 
 use super::bucket::*;
-use super::tile_grid;
 
 use crate::config;
 use crate::mapbox::common::types::{Threadable, ThreadableNew};
+use crate::mapbox::geo::TileCoordinate;
 use crate::mapbox::map::Map;
+use crate::mapbox::render_tile::{RenderTile, TileClip};
+use crate::mapbox::transform::Transform;
+use crate::mapbox::utils::matrix;
 use crate::mapbox::vector_tile_id::VectorTileID;
 use crate::mapbox::vector_tile_model::VectorTileModel;
 
 use crate::mapbox::vector_tile_observer::VectorTileObserver;
 use std::cell::RefCell;
-use std::collections::HashMap;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
@@ -138,14 +140,14 @@ pub struct Painter {
     // Markers
     need_update: bool,
     show_tile_grid: bool,
+    placement_pending: bool,
     //
     painter_impl: Threadable<PainterImpl>,
     //
     bucket: Threadable<Bucket>,
     //
     text: gfx_text::Renderer<ResourceType, Factory>,
-    //
-    tile_screen_coords: Threadable<HashMap<VectorTileID, (f32, f32, f32, f32)>>,
+    label_text: gfx_text::Renderer<ResourceType, Factory>,
 }
 
 impl Painter {
@@ -157,23 +159,23 @@ impl Painter {
         let text = gfx_text::new(gfx_delegate.factory.clone())
             .with_size(32)
             .unwrap();
+        // TODO: draw labels from the glyph atlas quads instead
+        let label_text = gfx_text::new(gfx_delegate.factory.clone())
+            .with_size(14)
+            .unwrap();
 
-        let tile_screen_coords = ThreadableNew(HashMap::new());
-
-        let mut painter = Painter {
+        let painter = Painter {
             map,
             gfx_delegate,
             need_update: true,
             show_tile_grid: true,
+            placement_pending: true,
             painter_impl: ThreadableNew(PainterImpl::new(bucket.clone())),
             bucket,
             text,
-            tile_screen_coords,
+            label_text,
         };
 
-        // TODO: support panning
-        painter.calc_tile_screenspace_rect();
-
         painter
             .map
             .borrow_mut()
@@ -182,37 +184,6 @@ impl Painter {
         painter
     }
 
-    fn calc_tile_screenspace_rect(&mut self) {
-        let covered_tilex_xy = self.map.borrow().get_covered_tiles_coords();
-
-        for tile_coord in covered_tilex_xy {
-            let (center_tile_x, center_tile_y) = self.map.borrow().get_center_point_tile_xy();
-            let (top_left_x, top_left_y, bottom_right_x, bottom_right_y) =
-                tile_grid::TileGrid::gen_tile_grid_screen_coords(
-                    (center_tile_x, center_tile_y),
-                    (tile_coord.0, tile_coord.1),
-                );
-            self.tile_screen_coords.lock().unwrap().insert(
-                VectorTileID {
-                    x: tile_coord.0 as u32,
-                    y: tile_coord.1 as u32,
-                    z: self.map.borrow().get_zoom() as u32,
-                },
-                (top_left_x, top_left_y, bottom_right_x, bottom_right_y),
-            );
-        }
-
-        self.painter_impl
-            .lock()
-            .unwrap()
-            .set_tile_screen_coords(self.tile_screen_coords.clone());
-    }
-
-    fn get_tile_screenspace_rect(&self, vector_tile_id: VectorTileID) -> (f32, f32, f32, f32) {
-        let tile_screen_coords = self.tile_screen_coords.lock().unwrap();
-        tile_screen_coords.get(&vector_tile_id).unwrap().clone()
-    }
-
     pub fn set_need_update(&mut self, need_update: bool) {
         self.need_update = need_update;
     }
@@ -241,22 +212,13 @@ impl Painter {
         (data, slice)
     }
 
-    fn tuple_vec_to_vertex_array(
-        &self,
-        top_left_screen_coords: (f32, f32),
-        tile_scale_factor: (f32, f32),
-        geom: &Vec<(f32, f32)>,
-    ) -> Vec<VertexType> {
+    // Vertices stay in normalized tile coords, the tile matrix places them
+    fn tuple_vec_to_vertex_array(&self, geom: &[(f32, f32)]) -> Vec<VertexType> {
         let mut arr: Vec<VertexType> = vec![];
 
         for point in geom.iter() {
             arr.push(VertexType {
-                pos: [
-                    top_left_screen_coords.0 + point.0 * tile_scale_factor.0,
-                    top_left_screen_coords.1 - point.1 * tile_scale_factor.1,
-                    0.0,
-                    1.0,
-                ],
+                pos: [point.0, point.1, 0.0, 1.0],
                 color: [0.0, 0.0, 1.0, 1.0], // TODO
             });
         }
@@ -266,82 +228,60 @@ impl Painter {
 
     fn gen_data(
         &mut self,
-        top_left_screen_coords: (f32, f32),
-        tile_scale_factor: (f32, f32),
-        geom: &Vec<(f32, f32)>,
+        geom: &[(f32, f32)],
     ) -> (pipe::Data<ResourceType>, gfx::Slice<ResourceType>) {
-        let arr = self.tuple_vec_to_vertex_array(top_left_screen_coords, tile_scale_factor, geom);
+        let arr = self.tuple_vec_to_vertex_array(geom);
         self.vertex_array_to_data(&arr)
     }
 
-    fn gen_grid_data(
-        &mut self,
-        target_tile_coords: (f32, f32),
-    ) -> (
-        pipe::Data<ResourceType>,
-        gfx::Slice<ResourceType>,
-        (f32, f32),
-    ) {
-        let vector_tile_id = VectorTileID {
-            x: target_tile_coords.0 as u32,
-            y: target_tile_coords.1 as u32,
-            z: self.map.borrow().get_zoom() as u32,
-        };
-        let (top_left_x, top_left_y, bottom_right_x, bottom_right_y) =
-            self.get_tile_screenspace_rect(vector_tile_id);
-
-        let lines: [VertexType; 5] = [
-            VertexType {
-                pos: [top_left_x, top_left_y, 0.0, 1.0],
-                color: [1.0, 0.0, 0.0, 1.0],
-            },
-            VertexType {
-                pos: [bottom_right_x, top_left_y, 0.0, 1.0],
-                color: [1.0, 0.0, 0.0, 1.0],
-            },
-            VertexType {
-                pos: [bottom_right_x, bottom_right_y, 0.0, 1.0],
-                color: [1.0, 0.0, 0.0, 1.0],
-            },
-            VertexType {
-                pos: [top_left_x, bottom_right_y, 0.0, 1.0],
-                color: [1.0, 0.0, 0.0, 1.0],
-            },
-            VertexType {
-                pos: [top_left_x, top_left_y, 0.0, 1.0],
+    // Outline of a tile, in its own coords
+    fn gen_grid_data(&mut self) -> (pipe::Data<ResourceType>, gfx::Slice<ResourceType>) {
+        let corners = [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0), (0.0, 0.0)];
+        let lines: Vec<VertexType> = corners
+            .iter()
+            .map(|(x, y)| VertexType {
+                pos: [*x, *y, 0.0, 1.0],
                 color: [1.0, 0.0, 0.0, 1.0],
-            },
-        ];
-
-        let (vertex_buffer, slice) = self
-            .gfx_delegate
-            .factory
-            .create_vertex_buffer_with_slice(&lines, {});
-
-        let transform_buffer = self.gfx_delegate.factory.create_constant_buffer(1);
-        let data = pipe::Data {
-            vbuf: vertex_buffer,
-            transform: transform_buffer,
-            out: self.gfx_delegate.color_view.clone(),
-        };
+            })
+            .collect();
+        self.vertex_array_to_data(&lines)
+    }
 
-        (data, slice, (top_left_x, top_left_y))
+    fn tile_transform(transform: &Transform, render_tile: &RenderTile) -> TransformType {
+        let clip = render_tile.clip;
+        TransformType {
+            matrix: matrix::to_columns_f32(&transform.tile_matrix(&render_tile.tile_id)),
+            clip: [
+                clip.left as f32,
+                clip.top as f32,
+                clip.right as f32,
+                clip.bottom as f32,
+            ],
+        }
     }
 
     fn render_tile_grid(&mut self) {
-        let covered_tilex_xy = self.map.borrow().get_covered_tiles_coords();
+        let transform = self.map.borrow().get_transform();
+        let covered_tiles = self.map.borrow().get_covered_tiles();
 
         // TODO: we can definitely do smarter line gen and rendering here.
-        for tile_coord in covered_tilex_xy {
-            let (data, slice, top_left_coord) = self.gen_grid_data(tile_coord);
+        for tile_id in covered_tiles {
+            let vector_tile_id = tile_id.canonical;
+            let (data, slice) = self.gen_grid_data();
 
-            //Identity Matrix
-            const TRANSFORM: TransformType = TransformType {
-                screen_ratio: config::GL_VIEWPORT_WIDTH as f32 / config::GL_VIEWPORT_HEIGHT as f32,
-            };
             self.gfx_delegate
                 .encoder
-                .update_buffer(&data.transform, &[TRANSFORM], 0)
+                .update_buffer(
+                    &data.transform,
+                    &[Self::tile_transform(
+                        &transform,
+                        &RenderTile {
+                            tile_id,
+                            clip: TileClip::full(),
+                        },
+                    )],
+                    0,
+                )
                 .unwrap_or_default();
 
             self.gfx_delegate
@@ -349,21 +289,20 @@ impl Painter {
                 .draw(&slice, &self.gfx_delegate.grid_pso, &data);
 
             // Render Text
-            let (text_x, text_y) = (
-                config::GL_VIEWPORT_WIDTH as f32 * (1.0 + top_left_coord.0),
-                config::GL_VIEWPORT_HEIGHT as f32 * (1.0 - top_left_coord.1),
-            );
+            let corner = transform.tile_coordinate_to_screen(&TileCoordinate::new(
+                tile_id.unwrapped_x(transform.world_width()) as f64,
+                vector_tile_id.y as f64,
+                vector_tile_id.z,
+            ));
 
             let tile_coord_text = format!(
                 "{}/{}/{}",
-                self.map.borrow().get_zoom(),
-                tile_coord.0 as u32,
-                tile_coord.1 as u32,
+                vector_tile_id.z, vector_tile_id.x, vector_tile_id.y,
             );
             // TODO: why gfx_text::Renderer doesn't support clear()?
             self.text.add(
                 &tile_coord_text,
-                [text_x as i32 + 5, text_y as i32 + 5],
+                [corner.x as i32 + 5, corner.y as i32 + 5],
                 [1.0, 0.0, 0.0, 1.0],
             );
 
@@ -385,23 +324,22 @@ impl Painter {
             .lock()
             .unwrap()
             .clone();
-        let loaded_vector_tiles = vector_tiles_map.get(&vector_tile_name).unwrap();
-
-        let covered_tilex_xy = self.map.borrow().get_covered_tiles_coords();
-        for tile_coord in covered_tilex_xy {
-            let vector_tile_id = VectorTileID {
-                x: tile_coord.0 as u32,
-                y: tile_coord.1 as u32,
-                z: self.map.borrow().get_zoom() as u32,
-            };
+        let loaded_vector_tiles = match vector_tiles_map.get(&vector_tile_name) {
+            Some(loaded_vector_tiles) => loaded_vector_tiles,
+            None => return, // no tile of this source has arrived yet
+        };
 
+        let transform = self.map.borrow().get_transform();
+        // Past the source's deepest zoom its tiles are stretched over the view,
+        // and tiles still loading are stood in for by their parents or
+        // children. The shader clips each to the area it stands in for.
+        let render_tiles = self.map.borrow().get_render_tiles(&vector_tile_name);
+        for render_tile in render_tiles {
+            let vector_tile_id = render_tile.tile_id.canonical;
             if !loaded_vector_tiles.contains_key(&vector_tile_id) {
                 continue;
             }
 
-            let (top_left_x, top_left_y, _bottom_right_x, _bottom_right_y) =
-                self.get_tile_screenspace_rect(vector_tile_id);
-
             // Render
             let vector_tile_model = loaded_vector_tiles.get(&vector_tile_id).unwrap();
 
@@ -413,9 +351,6 @@ impl Painter {
             if !renderable {
                 // TODO: MAJOR: merge point and line data into one drawcall.
 
-                let tile_dim_screen_ratio =
-                    tile_grid::TileGrid::get_normalized_tile_dim_screen_ratio();
-
                 let mut point_vertices: Vec<VertexType> = vec![];
                 let mut linestrip_vertices: Vec<VertexType> = vec![];
 
@@ -435,11 +370,7 @@ impl Painter {
                                         //       after all these polygons are triangulated and then we render it
                                         //       in TriangleList mode.
                                         // Polygon
-                                        let (render_data, render_slice) = self.gen_data(
-                                            (top_left_x, top_left_y),
-                                            tile_dim_screen_ratio,
-                                            geom,
-                                        );
+                                        let (render_data, render_slice) = self.gen_data(geom);
 
                                         self.bucket.lock().unwrap().add_renderable_item(
                                             vector_tile_name.clone(),
@@ -453,21 +384,13 @@ impl Painter {
                                     }
                                     1 => {
                                         // points
-                                        let mut points = self.tuple_vec_to_vertex_array(
-                                            (top_left_x, top_left_y),
-                                            tile_dim_screen_ratio,
-                                            geom,
-                                        );
+                                        let mut points = self.tuple_vec_to_vertex_array(geom);
                                         point_vertices.append(&mut points);
                                     }
                                     2 => {
                                         // lines
                                         // Ah here is the trick
-                                        let mut lines = self.tuple_vec_to_vertex_array(
-                                            (top_left_x, top_left_y),
-                                            tile_dim_screen_ratio,
-                                            geom,
-                                        );
+                                        let mut lines = self.tuple_vec_to_vertex_array(geom);
 
                                         if !linestrip_vertices.is_empty() && !lines.is_empty() {
                                             let mut last =
@@ -518,6 +441,26 @@ impl Painter {
                         },
                     );
                 }
+
+                // Counts towards the tile cache's byte limit
+                let buffer_size: usize = self
+                    .bucket
+                    .lock()
+                    .unwrap()
+                    .get_renderable_items(vector_tile_name.clone(), vector_tile_id)
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .map(|item| {
+                        (item.slice.end - item.slice.start) as usize
+                            * std::mem::size_of::<VertexType>()
+                    })
+                    .sum();
+                self.map.borrow().set_tile_buffer_size(
+                    &vector_tile_name,
+                    &vector_tile_id,
+                    buffer_size,
+                );
             } // if !renderable
 
             let renderable_items = self
@@ -525,15 +468,11 @@ impl Painter {
                 .lock()
                 .unwrap()
                 .get_renderable_items(vector_tile_name.clone(), vector_tile_id.clone());
+            let tile_transform = Self::tile_transform(&transform, &render_tile);
             for renderable_item in renderable_items.lock().unwrap().iter() {
-                //Identity Matrix
-                const TRANSFORM: TransformType = TransformType {
-                    screen_ratio: config::GL_VIEWPORT_WIDTH as f32
-                        / config::GL_VIEWPORT_HEIGHT as f32,
-                };
                 self.gfx_delegate
                     .encoder
-                    .update_buffer(&renderable_item.data.transform, &[TRANSFORM], 0)
+                    .update_buffer(&renderable_item.data.transform, &[tile_transform], 0)
                     .unwrap_or_default();
 
                 match renderable_item.geometry_type {
@@ -568,17 +507,51 @@ impl Painter {
         self.painter_impl.lock().unwrap().set_dirty(false);
     }
 
+    fn render_labels(&mut self) {
+        let map = self.map.borrow();
+        for placed_symbol in map.get_placed_symbols().iter() {
+            let text = match placed_symbol.text.as_ref() {
+                Some(text) => text,
+                None => continue,
+            };
+            self.label_text.add_anchored(
+                text,
+                [placed_symbol.anchor.0 as i32, placed_symbol.anchor.1 as i32],
+                gfx_text::HorizontalAnchor::Center,
+                gfx_text::VerticalAnchor::Center,
+                [0.2, 0.2, 0.2, 1.0],
+            );
+        }
+
+        self.label_text
+            .draw(
+                &mut self.gfx_delegate.encoder,
+                &self.gfx_delegate.color_view,
+            )
+            .unwrap_or_default();
+    }
+
     pub fn render(&mut self) {
         if !self.need_update && !self.painter_impl.lock().unwrap().is_dirty() {
             return;
         }
+        // Animations move the camera, placement follows
+        self.map.borrow_mut().update_transitions();
+        if self.placement_pending || self.painter_impl.lock().unwrap().is_dirty() {
+            // New tiles may have labels to place
+            self.placement_pending = !self.map.borrow_mut().update_placement();
+        }
         self.gfx_delegate
             .encoder
             .clear(&self.gfx_delegate.color_view, SEA);
 
         // Render Map Data
-        self.render_vector_tile("COMPOSITE".to_string());
-        self.render_vector_tile("BUILDINGS".to_string());
+        let source_ids = self.map.borrow().get_source_ids();
+        for source_id in source_ids {
+            self.render_vector_tile(source_id);
+        }
+
+        self.render_labels();
 
         // Render Tile Grids
         if self.show_tile_grid {
@@ -597,7 +570,6 @@ impl Painter {
 struct PainterImpl {
     dirty: AtomicBool,
     bucket: Threadable<Bucket>,
-    tile_screen_coords: Threadable<HashMap<VectorTileID, (f32, f32, f32, f32)>>,
 }
 
 impl PainterImpl {
@@ -605,7 +577,6 @@ impl PainterImpl {
         PainterImpl {
             dirty: AtomicBool::new(true),
             bucket,
-            tile_screen_coords: ThreadableNew(HashMap::new()),
         }
     }
 
@@ -616,13 +587,6 @@ impl PainterImpl {
     pub fn set_dirty(&mut self, dirty: bool) {
         self.dirty.store(dirty, Ordering::Relaxed);
     }
-
-    pub fn set_tile_screen_coords(
-        &mut self,
-        tile_screen_coords: Threadable<HashMap<VectorTileID, (f32, f32, f32, f32)>>,
-    ) {
-        self.tile_screen_coords = tile_screen_coords;
-    }
 }
 
 impl VectorTileObserver for PainterImpl {
@@ -645,4 +609,19 @@ impl VectorTileObserver for PainterImpl {
 
         self.dirty.store(true, Ordering::Relaxed)
     }
+    fn on_vector_source_invalidated(&mut self, name: String) {
+        self.bucket.lock().unwrap().invalidate_source(&name);
+        self.dirty.store(true, Ordering::Relaxed)
+    }
+
+    fn on_vector_source_removed(&mut self, name: String) {
+        self.bucket.lock().unwrap().remove_source(&name);
+        self.dirty.store(true, Ordering::Relaxed)
+    }
+
+    // Dropping the renderable items frees their GPU buffers
+    fn on_vector_tile_evicted(&mut self, name: String, vector_tile_id: VectorTileID) {
+        self.bucket.lock().unwrap().remove(&name, vector_tile_id);
+        self.dirty.store(true, Ordering::Relaxed)
+    }
 }
@@ -0,0 +1,77 @@
+// Window events in the terms of the map's InputHandler. Events it has no use
+// for come out as None.
+use glutin::{ElementState, KeyboardInput, MouseScrollDelta, VirtualKeyCode, WindowEvent};
+
+use mapbox_gl_native_rs::mapbox::geo::ScreenCoordinate;
+use mapbox_gl_native_rs::mapbox::input_handler::{
+    InputEvent, Key, MouseButton, ScrollDelta, TouchPhase,
+};
+
+pub fn to_input_event(event: &WindowEvent) -> Option<InputEvent> {
+    let input_event = match event {
+        WindowEvent::CursorMoved { position, .. } => {
+            InputEvent::CursorMoved(ScreenCoordinate::new(position.x, position.y))
+        }
+        WindowEvent::CursorLeft { .. } => InputEvent::CursorLeft,
+        WindowEvent::MouseInput {
+            state,
+            button,
+            modifiers,
+            ..
+        } => {
+            let button = match button {
+                glutin::MouseButton::Left => MouseButton::Left,
+                glutin::MouseButton::Right => MouseButton::Right,
+                glutin::MouseButton::Middle => MouseButton::Middle,
+                glutin::MouseButton::Other(_) => MouseButton::Other,
+            };
+            match state {
+                ElementState::Pressed => InputEvent::MousePressed {
+                    button,
+                    shift: modifiers.shift,
+                },
+                ElementState::Released => InputEvent::MouseReleased(button),
+            }
+        }
+        WindowEvent::MouseWheel { delta, .. } => InputEvent::Scroll(match delta {
+            MouseScrollDelta::LineDelta(_, y) => ScrollDelta::Lines(*y as f64),
+            MouseScrollDelta::PixelDelta(position) => ScrollDelta::Pixels(position.y),
+        }),
+        WindowEvent::KeyboardInput {
+            input:
+                KeyboardInput {
+                    state: ElementState::Pressed,
+                    virtual_keycode: Some(key),
+                    modifiers,
+                    ..
+                },
+            ..
+        } => {
+            let key = match key {
+                VirtualKeyCode::Left => Key::Left,
+                VirtualKeyCode::Right => Key::Right,
+                VirtualKeyCode::Up => Key::Up,
+                VirtualKeyCode::Down => Key::Down,
+                VirtualKeyCode::Equals | VirtualKeyCode::Add => Key::Plus,
+                VirtualKeyCode::Minus | VirtualKeyCode::Subtract => Key::Minus,
+                _ => return None,
+            };
+            InputEvent::KeyPressed {
+                key,
+                shift: modifiers.shift,
+            }
+        }
+        WindowEvent::Touch(touch) => InputEvent::Touch {
+            id: touch.id,
+            phase: match touch.phase {
+                glutin::TouchPhase::Started => TouchPhase::Started,
+                glutin::TouchPhase::Moved => TouchPhase::Moved,
+                glutin::TouchPhase::Ended => TouchPhase::Ended,
+                glutin::TouchPhase::Cancelled => TouchPhase::Cancelled,
+            },
+            position: ScreenCoordinate::new(touch.location.x, touch.location.y),
+        },
+        _ => return None,
+    };
+    Some(input_event)
+}